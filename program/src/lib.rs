@@ -11,12 +11,16 @@ use solana_program::{
 // Define a single program entrypoint - THE ONLY ENTRYPOINT IN THE CODEBASE
 entrypoint!(process_instruction);
 
+/// Version of the deployed program build
+pub const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 // Include all modules that make up the raffle contract
 pub mod raffle_state;
 pub mod raffle_instruction;
 pub mod raffle_error;
 pub mod vrf;
 pub mod utils;
+pub mod raffle_core;
 pub mod raffle_processor;
 
 // Process instruction just delegates to the Processor's process method
@@ -0,0 +1,314 @@
+//! Pure raffle math shared with off-chain verifiers and WASM frontends
+//!
+//! Nothing in this module depends on `solana_program` or `std`, so it can be
+//! copied into (or compiled for) `no_std` targets unchanged. On-chain callers
+//! use the wrappers in `utils` and `vrf`, which adapt errors to `ProgramError`.
+
+/// Basis points in 100%
+pub const BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+
+/// Which side keeps the remainder when a fee doesn't divide evenly
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoundingPolicy {
+    /// Round fees down, the prize pool keeps the remainder
+    FeeDown,
+    /// Round fees up, the platform keeps the remainder
+    FeeUp,
+}
+
+/// Rounding policy used for every fee and prize split in the program.
+/// Fees round down so buyers never pay more than the advertised rate.
+pub const FEE_ROUNDING: RoundingPolicy = RoundingPolicy::FeeDown;
+
+/// Calculate the fee taken from `amount` at `basis_points` using `FEE_ROUNDING`
+pub fn calculate_fee(amount: u64, basis_points: u16) -> Result<u64, &'static str> {
+    calculate_fee_with_rounding(amount, basis_points, FEE_ROUNDING)
+}
+
+/// Calculate the fee taken from `amount` at `basis_points` with an explicit rounding policy
+pub fn calculate_fee_with_rounding(
+    amount: u64,
+    basis_points: u16,
+    rounding: RoundingPolicy,
+) -> Result<u64, &'static str> {
+    if basis_points as u64 > BASIS_POINTS_DENOMINATOR {
+        return Err("Fee basis points exceed 100%");
+    }
+    let numerator = (amount as u128) * (basis_points as u128);
+    let denominator = BASIS_POINTS_DENOMINATOR as u128;
+    let fee = match rounding {
+        RoundingPolicy::FeeDown => numerator / denominator,
+        RoundingPolicy::FeeUp => (numerator + denominator - 1) / denominator,
+    };
+    // With basis points capped at 100% the fee can never exceed the amount
+    Ok(fee as u64)
+}
+
+/// Split `amount` into (pool, fee) so that `pool + fee == amount` exactly
+pub fn split_fee(amount: u64, basis_points: u16) -> Result<(u64, u64), &'static str> {
+    let fee = calculate_fee(amount, basis_points)?;
+    Ok((amount - fee, fee))
+}
+
+/// Convert VRF random bytes into a ticket index between 0 and (total_tickets - 1)
+///
+/// The first 8 bytes of the VRF result are read as a little-endian u64.
+pub fn winner_index(vrf_result: &[u8; 32], total_tickets: u64) -> Result<u64, &'static str> {
+    if total_tickets == 0 {
+        return Err("No tickets were sold");
+    }
+
+    let mut random_bytes = [0u8; 8];
+    random_bytes.copy_from_slice(&vrf_result[0..8]);
+    let random_value = u64::from_le_bytes(random_bytes);
+
+    Ok(random_value % total_tickets)
+}
+
+/// Number of independent 8-byte entropy windows in a VRF result
+pub const ENTROPY_WINDOWS: usize = 4;
+
+/// Position of the record whose cumulative ticket range contains `ticket_index`
+///
+/// Records are taken in the order given, which on-chain is ascending pubkey order.
+pub fn record_for_ticket(ticket_counts: &[u64], ticket_index: u64) -> Option<usize> {
+    let mut range_end = 0u64;
+    ticket_counts.iter().position(|&ticket_count| {
+        range_end = range_end.saturating_add(ticket_count);
+        ticket_index < range_end
+    })
+}
+
+/// Draw the `draw_number`-th distinct winning record (0 for the main winner)
+///
+/// Draw k reads entropy window k (bytes 8k..8k+8, wrapping after `ENTROPY_WINDOWS`) as a
+/// little-endian u64 and reduces it modulo the tickets still in play, i.e. the tickets sold
+/// minus those held by the records in `excluded` (earlier winners). The result is mapped onto
+/// the ranges of the remaining records only, so every remaining ticket is equally likely and
+/// a buyer wins at most once no matter how many tickets they hold. Draw 0 with nothing
+/// excluded matches `winner_index`.
+pub fn draw_distinct_record(
+    vrf_result: &[u8; 32],
+    ticket_counts: &[u64],
+    excluded: &[usize],
+    draw_number: usize,
+) -> Result<usize, &'static str> {
+    let mut total_tickets = 0u64;
+    let mut eligible_tickets = 0u64;
+    for (position, &ticket_count) in ticket_counts.iter().enumerate() {
+        total_tickets = total_tickets.checked_add(ticket_count).ok_or("Ticket count overflow")?;
+        if !excluded.contains(&position) {
+            eligible_tickets += ticket_count;
+        }
+    }
+    if total_tickets == 0 {
+        return Err("No tickets were sold");
+    }
+    if eligible_tickets == 0 {
+        return Err("Every record has already won");
+    }
+
+    let window = draw_number % ENTROPY_WINDOWS;
+    let mut random_bytes = [0u8; 8];
+    random_bytes.copy_from_slice(&vrf_result[window * 8..window * 8 + 8]);
+    let ticket_index = u64::from_le_bytes(random_bytes) % eligible_tickets;
+
+    // Walk only the records still in play; excluded ranges are skipped, not redrawn into
+    let mut range_end = 0u64;
+    ticket_counts
+        .iter()
+        .enumerate()
+        .filter(|(position, _)| !excluded.contains(position))
+        .find(|(_, &ticket_count)| {
+            range_end += ticket_count;
+            ticket_index < range_end
+        })
+        .map(|(position, _)| position)
+        .ok_or("Ticket index out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// VRF result whose first entropy window reads as `value`
+    fn vrf_with_window(window: usize, value: u64) -> [u8; 32] {
+        let mut vrf_result = [0u8; 32];
+        vrf_result[window * 8..window * 8 + 8].copy_from_slice(&value.to_le_bytes());
+        vrf_result
+    }
+
+    #[test]
+    fn split_fee_rounds_fee_down() {
+        assert_eq!(split_fee(1_000, 250), Ok((975, 25)));
+        assert_eq!(split_fee(999, 250), Ok((975, 24)));
+        assert_eq!(split_fee(1, 9_999), Ok((1, 0)));
+        assert_eq!(split_fee(u64::MAX, 10_000), Ok((0, u64::MAX)));
+        assert_eq!(split_fee(12_345, 0), Ok((12_345, 0)));
+    }
+
+    #[test]
+    fn split_fee_rejects_more_than_100_percent() {
+        assert!(split_fee(1_000, 10_001).is_err());
+        assert!(calculate_fee_with_rounding(1_000, u16::MAX, RoundingPolicy::FeeUp).is_err());
+    }
+
+    #[test]
+    fn fee_up_keeps_the_remainder() {
+        assert_eq!(calculate_fee_with_rounding(999, 250, RoundingPolicy::FeeUp), Ok(25));
+        assert_eq!(calculate_fee_with_rounding(1_000, 250, RoundingPolicy::FeeUp), Ok(25));
+        assert_eq!(calculate_fee_with_rounding(0, 250, RoundingPolicy::FeeUp), Ok(0));
+    }
+
+    /// Deterministic xorshift64 so randomized tests replay identically
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn splits_conserve_lamports_for_random_amounts() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        let adversarial = [0, 1, 9_999, 10_000, 10_001, u64::MAX - 1, u64::MAX];
+        for round in 0..20_000 {
+            let amount = match round % 4 {
+                0 => adversarial[round % adversarial.len()],
+                1 => next_random(&mut state) % 100_000,
+                _ => next_random(&mut state),
+            };
+            let basis_points = (next_random(&mut state) % (BASIS_POINTS_DENOMINATOR + 1)) as u16;
+
+            let (pool, fee) = split_fee(amount, basis_points).unwrap();
+            assert_eq!(pool as u128 + fee as u128, amount as u128, "amount={} bps={}", amount, basis_points);
+
+            let fee_down = calculate_fee_with_rounding(amount, basis_points, RoundingPolicy::FeeDown).unwrap();
+            let fee_up = calculate_fee_with_rounding(amount, basis_points, RoundingPolicy::FeeUp).unwrap();
+            assert!(fee_up <= amount);
+            assert!(fee_up - fee_down <= 1);
+        }
+    }
+
+    #[test]
+    fn winner_index_reduces_first_window() {
+        assert_eq!(winner_index(&vrf_with_window(0, 41), 10), Ok(1));
+        assert_eq!(winner_index(&vrf_with_window(0, u64::MAX), u64::MAX), Ok(0));
+        // Only the first 8 bytes take part in the main draw
+        assert_eq!(winner_index(&vrf_with_window(1, 41), 10), Ok(0));
+        assert!(winner_index(&[0u8; 32], 0).is_err());
+    }
+
+    fn from_hex(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    /// The published vectors in README.md, "Winner Selection Test Vectors"
+    #[test]
+    fn winner_index_matches_published_vectors() {
+        let vectors: [(&str, u64, u64); 11] = [
+        ("0000000000000000000000000000000000000000000000000000000000000000", 1, 0),
+        ("0100000000000000000000000000000000000000000000000000000000000000", 2, 1),
+        ("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f", 3, 1),
+        ("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff", 7, 1),
+        ("22ee5478ec302c07da803e86b3a67db65a6afcf8422369525afc3961d7c25d67", 10, 6),
+        ("7cde29ea6cde5e1008b08624a3c6bbe8ec0eb2d07dcaefe1852395d0eecd7649", 100, 56),
+        ("391790355538ede380cce0726d47ed1b98f15369865cbf1be7e495f27165af3a", 1000, 137),
+        ("740bf37078e092a091e9f5f7a9ab6fda9bf125f7699d08ae40baed4199e09f63", 65537, 55912),
+        ("6e6ed98558c9903aff6261596da3552ac66ce4c4428cbcfc4eda7bb301759aca", 1000000, 79086),
+        ("091dce561e6812003e066e173d5b0c92152b5a688dc70347a26fd07eb09bacc4", 4294967311, 1438253895),
+        ("ac08abdc6fe8998279d1fbce04af94945c701c20b341837959d3a73bfc14a12a", 9223372036854775809, 187436426653796523),
+        ];
+        for (vrf_hex, tickets_sold, expected) in vectors {
+            assert_eq!(winner_index(&from_hex(vrf_hex), tickets_sold), Ok(expected), "vrf={}", vrf_hex);
+        }
+    }
+
+    #[test]
+    fn record_for_ticket_maps_range_boundaries() {
+        let ticket_counts = [3, 1, 0, 5];
+        assert_eq!(record_for_ticket(&ticket_counts, 0), Some(0));
+        assert_eq!(record_for_ticket(&ticket_counts, 2), Some(0));
+        assert_eq!(record_for_ticket(&ticket_counts, 3), Some(1));
+        // An empty record never owns a ticket
+        assert_eq!(record_for_ticket(&ticket_counts, 4), Some(3));
+        assert_eq!(record_for_ticket(&ticket_counts, 8), Some(3));
+        assert_eq!(record_for_ticket(&ticket_counts, 9), None);
+        assert_eq!(record_for_ticket(&[], 0), None);
+    }
+
+    #[test]
+    fn draw_zero_matches_winner_index() {
+        let ticket_counts = [4, 2, 7];
+        for value in 0..26u64 {
+            let vrf_result = vrf_with_window(0, value);
+            let index = winner_index(&vrf_result, 13).unwrap();
+            assert_eq!(
+                draw_distinct_record(&vrf_result, &ticket_counts, &[], 0),
+                Ok(record_for_ticket(&ticket_counts, index).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn draw_never_returns_an_excluded_record() {
+        let ticket_counts = [5, 1, 3, 1];
+        for value in 0..40u64 {
+            for draw_number in 1..3 {
+                let vrf_result = vrf_with_window(draw_number, value);
+                let excluded = [0, 2];
+                let position = draw_distinct_record(&vrf_result, &ticket_counts, &excluded, draw_number).unwrap();
+                assert!(!excluded.contains(&position));
+                assert!(position < ticket_counts.len());
+            }
+        }
+    }
+
+    #[test]
+    fn draw_is_uniform_over_remaining_tickets() {
+        // One buyer holds 80 of 100 tickets; once they win, the other four split the rest evenly
+        let ticket_counts = [80, 5, 5, 5, 5];
+        for draw_number in 1..3 {
+            let mut wins = [0u64; 5];
+            for value in 0..20u64 {
+                let vrf_result = vrf_with_window(draw_number, value);
+                wins[draw_distinct_record(&vrf_result, &ticket_counts, &[0], draw_number).unwrap()] += 1;
+            }
+            assert_eq!(wins, [0, 5, 5, 5, 5]);
+        }
+
+        // Third draw with two earlier winners: only records 2..5 remain, 15 tickets in play
+        let mut wins = [0u64; 5];
+        for value in 0..15u64 {
+            let vrf_result = vrf_with_window(2, value);
+            wins[draw_distinct_record(&vrf_result, &ticket_counts, &[0, 1], 2).unwrap()] += 1;
+        }
+        assert_eq!(wins, [0, 0, 5, 5, 5]);
+    }
+
+    #[test]
+    fn draw_uses_its_own_window() {
+        let ticket_counts = [1, 1, 1, 1];
+        let mut vrf_result = [0u8; 32];
+        for window in 0..ENTROPY_WINDOWS {
+            vrf_result[window * 8] = window as u8;
+        }
+        assert_eq!(draw_distinct_record(&vrf_result, &ticket_counts, &[], 0), Ok(0));
+        assert_eq!(draw_distinct_record(&vrf_result, &ticket_counts, &[], 3), Ok(3));
+        // Draws past the last window wrap around instead of reading out of bounds
+        assert_eq!(draw_distinct_record(&vrf_result, &ticket_counts, &[], 5), Ok(1));
+    }
+
+    #[test]
+    fn draw_rejects_exhausted_records() {
+        assert!(draw_distinct_record(&[0u8; 32], &[], &[], 0).is_err());
+        assert!(draw_distinct_record(&[0u8; 32], &[0, 0], &[], 0).is_err());
+        assert!(draw_distinct_record(&[0u8; 32], &[2, 3], &[0, 1], 1).is_err());
+        assert!(draw_distinct_record(&[0u8; 32], &[2, 0], &[0], 1).is_err());
+        assert!(draw_distinct_record(&[0u8; 32], &[u64::MAX, 1], &[], 0).is_err());
+    }
+}
@@ -37,6 +37,46 @@ pub enum RaffleError {
     /// Ticket purchase does not match
     #[error("Ticket purchase does not match raffle or purchaser")]
     TicketPurchaseMismatch,
+
+    /// Raffle title is not valid
+    #[error("Raffle title is not valid UTF-8 or is longer than the limit")]
+    InvalidTitle,
+
+    /// Raffle has reached its maximum number of participants
+    #[error("Raffle participant cap reached")]
+    ParticipantCapReached,
+
+    /// Wallet is blacklisted from participating
+    #[error("Wallet is blacklisted")]
+    WalletBlacklisted,
+
+    /// Provided ticket records don't account for every ticket sold
+    #[error("Ticket records do not match tickets sold")]
+    TicketAccountingMismatch,
+
+    /// Raffle has been frozen by its authority or the admin
+    #[error("Raffle is frozen")]
+    RaffleFrozen,
+
+    /// Completion was attempted before randomness was requested
+    #[error("VRF request has not been initiated")]
+    VrfNotRequested,
+
+    /// Completion was attempted in or before the slot randomness was requested
+    #[error("Raffle cannot be completed in the slot randomness was requested")]
+    CompletedInRequestSlot,
+
+    /// Authority already has the maximum number of open raffles
+    #[error("Authority has too many active raffles")]
+    TooManyActiveRaffles,
+
+    /// Prize pool is below the raffle's minimum for a draw
+    #[error("Prize pool is below the raffle minimum")]
+    PoolBelowMinimum,
+
+    /// Purchaser's balance is below the raffle's minimum buyer balance
+    #[error("Buyer balance is below the raffle minimum")]
+    BuyerBelowMinBalance,
 }
 
 impl From<RaffleError> for ProgramError {
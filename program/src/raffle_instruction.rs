@@ -5,9 +5,111 @@ use solana_program::{
     system_program,
     sysvar::clock,
 };
+use crate::raffle_state::MAX_ALLOWED_DURATIONS;
+use crate::utils::{
+    find_authority_state_address, find_blacklist_address, find_index_pointer_pda, find_referral_address,
+    find_vault_address,
+};
 use std::convert::TryInto;
 use std::mem::size_of;
 
+/// Version of the instruction layout, bumped whenever instruction data changes shape
+pub const INSTRUCTION_SCHEMA_VERSION: u8 = 7;
+
+/// Serialized size of instructions that carry no fields (tag only)
+pub const EMPTY_INSTRUCTION_LEN: usize = 1;
+/// Serialized size of InitializeConfig: tag, ticket_price, fee_basis_points
+pub const INITIALIZE_CONFIG_LEN: usize = 1 + 8 + 2;
+/// Serialized size of InitializeRaffle: tag, title, duration, nonce, max_tickets,
+/// fee_override_bps, max_participants, fixed_prize_lamports, draw_on_sellout,
+/// soft_cap_tickets, min_unique_participants, claim_window, purchase_grace_seconds,
+/// consolation_bps, creator_bond, payout_installments, payout_interval, charity, charity_bps,
+/// min_pool_lamports, max_prize_lamports, max_total_fee_lamports, min_buyer_balance
+pub const INITIALIZE_RAFFLE_LEN: usize = 1 + 32 + 8 + 8 + 8 + 2 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 2 + 8 + 1 + 8 + 32 + 2 + 8 + 8 + 8 + 8;
+/// Serialized size of PurchaseTickets: tag, ticket_count, expected_ticket_price, referrer
+pub const PURCHASE_TICKETS_LEN: usize = 1 + 8 + 8 + 32;
+/// Serialized size of UpdateTicketPrice: tag, new_ticket_price
+pub const UPDATE_TICKET_PRICE_LEN: usize = 1 + 8;
+/// Serialized size of UpdateFeePercentage: tag, new_fee_basis_points
+pub const UPDATE_FEE_PERCENTAGE_LEN: usize = 1 + 2;
+/// Serialized size of IncreaseMaxTickets: tag, new_max
+pub const INCREASE_MAX_TICKETS_LEN: usize = 1 + 8;
+/// Serialized size of SetBlacklist: tag, add, pubkey
+pub const SET_BLACKLIST_LEN: usize = 1 + 1 + 32;
+/// Serialized size of FreezeRaffle: tag, frozen
+pub const FREEZE_RAFFLE_LEN: usize = 1 + 1;
+/// Serialized size of SimulatePurchase: tag, ticket_count
+pub const SIMULATE_PURCHASE_LEN: usize = 1 + 8;
+/// Serialized size of SetPermissionlessCompletion: tag, enabled
+pub const SET_PERMISSIONLESS_COMPLETION_LEN: usize = 1 + 1;
+/// Serialized size of SetAllowedDurations: tag, durations
+pub const SET_ALLOWED_DURATIONS_LEN: usize = 1 + 8 * MAX_ALLOWED_DURATIONS;
+/// Serialized size of SetBulkFee: tag, bulk_threshold, bulk_fee_bps
+pub const SET_BULK_FEE_LEN: usize = 1 + 8 + 2;
+/// Serialized size of DonateToPool: tag, amount
+pub const DONATE_TO_POOL_LEN: usize = 1 + 8;
+/// Serialized size of SetDrawDelay: tag, draw_delay
+pub const SET_DRAW_DELAY_LEN: usize = 1 + 8;
+/// Serialized size of SetKeeperReward: tag, keeper_reward_bps
+pub const SET_KEEPER_REWARD_LEN: usize = 1 + 2;
+/// Serialized size of SetReferralReward: tag, referral_bps
+pub const SET_REFERRAL_REWARD_LEN: usize = 1 + 2;
+/// Serialized size of SetMaxActiveRaffles: tag, max_active_raffles
+pub const SET_MAX_ACTIVE_RAFFLES_LEN: usize = 1 + 8;
+/// Serialized size of SetRequestReward: tag, request_reward_bps
+pub const SET_REQUEST_REWARD_LEN: usize = 1 + 2;
+/// Serialized size of UpdateRaffleTitle: tag, title
+pub const UPDATE_RAFFLE_TITLE_LEN: usize = 1 + 32;
+
+/// Settings of a new raffle, carried by InitializeRaffle in this order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaffleParams {
+    /// Title of the raffle, null-padded (at most `MAX_TITLE_LEN` bytes before the padding)
+    pub title: [u8; 32],
+    /// Duration of the raffle in seconds
+    pub duration: u64,
+    /// Unique identifier for this raffle
+    pub nonce: u64,
+    /// Maximum number of tickets that can be sold (zero means no cap)
+    pub max_tickets: u64,
+    /// Fee for this raffle in basis points, must not exceed the config fee
+    pub fee_override_bps: u16,
+    /// Maximum number of distinct buyers (zero means no cap)
+    pub max_participants: u64,
+    /// Creator-funded prize deposited into the vault at creation (zero for none)
+    pub fixed_prize_lamports: u64,
+    /// Move to ReadyForRandomness as soon as max_tickets are sold
+    pub draw_on_sellout: bool,
+    /// Tickets sold after which the authority may close sales and draw before end_time (zero for none)
+    pub soft_cap_tickets: u64,
+    /// Distinct buyers needed for the fixed prize to be paid out (zero for no minimum)
+    pub min_unique_participants: u64,
+    /// Seconds the winner has to claim before forfeiting to the runner-up (zero pays out at completion)
+    pub claim_window: u64,
+    /// Seconds after end_time during which purchases are still accepted
+    pub purchase_grace_seconds: u64,
+    /// Share of each purchase fee, in basis points, held back for a consolation draw
+    pub consolation_bps: u16,
+    /// Refundable bond in lamports the creator deposits, slashed into the prize if the raffle is abandoned
+    pub creator_bond: u64,
+    /// Number of equal installments the prize is paid in (zero or one pays it at once)
+    pub payout_installments: u8,
+    /// Seconds the winner must wait between installment claims
+    pub payout_interval: u64,
+    /// Address that receives charity_bps of the prize (default when there is no charity split)
+    pub charity: Pubkey,
+    /// Share of the prize, in basis points, paid to the charity at completion
+    pub charity_bps: u16,
+    /// Smallest prize pool in lamports worth drawing, zero for no minimum
+    pub min_pool_lamports: u64,
+    /// Largest prize paid to the winner, zero for no cap; the rest goes to the treasury
+    pub max_prize_lamports: u64,
+    /// Most lamports of fees taken across the whole raffle, zero for no cap
+    pub max_total_fee_lamports: u64,
+    /// Lamports a purchaser must hold before buying, zero for no minimum
+    pub min_buyer_balance: u64,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum RaffleInstruction {
     /// Initialize the config for the raffle program
@@ -29,16 +131,19 @@ pub enum RaffleInstruction {
     /// Accounts expected:
     /// 0. `[signer, writable]` The authority/creator of the raffle who pays for the raffle account
     /// 1. `[writable]` The raffle account, must be uninitialized
-    /// 2. `[]` Config account with raffle settings
+    /// 2. `[writable]` Config account with raffle settings, its next_raffle_index is advanced
     /// 3. `[]` The system program
     /// 4. `[]` The clock sysvar
+    /// 5. `[writable]` The raffle prize vault (PDA), receives the fixed prize and creator bond if any
+    /// 6. `[writable]` The index pointer account (PDA of `config.next_raffle_index`)
+    /// 7. `[writable]` The authority's open raffle counter (PDA, created on first use)
+    /// 8. `[signer, writable]` (optional) Payer for account rent, the fixed prize and the bond, defaults to the authority
+    ///
+    /// When called via CPI the authority may be a PDA of the calling program signing with
+    /// `invoke_signed`; pass a funded payer since a data-bearing PDA cannot pay for accounts.
     InitializeRaffle {
-        /// Title of the raffle (max 32 chars)
-        title: [u8; 32],
-        /// Duration of the raffle in seconds
-        duration: u64,
-        /// Unique identifier for this raffle
-        nonce: u64,
+        /// Settings of the new raffle
+        params: RaffleParams,
     },
 
     /// Purchase tickets for a raffle
@@ -46,16 +151,28 @@ pub enum RaffleInstruction {
     /// Accounts expected:
     /// 0. `[signer, writable]` The ticket purchaser account (pays for tickets)
     /// 1. `[writable]` The raffle account
-    /// 2. `[writable]` The ticket purchase record account (pre-created keypair)
+    /// 2. `[writable]` The ticket purchase record account (pre-created keypair; must also
+    ///    sign on the purchase that initializes it)
     /// 3. `[writable]` Treasury account to receive fees
     /// 4. `[]` The system program
     /// 5. `[]` The clock sysvar
+    /// 6. `[writable]` The raffle prize vault (PDA)
+    /// 7. `[]` The wallet blacklist (PDA, may not exist yet)
+    /// 8. `[writable]` The referrer wallet (only when `referrer` is set)
+    /// 9. `[writable]` The referrer's referral account (PDA, only when `referrer` is set)
     PurchaseTickets {
         /// Number of tickets to purchase
         ticket_count: u64,
+        /// Ticket price in lamports the buyer agreed to; the purchase fails if the raffle's differs
+        expected_ticket_price: u64,
+        /// Wallet that referred the buyer, or the default pubkey for none
+        referrer: Pubkey,
     },
 
-    /// Complete the raffle and pick a winner
+    /// Deprecated: always fails with `InvalidInstructionData`
+    ///
+    /// Winners are only drawn through RequestRandomness and CompleteRaffleWithVrf. The tag is
+    /// kept so the instructions after it keep their numbers.
     ///
     /// Accounts expected:
     /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
@@ -104,34 +221,405 @@ pub enum RaffleInstruction {
     /// Request VRF randomness for a raffle (step 1 of raffle completion)
     ///
     /// Accounts expected:
-    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
+    /// 0. `[signer]` Any user, or the raffle authority or admin when completion is permissioned
     /// 1. `[writable]` The raffle account
     /// 2. `[writable]` The VRF account
-    /// 3. `[signer, writable]` The payer account (pays for VRF request)
+    /// 3. `[signer, writable]` The payer account (pays for VRF request); receives the request
+    ///    reward at completion
     /// 4. `[]` The switchboard program account
-    /// 5. `[]` The oracle queue account
-    /// Remaining accounts needed by Switchboard VRF
+    /// 5. `[]` The oracle queue account, must be the queue allowlisted in config and, once the
+    ///    raffle has requested, the queue recorded on it
+    /// 6. `[]` Config account
+    /// 7-14. Accounts needed by Switchboard VRF, in the order of `vrf::VRF_REQUEST_REMAINING_ACCOUNTS`
     RequestRandomness {},
 
     /// Complete the raffle with VRF result (step 2 of raffle completion)
     ///
     /// Accounts expected:
-    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
+    /// 0. `[signer, writable]` Any user, or the raffle authority or admin when completion is
+    ///    permissioned; receives the keeper reward
     /// 1. `[writable]` The raffle account
     /// 2. `[]` The VRF account (must have a valid result)
-    /// 3. `[writable]` The prize recipient (winner)
+    /// 3. `[writable]` The winning ticket purchase record
     /// 4. `[]` The switchboard program account
     /// 5. `[]` The clock sysvar
+    /// 6. `[writable]` The raffle prize vault (PDA)
+    /// 7. `[]` The system program
+    /// 8. `[writable]` The raffle authority, gets the fixed prize back if too few buyers took part
+    /// 9. `[]` Config account
+    /// 10. `[writable]` The raffle's charity address, receives its share of the prize if any
+    /// 11. `[writable]` The raffle authority's open raffle counter (PDA)
+    /// 12. `[writable]` Treasury account recorded on the raffle, receives any prize above the cap
+    /// 13. `[writable]` The payer of the randomness request, receives the request reward; unused
+    ///     when a sole holder's raffle completes without a request
+    /// 14. `[writable]` The purchaser wallet of the winning record, receives the prize
+    /// 15. `[writable]` The purchaser wallet of the consolation record, or the treasury when
+    ///     the winner holds every ticket; receives the consolation pool if any
+    /// 16+. `[]` Every ticket purchase record of the raffle, in ascending pubkey order
+    ///     (may be omitted when the winner record holds every ticket sold)
     CompleteRaffleWithVrf {},
 
     /// Prepare raffle for randomness request (transition from Active to ReadyForRandomness)
-    /// This verifies time has ended and sets the correct status
+    /// This verifies time has ended and sets the correct status. Once the soft cap is reached
+    /// the raffle authority may prepare it before end_time, which closes sales early.
     ///
     /// Accounts expected:
-    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
+    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action); only
+    ///    the raffle authority before end_time
     /// 1. `[writable]` The raffle account
     /// 2. `[]` The clock sysvar
     PrepareRaffle {},
+
+    /// Emit the winner information of a raffle (read-only)
+    /// For a completed raffle this logs the winner, prize amount, completion time and VRF account,
+    /// then the runner-up and the consolation winner with what they were paid
+    /// For any other raffle only the current status is logged
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The raffle account
+    GetWinnerInfo {},
+
+    /// Raise the ticket supply cap of an active raffle (authority only)
+    /// The cap can only ever be increased so buyers' odds ceilings are never lowered
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The raffle authority
+    /// 1. `[writable]` The raffle account
+    IncreaseMaxTickets {
+        /// New maximum number of tickets, must be greater than the current cap
+        new_max: u64,
+    },
+
+    /// Cancel a raffle before a winner is drawn (authority only, or anyone once sales have
+    /// closed with the pool below `min_pool_lamports`)
+    /// Buyer principal stays in the vault for refunds, a fixed prize goes back to the authority
+    /// and any other extra funds go to the treasury
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The raffle authority, or any user for an undersized pool
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The raffle prize vault (PDA)
+    /// 3. `[writable]` Treasury account recorded on the raffle
+    /// 4. `[]` The system program
+    /// 5. `[writable]` The raffle authority's open raffle counter (PDA)
+    /// 6. `[writable]` (optional) The raffle authority, receives the fixed prize and bond;
+    ///    defaults to account 0
+    CancelRaffle {},
+
+    /// Refund a buyer's tickets from a cancelled raffle and close their ticket record
+    /// Anyone can trigger the refund, principal and record rent always go to the recorded purchaser
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The purchaser wallet recorded on the ticket purchase
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The ticket purchase record account
+    /// 3. `[writable]` The raffle prize vault (PDA)
+    /// 4. `[]` The system program
+    RefundTickets {},
+
+    /// Report the program version (read-only)
+    /// Return data is the instruction schema version byte followed by the program version string
+    ///
+    /// Accounts expected: none
+    GetVersion {},
+
+    /// Move the prize pool of a legacy raffle from the raffle account into its vault PDA
+    /// Can only run once, while the raffle is Active or ReadyForRandomness. A raffle account
+    /// still in the legacy layout is resized to the current one first.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Any user (fully decentralized - anyone can initiate this action);
+    ///    pays the rent for the larger account
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The raffle prize vault (PDA)
+    /// 3. `[]` The system program
+    MigrateToVault {},
+
+    /// Finalize a raffle that ended without selling any tickets
+    /// Any creator-funded fixed prize is returned to the raffle authority
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The raffle prize vault (PDA)
+    /// 3. `[writable]` The raffle authority
+    /// 4. `[]` The system program
+    /// 5. `[]` The clock sysvar
+    /// 6. `[writable]` The raffle authority's open raffle counter (PDA)
+    CloseEmptyRaffle {},
+
+    /// Add or remove a wallet on the blacklist (admin only)
+    /// Blacklisted wallets cannot purchase tickets
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin authority (pays for the blacklist account on first use)
+    /// 1. `[]` Config account
+    /// 2. `[writable]` The wallet blacklist (PDA)
+    /// 3. `[]` The system program
+    SetBlacklist {
+        /// True to ban the wallet, false to lift the ban
+        add: bool,
+        /// The wallet to update
+        pubkey: Pubkey,
+    },
+
+    /// Pause or resume ticket sales for a single raffle
+    /// Refunds and completion are unaffected
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The raffle authority or the config admin
+    /// 1. `[writable]` The raffle account
+    /// 2. `[]` Config account
+    FreezeRaffle {
+        /// True to block new purchases, false to allow them again
+        frozen: bool,
+    },
+
+    /// Move lamports sent directly to a raffle or its vault out of the prize pool (admin only)
+    /// Buyer principal and the fixed prize are left untouched
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[]` Config account
+    /// 2. `[writable]` The raffle account
+    /// 3. `[writable]` The raffle prize vault (PDA)
+    /// 4. `[writable]` The account receiving the excess (usually the treasury)
+    /// 5. `[]` The system program
+    ReconcileRaffle {},
+
+    /// Claim a prize held in the vault during the raffle's claim window
+    ///
+    /// A prize paid in installments is claimed once per installment, at most once per
+    /// `payout_interval`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The purchaser who owns the winning ticket record, receives the prize
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The raffle prize vault (PDA)
+    /// 3. `[]` The winning ticket purchase record
+    /// 4. `[]` The clock sysvar
+    /// 5. `[]` The system program
+    ClaimPrize {},
+
+    /// Pay an unclaimed prize to the runner-up once the claim deadline has passed
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The raffle prize vault (PDA)
+    /// 3. `[]` The runner-up ticket purchase record
+    /// 4. `[]` The clock sysvar
+    /// 5. `[]` The system program
+    /// 6. `[writable]` The purchaser wallet of the runner-up record, receives the prize
+    ForfeitToRunnerUp {},
+
+    /// Report the derivation seeds and bumps of a raffle and its vault (read-only)
+    /// Return data: nonce (u64 LE), raffle bump, raffle address, vault bump, vault address
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The raffle account
+    GetPdaInfo {},
+
+    /// Update the oracle queue randomness may be requested from (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[]` The new oracle queue
+    /// 2. `[writable]` Config account
+    UpdateOracleQueue {},
+
+    /// Preview the cost of a purchase at the raffle's current price (read-only)
+    /// Nothing is transferred or written. Return data: total price, fee, pool contribution
+    /// (u64 LE each) and the resulting share of all tickets in basis points (u16 LE)
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The raffle account
+    SimulatePurchase {
+        /// Number of tickets to price
+        ticket_count: u64,
+    },
+
+    /// Pay the fee-funded consolation pool to a second, non-winning ticket
+    /// If the winner held every ticket the pool goes to the treasury instead. Completion pays
+    /// the pool itself, so this is only needed for raffles completed before it did.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Any user (fully decentralized - anyone can initiate this action)
+    /// 1. `[writable]` The raffle account
+    /// 2. `[]` The VRF account used for the main draw
+    /// 3. `[]` The switchboard program account
+    /// 4. `[writable]` The raffle prize vault (PDA)
+    /// 5. `[writable]` The purchaser wallet of the consolation record (or the treasury)
+    /// 6. `[]` The system program
+    /// 7+. `[]` Every ticket purchase record of the raffle, in ascending pubkey order
+    ConsolationDraw {},
+
+    /// Choose whether anyone may request randomness and complete raffles (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetPermissionlessCompletion {
+        /// True lets anyone complete, false restricts it to the raffle authority and admin
+        enabled: bool,
+    },
+
+    /// Restrict raffle creation to a fixed set of durations (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetAllowedDurations {
+        /// Allowed durations in seconds; zero slots are unused and all zeros allows any duration
+        durations: [u64; MAX_ALLOWED_DURATIONS],
+    },
+
+    /// Slash the creator bond of an abandoned raffle into its prize and prepare it for randomness
+    ///
+    /// Allowed once `CREATOR_BOND_GRACE_SECONDS` have passed since sales closed without the
+    /// raffle being prepared.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Any user other than the raffle authority
+    /// 1. `[writable]` The raffle account
+    /// 2. `[]` The clock sysvar
+    SlashCreatorBond {},
+
+    /// Set the discounted fee for purchases of many tickets (admin only)
+    ///
+    /// Raffles take the bulk settings in effect when they are created.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetBulkFee {
+        /// Tickets in one purchase from which the bulk fee applies, zero disables the discount
+        bulk_threshold: u64,
+        /// Discounted fee in basis points, must not exceed the standard fee
+        bulk_fee_bps: u16,
+    },
+
+    /// Donate lamports to a raffle's prize without buying tickets
+    ///
+    /// Donations are not refundable and are paid to the winner with the rest of the prize.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The donor
+    /// 1. `[writable]` The raffle account
+    /// 2. `[writable]` The raffle prize vault (PDA)
+    /// 3. `[]` The system program
+    DonateToPool {
+        /// Lamports to donate
+        amount: u64,
+    },
+
+    /// Set the minimum delay between requesting randomness and completing a raffle (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetDrawDelay {
+        /// Seconds completion must wait after the randomness request
+        draw_delay: u64,
+    },
+
+    /// Report how much a buyer has spent on a raffle (read-only)
+    /// Return data: ticket count, total spent and fees paid (u64 LE each). Fees are computed at
+    /// the raffle's standard rate, so bulk discounts are not reflected.
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The raffle account
+    /// 1. `[]` The buyer's ticket purchase record
+    /// 2. `[]` The buyer's wallet
+    GetBuyerSpend {},
+
+    /// Set the share of the prize pool paid to whoever completes a raffle (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetKeeperReward {
+        /// Keeper reward in basis points, at most `MAX_KEEPER_REWARD_BPS`
+        keeper_reward_bps: u16,
+    },
+
+    /// Set the share of the treasury fee paid to a purchase's referrer (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetReferralReward {
+        /// Referral reward in basis points of the treasury fee
+        referral_bps: u16,
+    },
+
+    /// Drop a VRF request that has stalled past `VRF_REQUEST_TIMEOUT_SECONDS` and move the
+    /// raffle to a new oracle queue (raffle authority or admin)
+    ///
+    /// The old VRF account is unregistered so its result can no longer complete the raffle.
+    /// The new queue must be the config's allowlisted queue and differ from the one that
+    /// stalled; it is recorded on the raffle, and the next RequestRandomness must use it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The raffle authority or the config admin
+    /// 1. `[writable]` The raffle account
+    /// 2. `[]` Config account
+    /// 3. `[]` The oracle queue to request randomness from next
+    RetargetVrf {},
+
+    /// Limit how many raffles one authority may have open at once (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetMaxActiveRaffles {
+        /// Maximum open raffles per authority, zero for no limit
+        max_active_raffles: u64,
+    },
+
+    /// Recompute a raffle's pool, fee split and winner from first principles and compare them
+    /// with its recorded state and vault balance (read-only)
+    /// Each mismatch is logged as an `AuditDiscrepancy:` line followed by one `AuditReport:` summary.
+    /// Return data: checks run and discrepancies found (u64 LE each).
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The raffle account
+    /// 1. `[]` The raffle prize vault (PDA)
+    /// 2. `[]` The VRF account registered with the raffle
+    /// 3. `[]` The switchboard program account
+    /// 4+. `[]` (optional) Every ticket purchase record of the raffle; the record, participant
+    ///     and winner checks are skipped when none are given
+    AuditRaffle {},
+
+    /// Set the share of the prize pool paid to whoever paid for the randomness request (admin only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin authority
+    /// 1. `[writable]` Config account
+    SetRequestReward {
+        /// Request reward in basis points, at most `MAX_KEEPER_REWARD_BPS`
+        request_reward_bps: u16,
+    },
+
+    /// Change the title of an active raffle (authority only)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The raffle authority
+    /// 1. `[writable]` The raffle account
+    UpdateRaffleTitle {
+        /// New title, null-padded, held to the same rules as at creation
+        title: [u8; 32],
+    },
+
+    /// Resize a config account still in the legacy layout to the current one (admin only)
+    /// Fields the legacy layout lacks start at their zero value, apart from the ticket price
+    /// band and permissionless completion, which take their defaults.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin authority; pays the rent for the larger account
+    /// 1. `[writable]` Config account
+    /// 2. `[]` The system program
+    MigrateConfig {},
 }
 
 impl RaffleInstruction {
@@ -139,11 +627,18 @@ impl RaffleInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
         let (tag, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
 
+        // Every instruction except RequestRandomness has an exact size; reject anything
+        // shorter or longer so encoding bugs surface instead of being silently ignored
+        if let Some(expected_len) = Self::expected_len(*tag) {
+            if input.len() != expected_len {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+        }
+
         Ok(match tag {
             0 => {
-                if rest.len() < 10 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
+                let (ticket_price, rest) = Self::unpack_u64(rest)?;
+                let (fee_basis_points, _) = Self::unpack_u16(rest)?;
                 Self::InitializeConfig {
                     ticket_price,
                     fee_basis_points,
@@ -152,16 +647,58 @@ impl RaffleInstruction {
             1 => {
                 let (title, rest) = Self::unpack_fixed_bytes::<32>(rest)?;
                 let (duration, rest) = Self::unpack_u64(rest)?;
-                let (nonce, _) = Self::unpack_u64(rest)?;
+                let (nonce, rest) = Self::unpack_u64(rest)?;
+                let (max_tickets, rest) = Self::unpack_u64(rest)?;
+                let (fee_override_bps, rest) = Self::unpack_u16(rest)?;
+                let (max_participants, rest) = Self::unpack_u64(rest)?;
+                let (fixed_prize_lamports, rest) = Self::unpack_u64(rest)?;
+                let (draw_on_sellout, rest) = Self::unpack_bool(rest)?;
+                let (soft_cap_tickets, rest) = Self::unpack_u64(rest)?;
+                let (min_unique_participants, rest) = Self::unpack_u64(rest)?;
+                let (claim_window, rest) = Self::unpack_u64(rest)?;
+                let (purchase_grace_seconds, rest) = Self::unpack_u64(rest)?;
+                let (consolation_bps, rest) = Self::unpack_u16(rest)?;
+                let (creator_bond, rest) = Self::unpack_u64(rest)?;
+                let (payout_installments, rest) = Self::unpack_u8(rest)?;
+                let (payout_interval, rest) = Self::unpack_u64(rest)?;
+                let (charity, rest) = Self::unpack_pubkey(rest)?;
+                let (charity_bps, rest) = Self::unpack_u16(rest)?;
+                let (min_pool_lamports, rest) = Self::unpack_u64(rest)?;
+                let (max_prize_lamports, rest) = Self::unpack_u64(rest)?;
+                let (max_total_fee_lamports, rest) = Self::unpack_u64(rest)?;
+                let (min_buyer_balance, _) = Self::unpack_u64(rest)?;
                 Self::InitializeRaffle {
-                    title,
-                    duration,
-                    nonce,
+                    params: RaffleParams {
+                        title,
+                        duration,
+                        nonce,
+                        max_tickets,
+                        fee_override_bps,
+                        max_participants,
+                        fixed_prize_lamports,
+                        draw_on_sellout,
+                        soft_cap_tickets,
+                        min_unique_participants,
+                        claim_window,
+                        purchase_grace_seconds,
+                        consolation_bps,
+                        creator_bond,
+                        payout_installments,
+                        payout_interval,
+                        charity,
+                        charity_bps,
+                        min_pool_lamports,
+                        max_prize_lamports,
+                        max_total_fee_lamports,
+                        min_buyer_balance,
+                    },
                 }
             },
             2 => {
-                let (ticket_count, _) = Self::unpack_u64(rest)?;
-                Self::PurchaseTickets { ticket_count }
+                let (ticket_count, rest) = Self::unpack_u64(rest)?;
+                let (expected_ticket_price, rest) = Self::unpack_u64(rest)?;
+                let (referrer, _) = Self::unpack_pubkey(rest)?;
+                Self::PurchaseTickets { ticket_count, expected_ticket_price, referrer }
             },
             3 => Self::CompleteRaffle {},
             4 => Self::UpdateAdmin {},
@@ -177,10 +714,122 @@ impl RaffleInstruction {
             8 => Self::RequestRandomness {},
             9 => Self::CompleteRaffleWithVrf {},
             10 => Self::PrepareRaffle {},
+            11 => Self::GetWinnerInfo {},
+            12 => {
+                let (new_max, _) = Self::unpack_u64(rest)?;
+                Self::IncreaseMaxTickets { new_max }
+            },
+            13 => Self::CancelRaffle {},
+            14 => Self::RefundTickets {},
+            15 => Self::GetVersion {},
+            16 => Self::MigrateToVault {},
+            17 => Self::CloseEmptyRaffle {},
+            18 => {
+                let (add, rest) = Self::unpack_bool(rest)?;
+                let (pubkey, _) = Self::unpack_pubkey(rest)?;
+                Self::SetBlacklist { add, pubkey }
+            },
+            19 => {
+                let (frozen, _) = Self::unpack_bool(rest)?;
+                Self::FreezeRaffle { frozen }
+            },
+            20 => Self::ReconcileRaffle {},
+            21 => Self::ClaimPrize {},
+            22 => Self::ForfeitToRunnerUp {},
+            23 => Self::GetPdaInfo {},
+            24 => Self::UpdateOracleQueue {},
+            25 => {
+                let (ticket_count, _) = Self::unpack_u64(rest)?;
+                Self::SimulatePurchase { ticket_count }
+            },
+            26 => Self::ConsolationDraw {},
+            27 => {
+                let (enabled, _) = Self::unpack_bool(rest)?;
+                Self::SetPermissionlessCompletion { enabled }
+            },
+            28 => {
+                let mut durations = [0u64; MAX_ALLOWED_DURATIONS];
+                let mut rest = rest;
+                for duration in durations.iter_mut() {
+                    let (value, remaining) = Self::unpack_u64(rest)?;
+                    *duration = value;
+                    rest = remaining;
+                }
+                Self::SetAllowedDurations { durations }
+            },
+            29 => Self::SlashCreatorBond {},
+            30 => {
+                let (bulk_threshold, rest) = Self::unpack_u64(rest)?;
+                let (bulk_fee_bps, _) = Self::unpack_u16(rest)?;
+                Self::SetBulkFee { bulk_threshold, bulk_fee_bps }
+            },
+            31 => {
+                let (amount, _) = Self::unpack_u64(rest)?;
+                Self::DonateToPool { amount }
+            },
+            32 => {
+                let (draw_delay, _) = Self::unpack_u64(rest)?;
+                Self::SetDrawDelay { draw_delay }
+            },
+            33 => Self::GetBuyerSpend {},
+            34 => {
+                let (keeper_reward_bps, _) = Self::unpack_u16(rest)?;
+                Self::SetKeeperReward { keeper_reward_bps }
+            },
+            35 => {
+                let (referral_bps, _) = Self::unpack_u16(rest)?;
+                Self::SetReferralReward { referral_bps }
+            },
+            36 => Self::RetargetVrf {},
+            38 => Self::AuditRaffle {},
+            37 => {
+                let (max_active_raffles, _) = Self::unpack_u64(rest)?;
+                Self::SetMaxActiveRaffles { max_active_raffles }
+            },
+            39 => {
+                let (request_reward_bps, _) = Self::unpack_u16(rest)?;
+                Self::SetRequestReward { request_reward_bps }
+            },
+            40 => {
+                let (title, _) = Self::unpack_fixed_bytes::<32>(rest)?;
+                Self::UpdateRaffleTitle { title }
+            },
+            41 => Self::MigrateConfig {},
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
 
+    /// Expected serialized size for an instruction tag
+    ///
+    /// `RequestRandomness` carries no fields and is exempt, since it has nothing for
+    /// extra bytes to be confused with. Unknown tags are rejected by `unpack` itself.
+    pub fn expected_len(tag: u8) -> Option<usize> {
+        match tag {
+            0 => Some(INITIALIZE_CONFIG_LEN),
+            1 => Some(INITIALIZE_RAFFLE_LEN),
+            2 => Some(PURCHASE_TICKETS_LEN),
+            6 => Some(UPDATE_TICKET_PRICE_LEN),
+            7 => Some(UPDATE_FEE_PERCENTAGE_LEN),
+            8 => None,
+            12 => Some(INCREASE_MAX_TICKETS_LEN),
+            18 => Some(SET_BLACKLIST_LEN),
+            19 => Some(FREEZE_RAFFLE_LEN),
+            25 => Some(SIMULATE_PURCHASE_LEN),
+            27 => Some(SET_PERMISSIONLESS_COMPLETION_LEN),
+            28 => Some(SET_ALLOWED_DURATIONS_LEN),
+            30 => Some(SET_BULK_FEE_LEN),
+            31 => Some(DONATE_TO_POOL_LEN),
+            32 => Some(SET_DRAW_DELAY_LEN),
+            34 => Some(SET_KEEPER_REWARD_LEN),
+            35 => Some(SET_REFERRAL_REWARD_LEN),
+            37 => Some(SET_MAX_ACTIVE_RAFFLES_LEN),
+            39 => Some(SET_REQUEST_REWARD_LEN),
+            40 => Some(UPDATE_RAFFLE_TITLE_LEN),
+            3..=26 | 29 | 33 | 36 | 38 | 41 => Some(EMPTY_INSTRUCTION_LEN),
+            _ => None,
+        }
+    }
+
     /// Packs a RaffleInstruction into a byte buffer
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
@@ -193,19 +842,60 @@ impl RaffleInstruction {
                 buf.extend_from_slice(&ticket_price.to_le_bytes());
                 buf.extend_from_slice(&fee_basis_points.to_le_bytes());
             }
-            Self::InitializeRaffle {
-                ref title,
-                duration,
-                nonce,
-            } => {
+            Self::InitializeRaffle { ref params } => {
+                let RaffleParams {
+                    ref title,
+                    duration,
+                    nonce,
+                    max_tickets,
+                    fee_override_bps,
+                    max_participants,
+                    fixed_prize_lamports,
+                    draw_on_sellout,
+                    soft_cap_tickets,
+                    min_unique_participants,
+                    claim_window,
+                    purchase_grace_seconds,
+                    consolation_bps,
+                    creator_bond,
+                    payout_installments,
+                    payout_interval,
+                    charity,
+                    charity_bps,
+                    min_pool_lamports,
+                    max_prize_lamports,
+                    max_total_fee_lamports,
+                    min_buyer_balance,
+                } = *params;
                 buf.push(1);
                 buf.extend_from_slice(title);
                 buf.extend_from_slice(&duration.to_le_bytes());
                 buf.extend_from_slice(&nonce.to_le_bytes());
+                buf.extend_from_slice(&max_tickets.to_le_bytes());
+                buf.extend_from_slice(&fee_override_bps.to_le_bytes());
+                buf.extend_from_slice(&max_participants.to_le_bytes());
+                buf.extend_from_slice(&fixed_prize_lamports.to_le_bytes());
+                buf.push(draw_on_sellout as u8);
+                buf.extend_from_slice(&soft_cap_tickets.to_le_bytes());
+                buf.extend_from_slice(&min_unique_participants.to_le_bytes());
+                buf.extend_from_slice(&claim_window.to_le_bytes());
+                buf.extend_from_slice(&purchase_grace_seconds.to_le_bytes());
+                buf.extend_from_slice(&consolation_bps.to_le_bytes());
+                buf.extend_from_slice(&creator_bond.to_le_bytes());
+                buf.push(payout_installments);
+                buf.extend_from_slice(&payout_interval.to_le_bytes());
+                buf.extend_from_slice(charity.as_ref());
+                buf.extend_from_slice(&charity_bps.to_le_bytes());
+                buf.extend_from_slice(&min_pool_lamports.to_le_bytes());
+                buf.extend_from_slice(&max_prize_lamports.to_le_bytes());
+                buf.extend_from_slice(&max_total_fee_lamports.to_le_bytes());
+                buf.extend_from_slice(&min_buyer_balance.to_le_bytes());
             }
-            Self::PurchaseTickets { ticket_count } => {
+            Self::PurchaseTickets { ticket_count, expected_ticket_price, ref referrer } => {
                 buf.push(2);
                 buf.extend_from_slice(&ticket_count.to_le_bytes());
+                buf.extend_from_slice(&expected_ticket_price.to_le_bytes());
+                buf.extend_from_slice(referrer.as_ref());
             }
             Self::CompleteRaffle {} => buf.push(3),
             Self::UpdateAdmin {} => buf.push(4),
@@ -221,9 +911,146 @@ impl RaffleInstruction {
             Self::RequestRandomness {} => buf.push(8),
             Self::CompleteRaffleWithVrf {} => buf.push(9),
             Self::PrepareRaffle {} => buf.push(10),
+            Self::GetWinnerInfo {} => buf.push(11),
+            Self::IncreaseMaxTickets { new_max } => {
+                buf.push(12);
+                buf.extend_from_slice(&new_max.to_le_bytes());
+            }
+            Self::CancelRaffle {} => buf.push(13),
+            Self::RefundTickets {} => buf.push(14),
+            Self::GetVersion {} => buf.push(15),
+            Self::MigrateToVault {} => buf.push(16),
+            Self::CloseEmptyRaffle {} => buf.push(17),
+            Self::SetBlacklist { add, ref pubkey } => {
+                buf.push(18);
+                buf.push(add as u8);
+                buf.extend_from_slice(pubkey.as_ref());
+            }
+            Self::FreezeRaffle { frozen } => {
+                buf.push(19);
+                buf.push(frozen as u8);
+            }
+            Self::ReconcileRaffle {} => buf.push(20),
+            Self::ClaimPrize {} => buf.push(21),
+            Self::ForfeitToRunnerUp {} => buf.push(22),
+            Self::GetPdaInfo {} => buf.push(23),
+            Self::UpdateOracleQueue {} => buf.push(24),
+            Self::SimulatePurchase { ticket_count } => {
+                buf.push(25);
+                buf.extend_from_slice(&ticket_count.to_le_bytes());
+            }
+            Self::ConsolationDraw {} => buf.push(26),
+            Self::SetPermissionlessCompletion { enabled } => {
+                buf.push(27);
+                buf.push(enabled as u8);
+            }
+            Self::SetAllowedDurations { durations } => {
+                buf.push(28);
+                for duration in durations.iter() {
+                    buf.extend_from_slice(&duration.to_le_bytes());
+                }
+            }
+            Self::SlashCreatorBond {} => buf.push(29),
+            Self::SetBulkFee { bulk_threshold, bulk_fee_bps } => {
+                buf.push(30);
+                buf.extend_from_slice(&bulk_threshold.to_le_bytes());
+                buf.extend_from_slice(&bulk_fee_bps.to_le_bytes());
+            }
+            Self::DonateToPool { amount } => {
+                buf.push(31);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SetDrawDelay { draw_delay } => {
+                buf.push(32);
+                buf.extend_from_slice(&draw_delay.to_le_bytes());
+            }
+            Self::GetBuyerSpend {} => buf.push(33),
+            Self::SetKeeperReward { keeper_reward_bps } => {
+                buf.push(34);
+                buf.extend_from_slice(&keeper_reward_bps.to_le_bytes());
+            }
+            Self::SetReferralReward { referral_bps } => {
+                buf.push(35);
+                buf.extend_from_slice(&referral_bps.to_le_bytes());
+            }
+            Self::RetargetVrf {} => buf.push(36),
+            Self::AuditRaffle {} => buf.push(38),
+            Self::SetMaxActiveRaffles { max_active_raffles } => {
+                buf.push(37);
+                buf.extend_from_slice(&max_active_raffles.to_le_bytes());
+            }
+            Self::SetRequestReward { request_reward_bps } => {
+                buf.push(39);
+                buf.extend_from_slice(&request_reward_bps.to_le_bytes());
+            }
+            Self::UpdateRaffleTitle { ref title } => {
+                buf.push(40);
+                buf.extend_from_slice(title);
+            }
+            Self::MigrateConfig {} => buf.push(41),
         }
         buf
     }
+
+    /// Unpacks a little-endian u64 from the front of the input
+    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() < 8 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (bytes, rest) = input.split_at(8);
+        let value = bytes
+            .try_into()
+            .map(u64::from_le_bytes)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok((value, rest))
+    }
+
+    /// Unpacks a little-endian u16 from the front of the input
+    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        if input.len() < 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (bytes, rest) = input.split_at(2);
+        let value = bytes
+            .try_into()
+            .map(u16::from_le_bytes)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok((value, rest))
+    }
+
+    /// Unpacks a fixed-size byte array from the front of the input
+    fn unpack_fixed_bytes<const N: usize>(input: &[u8]) -> Result<([u8; N], &[u8]), ProgramError> {
+        if input.len() < N {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (bytes, rest) = input.split_at(N);
+        let value = bytes
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok((value, rest))
+    }
+
+    /// Unpacks a u8 from the front of the input
+    fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        let (value, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+        Ok((*value, rest))
+    }
+
+    /// Unpacks a bool (0 or 1) from the front of the input
+    fn unpack_bool(input: &[u8]) -> Result<(bool, &[u8]), ProgramError> {
+        let (value, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+        match value {
+            0 => Ok((false, rest)),
+            1 => Ok((true, rest)),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    /// Unpacks a pubkey from the front of the input
+    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+        let (bytes, rest) = Self::unpack_fixed_bytes::<32>(input)?;
+        Ok((Pubkey::new_from_array(bytes), rest))
+    }
 }
 
 /// Create initialize_config instruction
@@ -261,18 +1088,23 @@ pub fn initialize_raffle(
     authority: &Pubkey,
     raffle_account: &Pubkey,
     config_account: &Pubkey,
-    title: [u8; 32],
-    duration: u64,
-    nonce: u64,
+    raffle_index: u64,
+    params: RaffleParams,
 ) -> Result<Instruction, ProgramError> {
-    let data = RaffleInstruction::InitializeRaffle { title, duration, nonce }.pack();
+    let data = RaffleInstruction::InitializeRaffle { params }.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+    let (index_pointer, _) = find_index_pointer_pda(program_id, raffle_index);
+    let (authority_state, _) = find_authority_state_address(program_id, authority);
 
     let accounts = vec![
         AccountMeta::new(*authority, true),
         AccountMeta::new(*raffle_account, false),
-        AccountMeta::new_readonly(*config_account, false),
+        AccountMeta::new(*config_account, false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(index_pointer, false),
+        AccountMeta::new(authority_state, false),
     ];
 
     Ok(Instruction {
@@ -283,6 +1115,8 @@ pub fn initialize_raffle(
 }
 
 /// Create purchase_tickets instruction
+///
+/// The ticket purchase record is marked as a signer, which the first purchase requires.
 pub fn purchase_tickets(
     program_id: &Pubkey,
     purchaser: &Pubkey,
@@ -290,17 +1124,32 @@ pub fn purchase_tickets(
     ticket_purchase_account: &Pubkey,
     treasury: &Pubkey,
     ticket_count: u64,
+    expected_ticket_price: u64,
+    referrer: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let data = RaffleInstruction::PurchaseTickets { ticket_count }.pack();
+    let data = RaffleInstruction::PurchaseTickets {
+        ticket_count,
+        expected_ticket_price,
+        referrer: referrer.copied().unwrap_or_default(),
+    }.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+    let (blacklist, _) = find_blacklist_address(program_id);
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*purchaser, true),
         AccountMeta::new(*raffle_account, false),
-        AccountMeta::new(*ticket_purchase_account, false),
+        AccountMeta::new(*ticket_purchase_account, true),
         AccountMeta::new(*treasury, false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(blacklist, false),
     ];
+    if let Some(referrer) = referrer {
+        let (referral, _) = find_referral_address(program_id, referrer);
+        accounts.push(AccountMeta::new(*referrer, false));
+        accounts.push(AccountMeta::new(referral, false));
+    }
 
     Ok(Instruction {
         program_id: *program_id,
@@ -310,6 +1159,7 @@ pub fn purchase_tickets(
 }
 
 /// Create complete_raffle instruction
+#[deprecated(note = "CompleteRaffle always fails; use complete_raffle_with_vrf")]
 pub fn complete_raffle(
     program_id: &Pubkey,
     authority: &Pubkey,
@@ -427,6 +1277,7 @@ pub fn request_randomness(
     payer: &Pubkey,
     switchboard_program: &Pubkey,
     oracle_queue: &Pubkey,
+    config_account: &Pubkey,
     remaining_accounts: &[AccountMeta],
 ) -> Result<Instruction, ProgramError> {
     let data = RaffleInstruction::RequestRandomness {}.pack();
@@ -439,6 +1290,7 @@ pub fn request_randomness(
         AccountMeta::new(*payer, true),
         AccountMeta::new_readonly(*switchboard_program, false),
         AccountMeta::new_readonly(*oracle_queue, false),
+        AccountMeta::new_readonly(*config_account, false),
     ];
     
     // Add all remaining accounts needed for Switchboard
@@ -459,18 +1311,43 @@ pub fn complete_raffle_with_vrf(
     vrf_account: &Pubkey,
     winner: &Pubkey,
     switchboard_program: &Pubkey,
+    raffle_authority: &Pubkey,
+    config_account: &Pubkey,
+    charity: &Pubkey,
+    treasury: &Pubkey,
+    vrf_requester: &Pubkey,
+    winner_wallet: &Pubkey,
+    consolation_wallet: &Pubkey,
+    ticket_records: &[Pubkey],
 ) -> Result<Instruction, ProgramError> {
     let data = RaffleInstruction::CompleteRaffleWithVrf {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+    let (authority_state, _) = find_authority_state_address(program_id, raffle_authority);
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*authority, true),
         AccountMeta::new(*raffle_account, false),
         AccountMeta::new_readonly(*vrf_account, false),
         AccountMeta::new(*winner, false),
         AccountMeta::new_readonly(*switchboard_program, false),
         AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*raffle_authority, false),
+        AccountMeta::new_readonly(*config_account, false),
+        AccountMeta::new(*charity, false),
+        AccountMeta::new(authority_state, false),
+        AccountMeta::new(*treasury, false),
+        AccountMeta::new(*vrf_requester, false),
+        AccountMeta::new(*winner_wallet, false),
+        AccountMeta::new(*consolation_wallet, false),
     ];
 
+    // The program walks ticket ranges in ascending pubkey order
+    let mut ticket_records = ticket_records.to_vec();
+    ticket_records.sort();
+    accounts.extend(ticket_records.iter().map(|record| AccountMeta::new_readonly(*record, false)));
+
     Ok(Instruction {
         program_id: *program_id,
         accounts,
@@ -498,3 +1375,711 @@ pub fn prepare_raffle(
         data,
     })
 }
+
+/// Create get_winner_info instruction
+pub fn get_winner_info(
+    program_id: &Pubkey,
+    raffle_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::GetWinnerInfo {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*raffle_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create increase_max_tickets instruction
+pub fn increase_max_tickets(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    raffle_account: &Pubkey,
+    new_max: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::IncreaseMaxTickets { new_max }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*raffle_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create cancel_raffle instruction
+///
+/// `signer` is the raffle authority, or anyone cancelling a raffle whose pool ended below its minimum.
+pub fn cancel_raffle(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    raffle_authority: &Pubkey,
+    raffle_account: &Pubkey,
+    treasury: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::CancelRaffle {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+    let (authority_state, _) = find_authority_state_address(program_id, raffle_authority);
+
+    let accounts = vec![
+        AccountMeta::new(*signer, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*treasury, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(authority_state, false),
+        AccountMeta::new(*raffle_authority, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create refund_tickets instruction
+pub fn refund_tickets(
+    program_id: &Pubkey,
+    purchaser: &Pubkey,
+    raffle_account: &Pubkey,
+    ticket_purchase_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::RefundTickets {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let accounts = vec![
+        AccountMeta::new(*purchaser, false),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(*ticket_purchase_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create get_version instruction
+pub fn get_version(program_id: &Pubkey) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::GetVersion {}.pack();
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![],
+        data,
+    })
+}
+
+/// Create migrate_to_vault instruction
+pub fn migrate_to_vault(
+    program_id: &Pubkey,
+    initiator: &Pubkey,
+    raffle_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::MigrateToVault {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let accounts = vec![
+        AccountMeta::new(*initiator, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create close_empty_raffle instruction
+pub fn close_empty_raffle(
+    program_id: &Pubkey,
+    initiator: &Pubkey,
+    raffle_account: &Pubkey,
+    raffle_authority: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::CloseEmptyRaffle {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+    let (authority_state, _) = find_authority_state_address(program_id, raffle_authority);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initiator, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*raffle_authority, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new(authority_state, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create set_blacklist instruction
+pub fn set_blacklist(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    add: bool,
+    pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetBlacklist { add, pubkey: *pubkey }.pack();
+    let (blacklist, _) = find_blacklist_address(program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(*config_account, false),
+        AccountMeta::new(blacklist, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create FreezeRaffle instruction
+pub fn freeze_raffle(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    raffle_account: &Pubkey,
+    config_account: &Pubkey,
+    frozen: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::FreezeRaffle { frozen }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*signer, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new_readonly(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create ReconcileRaffle instruction
+pub fn reconcile_raffle(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    raffle_account: &Pubkey,
+    recipient: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::ReconcileRaffle {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(*config_account, false),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*recipient, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create ClaimPrize instruction
+pub fn claim_prize(
+    program_id: &Pubkey,
+    purchaser: &Pubkey,
+    raffle_account: &Pubkey,
+    winner_record: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::ClaimPrize {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let accounts = vec![
+        AccountMeta::new(*purchaser, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*winner_record, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create ForfeitToRunnerUp instruction
+pub fn forfeit_to_runner_up(
+    program_id: &Pubkey,
+    initiator: &Pubkey,
+    raffle_account: &Pubkey,
+    runner_up_record: &Pubkey,
+    runner_up_wallet: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::ForfeitToRunnerUp {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initiator, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*runner_up_record, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*runner_up_wallet, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create GetPdaInfo instruction
+pub fn get_pda_info(
+    program_id: &Pubkey,
+    raffle_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::GetPdaInfo {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*raffle_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create UpdateOracleQueue instruction
+pub fn update_oracle_queue(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    new_oracle_queue: &Pubkey,
+    config_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::UpdateOracleQueue {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(*new_oracle_queue, false),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SimulatePurchase instruction
+pub fn simulate_purchase(
+    program_id: &Pubkey,
+    raffle_account: &Pubkey,
+    ticket_count: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SimulatePurchase { ticket_count }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*raffle_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create ConsolationDraw instruction
+pub fn consolation_draw(
+    program_id: &Pubkey,
+    initiator: &Pubkey,
+    raffle_account: &Pubkey,
+    vrf_account: &Pubkey,
+    switchboard_program: &Pubkey,
+    recipient: &Pubkey,
+    ticket_records: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::ConsolationDraw {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*initiator, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new_readonly(*vrf_account, false),
+        AccountMeta::new_readonly(*switchboard_program, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*recipient, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    // The program walks ticket ranges in ascending pubkey order
+    let mut ticket_records = ticket_records.to_vec();
+    ticket_records.sort();
+    accounts.extend(ticket_records.iter().map(|record| AccountMeta::new_readonly(*record, false)));
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetPermissionlessCompletion instruction
+pub fn set_permissionless_completion(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    enabled: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetPermissionlessCompletion { enabled }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetAllowedDurations instruction
+pub fn set_allowed_durations(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    durations: [u64; MAX_ALLOWED_DURATIONS],
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetAllowedDurations { durations }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SlashCreatorBond instruction
+pub fn slash_creator_bond(
+    program_id: &Pubkey,
+    initiator: &Pubkey,
+    raffle_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SlashCreatorBond {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*initiator, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetBulkFee instruction
+pub fn set_bulk_fee(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    bulk_threshold: u64,
+    bulk_fee_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetBulkFee { bulk_threshold, bulk_fee_bps }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create DonateToPool instruction
+pub fn donate_to_pool(
+    program_id: &Pubkey,
+    donor: &Pubkey,
+    raffle_account: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::DonateToPool { amount }.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let accounts = vec![
+        AccountMeta::new(*donor, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetDrawDelay instruction
+pub fn set_draw_delay(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    draw_delay: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetDrawDelay { draw_delay }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create GetBuyerSpend instruction
+pub fn get_buyer_spend(
+    program_id: &Pubkey,
+    raffle_account: &Pubkey,
+    ticket_purchase_account: &Pubkey,
+    buyer: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::GetBuyerSpend {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*raffle_account, false),
+        AccountMeta::new_readonly(*ticket_purchase_account, false),
+        AccountMeta::new_readonly(*buyer, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetKeeperReward instruction
+pub fn set_keeper_reward(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    keeper_reward_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetKeeperReward { keeper_reward_bps }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetReferralReward instruction
+pub fn set_referral_reward(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    referral_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetReferralReward { referral_bps }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create RetargetVrf instruction
+pub fn retarget_vrf(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    raffle_account: &Pubkey,
+    config_account: &Pubkey,
+    oracle_queue: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::RetargetVrf {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*signer, true),
+        AccountMeta::new(*raffle_account, false),
+        AccountMeta::new_readonly(*config_account, false),
+        AccountMeta::new_readonly(*oracle_queue, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetMaxActiveRaffles instruction
+pub fn set_max_active_raffles(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    max_active_raffles: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetMaxActiveRaffles { max_active_raffles }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create AuditRaffle instruction
+pub fn audit_raffle(
+    program_id: &Pubkey,
+    raffle_account: &Pubkey,
+    vrf_account: &Pubkey,
+    switchboard_program: &Pubkey,
+    ticket_records: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::AuditRaffle {}.pack();
+    let (vault, _) = find_vault_address(program_id, raffle_account);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*raffle_account, false),
+        AccountMeta::new_readonly(vault, false),
+        AccountMeta::new_readonly(*vrf_account, false),
+        AccountMeta::new_readonly(*switchboard_program, false),
+    ];
+    accounts.extend(ticket_records.iter().map(|record| AccountMeta::new_readonly(*record, false)));
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create SetRequestReward instruction
+pub fn set_request_reward(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+    request_reward_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::SetRequestReward { request_reward_bps }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create UpdateRaffleTitle instruction
+pub fn update_raffle_title(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    raffle_account: &Pubkey,
+    title: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::UpdateRaffleTitle { title }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*raffle_account, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Create migrate_config instruction
+pub fn migrate_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    config_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = RaffleInstruction::MigrateConfig {}.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(*config_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
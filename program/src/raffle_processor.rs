@@ -1,13 +1,18 @@
 // Fixed imports to address compiler errors
-use crate::raffle_instruction::RaffleInstruction;
-use crate::raffle_state::{Config, Raffle, RaffleStatus, TicketPurchase};
+use crate::raffle_instruction::{RaffleInstruction, RaffleParams, INSTRUCTION_SCHEMA_VERSION};
+use crate::raffle_error::RaffleError;
+use crate::raffle_state::{
+    AuthorityState, Blacklist, Config, Raffle, RaffleIndexPointer, RaffleStatus, Referral, TicketPurchase, MAX_BLACKLIST_ENTRIES,
+    CREATOR_BOND_GRACE_SECONDS, MAX_ALLOWED_DURATIONS, LEGACY_CONFIG_LEN, LEGACY_RAFFLE_LEN, MAX_KEEPER_REWARD_BPS, MAX_TITLE_LEN,
+    VRF_REQUEST_TIMEOUT_SECONDS,
+};
 use crate::vrf;
 
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
@@ -16,6 +21,11 @@ use solana_program::{
     sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
+/// Instruction processor for the raffle program
+///
+/// Structured event logs (`PurchaseEvent:`, `WinnerInfo:`) and return data are only
+/// written after a handler's final successful `pack` and fund transfers, so a log-only
+/// indexer never sees an event for state that was not committed.
 pub struct Processor;
 
 impl Processor {
@@ -34,13 +44,13 @@ impl Processor {
                 msg!("Instruction: Initialize Config");
                 Self::process_initialize_config(accounts, ticket_price, fee_basis_points, program_id)
             }
-            RaffleInstruction::InitializeRaffle { title, duration, nonce } => {
+            RaffleInstruction::InitializeRaffle { params } => {
                 msg!("Instruction: Initialize Raffle");
-                Self::process_initialize_raffle(accounts, title, duration, nonce, program_id)
+                Self::process_initialize_raffle(accounts, params, program_id)
             }
-            RaffleInstruction::PurchaseTickets { ticket_count } => {
+            RaffleInstruction::PurchaseTickets { ticket_count, expected_ticket_price, referrer } => {
                 msg!("Instruction: Purchase Tickets");
-                Self::process_purchase_tickets(accounts, ticket_count, program_id)
+                Self::process_purchase_tickets(accounts, ticket_count, expected_ticket_price, referrer, program_id)
             }
             RaffleInstruction::CompleteRaffle {} => {
                 msg!("Instruction: Complete Raffle");
@@ -74,6 +84,130 @@ impl Processor {
                 msg!("Instruction: Prepare Raffle for Randomness");
                 Self::process_prepare_raffle(accounts, program_id)
             },
+            RaffleInstruction::GetWinnerInfo {} => {
+                msg!("Instruction: Get Winner Info");
+                Self::process_get_winner_info(accounts, program_id)
+            },
+            RaffleInstruction::IncreaseMaxTickets { new_max } => {
+                msg!("Instruction: Increase Max Tickets");
+                Self::process_increase_max_tickets(accounts, new_max, program_id)
+            },
+            RaffleInstruction::CancelRaffle {} => {
+                msg!("Instruction: Cancel Raffle");
+                Self::process_cancel_raffle(accounts, program_id)
+            },
+            RaffleInstruction::RefundTickets {} => {
+                msg!("Instruction: Refund Tickets");
+                Self::process_refund_tickets(accounts, program_id)
+            },
+            RaffleInstruction::GetVersion {} => {
+                msg!("Instruction: Get Version");
+                Self::process_get_version()
+            },
+            RaffleInstruction::MigrateToVault {} => {
+                msg!("Instruction: Migrate To Vault");
+                Self::process_migrate_to_vault(accounts, program_id)
+            },
+            RaffleInstruction::CloseEmptyRaffle {} => {
+                msg!("Instruction: Close Empty Raffle");
+                Self::process_close_empty_raffle(accounts, program_id)
+            },
+            RaffleInstruction::SetBlacklist { add, pubkey } => {
+                msg!("Instruction: Set Blacklist");
+                Self::process_set_blacklist(accounts, add, pubkey, program_id)
+            },
+            RaffleInstruction::FreezeRaffle { frozen } => {
+                msg!("Instruction: Freeze Raffle");
+                Self::process_freeze_raffle(accounts, frozen, program_id)
+            },
+            RaffleInstruction::ReconcileRaffle {} => {
+                msg!("Instruction: Reconcile Raffle");
+                Self::process_reconcile_raffle(accounts, program_id)
+            },
+            RaffleInstruction::ClaimPrize {} => {
+                msg!("Instruction: Claim Prize");
+                Self::process_claim_prize(accounts, program_id)
+            },
+            RaffleInstruction::ForfeitToRunnerUp {} => {
+                msg!("Instruction: Forfeit To Runner-Up");
+                Self::process_forfeit_to_runner_up(accounts, program_id)
+            },
+            RaffleInstruction::GetPdaInfo {} => {
+                msg!("Instruction: Get PDA Info");
+                Self::process_get_pda_info(accounts, program_id)
+            },
+            RaffleInstruction::UpdateOracleQueue {} => {
+                msg!("Instruction: Update Oracle Queue");
+                Self::process_update_oracle_queue(accounts, program_id)
+            },
+            RaffleInstruction::SimulatePurchase { ticket_count } => {
+                msg!("Instruction: Simulate Purchase");
+                Self::process_simulate_purchase(accounts, ticket_count, program_id)
+            },
+            RaffleInstruction::ConsolationDraw {} => {
+                msg!("Instruction: Consolation Draw");
+                Self::process_consolation_draw(accounts, program_id)
+            },
+            RaffleInstruction::SetPermissionlessCompletion { enabled } => {
+                msg!("Instruction: Set Permissionless Completion");
+                Self::process_set_permissionless_completion(accounts, enabled, program_id)
+            },
+            RaffleInstruction::SetAllowedDurations { durations } => {
+                msg!("Instruction: Set Allowed Durations");
+                Self::process_set_allowed_durations(accounts, durations, program_id)
+            },
+            RaffleInstruction::SetBulkFee { bulk_threshold, bulk_fee_bps } => {
+                msg!("Instruction: Set Bulk Fee");
+                Self::process_set_bulk_fee(accounts, bulk_threshold, bulk_fee_bps, program_id)
+            },
+            RaffleInstruction::SetKeeperReward { keeper_reward_bps } => {
+                msg!("Instruction: Set Keeper Reward");
+                Self::process_set_keeper_reward(accounts, keeper_reward_bps, program_id)
+            },
+            RaffleInstruction::SetReferralReward { referral_bps } => {
+                msg!("Instruction: Set Referral Reward");
+                Self::process_set_referral_reward(accounts, referral_bps, program_id)
+            },
+            RaffleInstruction::GetBuyerSpend {} => {
+                msg!("Instruction: Get Buyer Spend");
+                Self::process_get_buyer_spend(accounts, program_id)
+            },
+            RaffleInstruction::AuditRaffle {} => {
+                msg!("Instruction: Audit Raffle");
+                Self::process_audit_raffle(accounts, program_id)
+            },
+            RaffleInstruction::SetDrawDelay { draw_delay } => {
+                msg!("Instruction: Set Draw Delay");
+                Self::process_set_draw_delay(accounts, draw_delay, program_id)
+            },
+            RaffleInstruction::DonateToPool { amount } => {
+                msg!("Instruction: Donate To Pool");
+                Self::process_donate_to_pool(accounts, amount, program_id)
+            },
+            RaffleInstruction::SlashCreatorBond {} => {
+                msg!("Instruction: Slash Creator Bond");
+                Self::process_slash_creator_bond(accounts, program_id)
+            },
+            RaffleInstruction::RetargetVrf {} => {
+                msg!("Instruction: Retarget VRF");
+                Self::process_retarget_vrf(accounts, program_id)
+            },
+            RaffleInstruction::SetMaxActiveRaffles { max_active_raffles } => {
+                msg!("Instruction: Set Max Active Raffles");
+                Self::process_set_max_active_raffles(accounts, max_active_raffles, program_id)
+            },
+            RaffleInstruction::SetRequestReward { request_reward_bps } => {
+                msg!("Instruction: Set Request Reward");
+                Self::process_set_request_reward(accounts, request_reward_bps, program_id)
+            },
+            RaffleInstruction::UpdateRaffleTitle { title } => {
+                msg!("Instruction: Update Raffle Title");
+                Self::process_update_raffle_title(accounts, title, program_id)
+            },
+            RaffleInstruction::MigrateConfig {} => {
+                msg!("Instruction: Migrate Config");
+                Self::process_migrate_config(accounts, program_id)
+            },
         }
     }
 
@@ -93,6 +227,8 @@ impl Processor {
         let config_info = next_account_info(account_info_iter)?;
         let treasury_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 3)?;
         
         // Verify the admin signed the transaction
         if !admin_info.is_signer {
@@ -143,13 +279,17 @@ impl Processor {
             msg!("Admin: {}", config_data.admin.to_string());
             msg!("Treasury: {}", config_data.treasury.to_string());
             msg!("Ticket Price: {} lamports ({}SOL)", config_data.ticket_price, config_data.ticket_price as f64 / 1_000_000_000.0);
-            msg!("Fee: {} basis points ({}%)", config_data.fee_basis_points, config_data.fee_basis_points as f64 / 100.0);
+            let (fee_pct, fee_frac) = crate::utils::format_bps(config_data.fee_basis_points);
+            msg!("Fee: {} basis points ({}.{:02}%)", config_data.fee_basis_points, fee_pct, fee_frac);
 
             Config::pack(config_data, &mut config_info.data.borrow_mut())?;
             return Ok(());
         } 
         
-        // If we get here, the account already exists and is owned by our program
+        // If we get here, the account already exists and is owned by our program.
+        // A config left at an earlier layout's size can't be read or rewritten in place
+        Self::check_data_len(config_info, Config::LEN, "Config")?;
+
         // Check if it's already initialized
         if let Ok(config) = Config::unpack(&config_info.data.borrow()) {
             if config.is_initialized {
@@ -158,7 +298,8 @@ impl Processor {
                 msg!("Admin: {}", config.admin.to_string());
                 msg!("Treasury: {}", config.treasury.to_string());
                 msg!("Ticket Price: {} lamports ({}SOL)", config.ticket_price, config.ticket_price as f64 / 1_000_000_000.0);
-                msg!("Fee: {} basis points ({}%)", config.fee_basis_points, config.fee_basis_points as f64 / 100.0);
+                let (fee_pct, fee_frac) = crate::utils::format_bps(config.fee_basis_points);
+                msg!("Fee: {} basis points ({}.{:02}%)", config.fee_basis_points, fee_pct, fee_frac);
                 return Ok(());
             }
         }
@@ -170,44 +311,101 @@ impl Processor {
         msg!("Admin: {}", config_data.admin.to_string());
         msg!("Treasury: {}", config_data.treasury.to_string());
         msg!("Ticket Price: {} lamports ({}SOL)", config_data.ticket_price, config_data.ticket_price as f64 / 1_000_000_000.0);
-        msg!("Fee: {} basis points ({}%)", config_data.fee_basis_points, config_data.fee_basis_points as f64 / 100.0);
+        let (fee_pct, fee_frac) = crate::utils::format_bps(config_data.fee_basis_points);
+        msg!("Fee: {} basis points ({}.{:02}%)", config_data.fee_basis_points, fee_pct, fee_frac);
         
         // Save the config data
         Config::pack(config_data, &mut config_info.data.borrow_mut())?;
         
-        msg!("Config initialized: Admin={}, Treasury={}, TicketPrice={}, Fee={}%",
+        let (fee_pct, fee_frac) = crate::utils::format_bps(fee_basis_points);
+        msg!("Config initialized: Admin={}, Treasury={}, TicketPrice={}, Fee={}.{:02}%",
             admin_info.key,
             treasury_info.key,
             ticket_price,
-            fee_basis_points as f32 / 100.0);
+            fee_pct,
+            fee_frac);
             
         Ok(())
     }
 
     fn process_initialize_raffle(
         accounts: &[AccountInfo],
-        title: [u8; 32],
-        duration: u64,
-        nonce: u64,
+        params: RaffleParams,
         program_id: &Pubkey,
     ) -> ProgramResult {
+        let RaffleParams {
+            title,
+            duration,
+            nonce,
+            max_tickets,
+            fee_override_bps,
+            max_participants,
+            fixed_prize_lamports,
+            draw_on_sellout,
+            soft_cap_tickets,
+            min_unique_participants,
+            claim_window,
+            purchase_grace_seconds,
+            consolation_bps,
+            creator_bond,
+            payout_installments,
+            payout_interval,
+            charity,
+            charity_bps,
+            min_pool_lamports,
+            max_prize_lamports,
+            max_total_fee_lamports,
+            min_buyer_balance,
+        } = params;
+
         let account_info_iter = &mut accounts.iter();
         let authority_info = next_account_info(account_info_iter)?;
         let raffle_info = next_account_info(account_info_iter)?;
         let config_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
         let clock_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let index_pointer_info = next_account_info(account_info_iter)?;
+        let authority_state_info = next_account_info(account_info_iter)?;
+        // A program-derived authority signing through CPI usually can't fund accounts itself,
+        // so an optional trailing payer covers rent and the fixed prize instead
+        let payer_info = next_account_info(account_info_iter).unwrap_or(authority_info);
 
-        // Ensure the authority signed the transaction
+        Self::check_system_program(system_program_info, 3)?;
+
+        // Ensure the authority signed the transaction; a PDA signer via invoke_signed is accepted
         if !authority_info.is_signer {
             msg!("Authority must sign the transaction");
             return Err(ProgramError::MissingRequiredSignature);
         }
+
+        if !payer_info.is_signer {
+            msg!("Payer must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
         
         // Get current time from the clock
         let clock = Clock::from_account_info(clock_info)?;
         let current_time = clock.unix_timestamp;
+
+        let title = Self::canonical_title(title)?;
         
+        // Derive the expected PDA for the raffle account using the nonce to ensure uniqueness
+        // This allows the raffle account to receive funds (tokens can only be transferred out via instructions)
+        let nonce_bytes = nonce.to_le_bytes();
+        let seeds = &[
+            b"raffle",
+            authority_info.key.as_ref(),
+            &nonce_bytes,
+        ];
+        let (raffle_pda, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+
+        // Verify the provided raffle account is the correct PDA
+        if *raffle_info.key != raffle_pda {
+            msg!("Raffle account does not match expected PDA");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Check if the raffle account needs to be created (not owned by program yet)
         if raffle_info.owner != program_id {
             msg!("Creating new raffle account");
@@ -217,35 +415,19 @@ impl Processor {
             let raffle_account_size = Raffle::LEN; // Use the proper size constant
             let rent_lamports = rent.minimum_balance(raffle_account_size);
             
-            // Derive the expected PDA for the raffle account using the nonce to ensure uniqueness
-            // This allows the raffle account to receive funds (tokens can only be transferred out via instructions)
-            let nonce_bytes = nonce.to_le_bytes();
-            let seeds = &[
-                b"raffle",
-                authority_info.key.as_ref(),
-                &nonce_bytes,
-            ];
-            let (raffle_pda, bump_seed) = Pubkey::find_program_address(seeds, program_id);
-            
             msg!("Creating raffle with nonce: {}", nonce);
             
-            // Verify the provided raffle account is the correct PDA
-            if *raffle_info.key != raffle_pda {
-                msg!("Raffle account does not match expected PDA");
-                return Err(ProgramError::InvalidArgument);
-            }
-            
             // Create the raffle account with exact size needed
             invoke_signed(
                 &system_instruction::create_account(
-                    authority_info.key,
+                    payer_info.key,
                     raffle_info.key,
                     rent_lamports,
                     raffle_account_size as u64,
                     program_id,
                 ),
                 &[
-                    authority_info.clone(),
+                    payer_info.clone(),
                     raffle_info.clone(),
                     system_program_info.clone(),
                 ],
@@ -268,16 +450,30 @@ impl Processor {
         } else {
             msg!("Checking existing raffle account");
             
-            // Verify the raffle hasn't already been initialized
-            let existing_raffle = Raffle::unpack(&raffle_info.data.borrow())?;
-            if existing_raffle.is_initialized {
-                msg!("Raffle already initialized. Each raffle must have a unique nonce.");
-                return Err(ProgramError::AccountAlreadyInitialized);
+            // The PDA for this authority and nonce already exists, so it must still be blank.
+            // A second InitializeRaffle with the same nonce fails here instead of overwriting the first raffle.
+            Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+            let data = raffle_info.data.borrow();
+            let is_blank = data.iter().all(|&byte| byte == 0);
+            if !is_blank {
+                let already_initialized = data.len() != Raffle::LEN
+                    || Raffle::unpack_unchecked(&data).map_or(true, |raffle| raffle.is_initialized);
+                if already_initialized {
+                    msg!("Raffle already initialized. Each raffle must have a unique nonce.");
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
             }
             
             msg!("Existing account is valid for initialization");
         }
 
+        // The raffle index counter lives in the canonical config PDA
+        Self::check_config(program_id, config_info, 2)?;
+        if !config_info.is_writable {
+            msg!("Config at account index 2 must be writable so next_raffle_index can advance");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Load config to get ticket price and fee information
         let config_data = match Config::unpack(&config_info.data.borrow()) {
             Ok(config) => config,
@@ -297,6 +493,51 @@ impl Processor {
             return Err(ProgramError::InvalidAccountData);
         }
 
+        // Operators may limit raffles to a fixed menu of lengths
+        if !config_data.is_duration_allowed(duration) {
+            msg!("Duration {} is not one of the allowed durations {:?}", duration, config_data.allowed_durations);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Each authority may only keep a limited number of raffles open at once
+        let authority_state_bump = Self::check_authority_state(program_id, authority_state_info, authority_info.key, 7)?;
+        let active_raffle_count = if authority_state_info.owner == program_id {
+            AuthorityState::unpack(&authority_state_info.data.borrow())?.active_raffle_count
+        } else {
+            0
+        };
+        if config_data.max_active_raffles > 0 && active_raffle_count >= config_data.max_active_raffles {
+            msg!("Authority {} already has {} active raffles, the limit is {}",
+                 authority_info.key, active_raffle_count, config_data.max_active_raffles);
+            return Err(RaffleError::TooManyActiveRaffles.into());
+        }
+
+        // A soft cap above the hard cap could never be reached
+        if max_tickets > 0 && soft_cap_tickets > max_tickets {
+            msg!("Soft cap {} exceeds max tickets {}", soft_cap_tickets, max_tickets);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The treasury must never be the raffle itself, otherwise fees would land in the prize pool
+        if config_data.treasury == *raffle_info.key {
+            msg!("Treasury cannot be the raffle account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // A raffle must not be born already ended, or it could be drawn immediately.
+        // A huge duration would otherwise wrap to a negative i64 and land in the past.
+        if duration == 0 {
+            msg!("Raffle duration must be greater than zero");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let end_time = i64::try_from(duration).ok()
+            .and_then(|duration| current_time.checked_add(duration))
+            .ok_or(ProgramError::InvalidArgument)?;
+        if end_time <= current_time {
+            msg!("Raffle end time {} must be after the current time {}", end_time, current_time);
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Get the next raffle index from config and increment it for future raffles
         let current_raffle_index = config_data.next_raffle_index;
         msg!("Assigning raffle index: {}", current_raffle_index);
@@ -309,22 +550,177 @@ impl Processor {
             is_initialized: true,
             authority: *authority_info.key,
             title,
-            end_time: clock.unix_timestamp + duration as i64,
+            end_time,
             ticket_price: config_data.ticket_price,
             status: RaffleStatus::Active,
             winner: Pubkey::default(), // No winner yet
             tickets_sold: 0,
-            fee_basis_points: config_data.fee_basis_points,
+            fee_basis_points: fee_override_bps,
             treasury: config_data.treasury,
             vrf_account: Pubkey::default(), // Will be set later when requesting randomness
             vrf_request_in_progress: false,
             nonce, // Store the nonce for future reference
             raffle_index: current_raffle_index, // Assign the sequential ID
+            prize_amount: 0, // Set when the raffle is completed
+            completed_at: 0, // Set when the raffle is completed
+            max_tickets, // Zero means unlimited supply
+            pool_principal: 0,
+            max_participants, // Zero means unlimited buyers
+            participant_count: 0,
+            uses_vault: true, // New raffles always hold their pool in the vault PDA
+            fixed_prize_lamports,
+            draw_on_sellout, // Only meaningful together with max_tickets
+            soft_cap_tickets, // Zero means no early draw
+            frozen: false,
+            min_unique_participants, // Zero means the fixed prize is always paid
+            claim_window, // Zero pays the winner at completion
+            claim_deadline: 0, // Set when the raffle is completed
+            runner_up: Pubkey::default(), // Set when the raffle is completed
+            prize_claimed: false,
+            purchase_grace_seconds, // Zero closes sales exactly at end_time
+            consolation_bps, // Zero sends the whole fee to the treasury
+            consolation_pool: 0,
+            consolation_winner: Pubkey::default(), // Set at completion
+            vrf_requested_at: 0, // Set by RequestRandomness
+            vrf_requested_slot: 0,
+            creator_bond,
+            creator_bond_slashed: false,
+            payout_installments,
+            payout_interval,
+            installments_claimed: 0,
+            last_installment_at: 0,
+            bulk_threshold: config_data.bulk_threshold, // Bulk pricing is fixed when the raffle opens
+            bulk_fee_bps: config_data.bulk_fee_bps,
+            donated_amount: 0,
+            charity,
+            charity_bps,
+            referral_bps: config_data.referral_bps, // Referral rewards are fixed when the raffle opens
+            min_pool_lamports, // Zero draws any pool
+            closing_tickets_sold: 0, // Set when sales close
+            closing_participant_count: 0,
+            max_prize_lamports, // Zero pays the whole prize
+            vrf_requester: Pubkey::default(), // Set by RequestRandomness
+            request_reward_bps: config_data.request_reward_bps, // Keeper rewards are fixed when the raffle opens
+            complete_reward_bps: config_data.keeper_reward_bps,
+            max_total_fee_lamports, // Zero takes the full fee on every purchase
+            fees_collected: 0,
+            min_buyer_balance, // Zero lets any wallet buy
+            oracle_queue: Pubkey::default(), // Set by RequestRandomness
+            consolation_paid: 0,
         };
 
+        // A cap below the creator's own prize would send their deposit to the treasury
+        if max_prize_lamports > 0 && max_prize_lamports < fixed_prize_lamports {
+            msg!("Prize cap {} lamports is below the fixed prize of {} lamports", max_prize_lamports, fixed_prize_lamports);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Installments need a spacing that can be added to a timestamp
+        if payout_installments > 1
+            && (payout_interval == 0 || i64::try_from(payout_interval).is_err())
+        {
+            msg!("Payout interval {} is not valid for {} installments", payout_interval, payout_installments);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The consolation share is carved out of the fee, so it can be at most all of it
+        if consolation_bps as u64 > crate::raffle_core::BASIS_POINTS_DENOMINATOR {
+            msg!("Consolation share {} bps exceeds 100% of the fee", consolation_bps);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // A raffle can run with a promotional fee, but never above the platform fee, and the
+        // combined cut must always leave something for the winner
+        crate::utils::validate_fee_configuration(&config_data, &raffle_data)?;
+
+        // Deposit the creator-funded prize and bond into the vault up front
+        let creator_deposit = fixed_prize_lamports.checked_add(creator_bond)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if creator_deposit > 0 {
+            let (vault_pda, _) = crate::utils::find_vault_address(program_id, raffle_info.key);
+            if *vault_info.key != vault_pda {
+                msg!("Expected vault {} at account index 5, got {}", vault_pda, vault_info.key);
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            msg!("Depositing fixed prize of {} lamports and bond of {} lamports into vault {}",
+                 fixed_prize_lamports, creator_bond, vault_info.key);
+            invoke(
+                &system_instruction::transfer(
+                    payer_info.key,
+                    vault_info.key,
+                    creator_deposit,
+                ),
+                &[
+                    payer_info.clone(),
+                    vault_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+
         // Save the raffle data
         Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
 
+        // Record an index -> address pointer so clients can resolve "raffle #N" without the nonce
+        let index_bytes = current_raffle_index.to_le_bytes();
+        let (index_pointer_pda, index_bump) = crate::utils::find_index_pointer_pda(program_id, current_raffle_index);
+        if *index_pointer_info.key != index_pointer_pda {
+            msg!("Index pointer account does not match expected PDA for index {}", current_raffle_index);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if index_pointer_info.owner == program_id {
+            msg!("Index pointer for raffle index {} already exists", current_raffle_index);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                index_pointer_info.key,
+                rent.minimum_balance(RaffleIndexPointer::LEN),
+                RaffleIndexPointer::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                index_pointer_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"index", &index_bytes, &[index_bump]]],
+        )?;
+        let index_pointer = RaffleIndexPointer {
+            is_initialized: true,
+            raffle: *raffle_info.key,
+        };
+        RaffleIndexPointer::pack(index_pointer, &mut index_pointer_info.data.borrow_mut())?;
+
+        // Count the new raffle against its authority until it is completed, cancelled or closed
+        if authority_state_info.owner != program_id {
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer_info.key,
+                    authority_state_info.key,
+                    rent.minimum_balance(AuthorityState::LEN),
+                    AuthorityState::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    payer_info.clone(),
+                    authority_state_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[b"authority", authority_info.key.as_ref(), &[authority_state_bump]]],
+            )?;
+        }
+        let authority_state = AuthorityState {
+            is_initialized: true,
+            authority: *authority_info.key,
+            active_raffle_count: active_raffle_count.checked_add(1)
+                .ok_or(ProgramError::ArithmeticOverflow)?,
+        };
+        AuthorityState::pack(authority_state, &mut authority_state_info.data.borrow_mut())?;
+
         // Now that the raffle is successfully initialized, update the config's counter
         // This ensures atomicity - if raffle init fails, counter won't be incremented
         let mut updated_config = config_data;
@@ -340,6 +736,8 @@ impl Processor {
     fn process_purchase_tickets(
         accounts: &[AccountInfo],
         ticket_count: u64,
+        expected_ticket_price: u64,
+        referrer: Pubkey,
         program_id: &Pubkey,
     ) -> ProgramResult {
         // Validate ticket count - must be positive
@@ -355,6 +753,16 @@ impl Processor {
         let treasury_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
         let clock_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let blacklist_info = next_account_info(account_info_iter)?;
+        // Referral accounts are only passed when the buyer names a referrer
+        let referral_infos = if referrer != Pubkey::default() {
+            Some((next_account_info(account_info_iter)?, next_account_info(account_info_iter)?))
+        } else {
+            None
+        };
+
+        Self::check_system_program(system_program_info, 4)?;
 
         // Ensure the purchaser signed the transaction
         if !purchaser_info.is_signer {
@@ -362,30 +770,121 @@ impl Processor {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
+        let mut referral_bump = 0;
+        if let Some((referrer_info, referral_info)) = referral_infos {
+            if referrer == *purchaser_info.key {
+                msg!("Purchaser cannot refer themselves");
+                return Err(ProgramError::InvalidArgument);
+            }
+            if *referrer_info.key != referrer {
+                msg!("Expected referrer {} at account index 8, got {}", referrer, referrer_info.key);
+                return Err(ProgramError::InvalidArgument);
+            }
+            let (referral_pda, bump) = crate::utils::find_referral_address(program_id, &referrer);
+            if *referral_info.key != referral_pda {
+                msg!("Expected referral account {} at account index 9, got {}", referral_pda, referral_info.key);
+                return Err(ProgramError::InvalidArgument);
+            }
+            referral_bump = bump;
+        }
+
+        // Reject banned wallets; a blacklist that was never created bans nobody
+        let (blacklist_pda, _) = crate::utils::find_blacklist_address(program_id);
+        if *blacklist_info.key != blacklist_pda {
+            msg!("Blacklist account does not match expected PDA");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if blacklist_info.owner == program_id {
+            let blacklist = Blacklist::unpack(&blacklist_info.data.borrow())?;
+            if blacklist.contains(purchaser_info.key) {
+                msg!("Wallet {} is blacklisted", purchaser_info.key);
+                return Err(RaffleError::WalletBlacklisted.into());
+            }
+        }
+
         // Check that accounts are owned by correct programs
         if raffle_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
 
+        // A fee transfer into the raffle itself would be a self-transfer that scrambles pool accounting
+        if treasury_info.key == raffle_info.key {
+            msg!("Treasury cannot be the raffle account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The prize pool lives in the raffle's vault PDA
+        let (vault_pda, _) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 6, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Get the raffle data
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
         let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
 
+        // Fees go to the treasury recorded at creation, not whichever account the client passed
+        if *treasury_info.key != raffle_data.treasury {
+            msg!("Expected treasury {} at account index 3, got {}", raffle_data.treasury, treasury_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Check if raffle is still active
         if raffle_data.status != RaffleStatus::Active {
             msg!("Raffle is not active");
             return Err(ProgramError::InvalidAccountData);
         }
 
+        // A frozen raffle takes no new purchases until it is unfrozen
+        if raffle_data.frozen {
+            msg!("Raffle is frozen");
+            return Err(RaffleError::RaffleFrozen.into());
+        }
+
+        // The buyer signed for a specific price and is never charged a different one
+        if raffle_data.ticket_price != expected_ticket_price {
+            msg!("Ticket price is {} lamports, buyer expected {}", raffle_data.ticket_price, expected_ticket_price);
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Get the current time
         let clock = Clock::from_account_info(clock_info)?;
         let current_time = clock.unix_timestamp;
 
-        // Check if raffle has ended
-        if current_time >= raffle_data.end_time {
+        // Check if raffle has ended; a grace window tolerates clock skew until randomness is requested
+        if current_time >= raffle_data.sales_close_time() || raffle_data.vrf_request_in_progress {
             msg!("Raffle has ended");
             return Err(ProgramError::InvalidArgument);
         }
         
+        // Enforce the ticket supply cap if one is set
+        if raffle_data.max_tickets > 0 {
+            let remaining = raffle_data.max_tickets.saturating_sub(raffle_data.tickets_sold);
+            if ticket_count > remaining {
+                msg!("Only {} tickets remaining, requested {}", remaining, ticket_count);
+                return Err(RaffleError::InsufficientTickets.into());
+            }
+        }
+
+        // A purchase without an initialized record creates a new participant; top-ups are always allowed
+        let is_new_participant = ticket_purchase_info.owner != program_id
+            || !matches!(ticket_purchase_info.try_data_len(), Ok(len) if len >= 1 && ticket_purchase_info.data.borrow()[0] != 0);
+        if is_new_participant
+            && raffle_data.max_participants > 0
+            && raffle_data.participant_count >= raffle_data.max_participants
+        {
+            msg!("Raffle already has the maximum of {} participants", raffle_data.max_participants);
+            return Err(RaffleError::ParticipantCapReached.into());
+        }
+
+        // A new record is the buyer's own fresh keypair, so its signature shows the buyer
+        // controls it and nobody else can open a record on their behalf
+        if is_new_participant && !ticket_purchase_info.is_signer {
+            msg!("Ticket purchase record {} at account index 2 must sign when it is created", ticket_purchase_info.key);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
         // Calculate total price and fee amount with overflow protection
         let total_price = ticket_count.checked_mul(raffle_data.ticket_price)
             .ok_or(ProgramError::InvalidArgument)?;
@@ -393,30 +892,59 @@ impl Processor {
         msg!("Ticket price: {} lamports", raffle_data.ticket_price);
         msg!("Total price for {} tickets: {} lamports", ticket_count, total_price);
         
-        // Ensure the purchaser has sufficient funds
-        if purchaser_info.lamports() < total_price {
-            msg!("Insufficient funds: needed {} lamports, had {} lamports", 
-                 total_price, purchaser_info.lamports());
+        // A minimum balance makes it costly to spread purchases over many throwaway wallets
+        if purchaser_info.lamports() < raffle_data.min_buyer_balance {
+            msg!("Purchaser {} holds {} lamports, below the raffle's {} lamport minimum",
+                 purchaser_info.key, purchaser_info.lamports(), raffle_data.min_buyer_balance);
+            return Err(RaffleError::BuyerBelowMinBalance.into());
+        }
+
+        // Ensure the purchaser has sufficient funds and stays rent-exempt afterwards, so a
+        // purchase never drains the wallet to the point of it being closed
+        let purchaser_reserve = Rent::get()?.minimum_balance(purchaser_info.data_len());
+        let required_balance = total_price.checked_add(purchaser_reserve)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if purchaser_info.lamports() < required_balance {
+            msg!("Insufficient funds: needed {} lamports plus a {} lamport rent reserve, had {} lamports", 
+                 total_price, purchaser_reserve, purchaser_info.lamports());
             return Err(ProgramError::InsufficientFunds);
         }
         
-        // Calculate fee with overflow protection
-        let fee_amount = crate::utils::calculate_fee(total_price, raffle_data.fee_basis_points);
-        msg!("Fee amount ({}%): {} lamports", raffle_data.fee_basis_points as f64 / 100.0, fee_amount);
-        
-        // Calculate raffle pool amount (total minus fee)
-        let raffle_amount = total_price.checked_sub(fee_amount)
-            .ok_or(ProgramError::InvalidArgument)?;
+        // Split the total into pool and fee; the two always add back up to total_price.
+        // Large purchases may qualify for the bulk fee rate.
+        let fee_bps = raffle_data.fee_bps_for(ticket_count);
+        let (raffle_amount, fee_amount) = crate::utils::split_fee(total_price, fee_bps)?;
+
+        // Once the raffle's fee cap is reached the rest of every purchase goes to the pool
+        let fee_over_cap = raffle_data.fee_over_cap(fee_amount);
+        let (raffle_amount, fee_amount) = (raffle_amount + fee_over_cap, fee_amount - fee_over_cap);
+        if fee_over_cap > 0 {
+            msg!("Fee cap of {} lamports reached, {} lamports of fee added to the pool",
+                 raffle_data.max_total_fee_lamports, fee_over_cap);
+        }
+        let (fee_pct, fee_frac) = crate::utils::format_bps(fee_bps);
+        msg!("Fee amount ({}.{:02}%): {} lamports", fee_pct, fee_frac, fee_amount);
         msg!("Raffle prize amount: {} lamports", raffle_amount);
+
+        // Part of the fee can be held back in the vault for the consolation draw
+        let consolation_amount = crate::utils::calculate_fee(fee_amount, raffle_data.consolation_bps)?;
+        let treasury_amount = fee_amount - consolation_amount;
+
+        // A referrer's reward comes out of the treasury's part of the fee
+        let (treasury_amount, referral_amount) = if referral_infos.is_some() {
+            crate::utils::split_fee(treasury_amount, raffle_data.referral_bps)?
+        } else {
+            (treasury_amount, 0)
+        };
         
         // Transfer fee to treasury if fee is greater than 0
-        if fee_amount > 0 {
-            msg!("Transferring fee of {} lamports to treasury {}", fee_amount, treasury_info.key);
+        if treasury_amount > 0 {
+            msg!("Transferring fee of {} lamports to treasury {}", treasury_amount, treasury_info.key);
             invoke(
                 &system_instruction::transfer(
                     purchaser_info.key,
                     treasury_info.key,
-                    fee_amount,
+                    treasury_amount,
                 ),
                 &[
                     purchaser_info.clone(),
@@ -426,18 +954,72 @@ impl Processor {
             )?;
             msg!("Fee transfer successful");
         }
+
+        if let Some((referrer_info, referral_info)) = referral_infos {
+            if referral_amount > 0 {
+                Self::check_wallet_payout(referrer_info, referral_amount, 8)?;
+                msg!("Transferring referral reward of {} lamports to {}", referral_amount, referrer_info.key);
+                invoke(
+                    &system_instruction::transfer(
+                        purchaser_info.key,
+                        referrer_info.key,
+                        referral_amount,
+                    ),
+                    &[
+                        purchaser_info.clone(),
+                        referrer_info.clone(),
+                        system_program_info.clone(),
+                    ],
+                )?;
+            }
+
+            // The buyer pays to open a referrer's earnings account on their first referral
+            if referral_info.owner != program_id {
+                let rent = Rent::get()?;
+                invoke_signed(
+                    &system_instruction::create_account(
+                        purchaser_info.key,
+                        referral_info.key,
+                        rent.minimum_balance(Referral::LEN),
+                        Referral::LEN as u64,
+                        program_id,
+                    ),
+                    &[
+                        purchaser_info.clone(),
+                        referral_info.clone(),
+                        system_program_info.clone(),
+                    ],
+                    &[&[b"referral", referrer.as_ref(), &[referral_bump]]],
+                )?;
+                Referral::pack(
+                    Referral {
+                        is_initialized: true,
+                        referrer,
+                        total_earned: 0,
+                        referral_count: 0,
+                    },
+                    &mut referral_info.data.borrow_mut(),
+                )?;
+            }
+            let mut referral_data = Referral::unpack(&referral_info.data.borrow())?;
+            referral_data.total_earned = referral_data.total_earned.checked_add(referral_amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            referral_data.referral_count = referral_data.referral_count.checked_add(1)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            Referral::pack(referral_data, &mut referral_info.data.borrow_mut())?;
+        }
         
-        // Transfer remaining funds to the raffle account (prize pool)
-        msg!("Transferring {} lamports to raffle prize pool {}", raffle_amount, raffle_info.key);
+        // Transfer remaining funds to the raffle vault (prize pool plus any consolation share)
+        msg!("Transferring {} lamports to raffle prize pool {}", raffle_amount, vault_info.key);
         invoke(
             &system_instruction::transfer(
                 purchaser_info.key,
-                raffle_info.key,
-                raffle_amount,
+                vault_info.key,
+                raffle_amount + consolation_amount,
             ),
             &[
                 purchaser_info.clone(),
-                raffle_info.clone(),
+                vault_info.clone(),
                 system_program_info.clone(),
             ],
         )?;
@@ -532,6 +1114,22 @@ impl Processor {
         // Update raffle data
         raffle_data.tickets_sold = raffle_data.tickets_sold.checked_add(ticket_count)
             .ok_or(ProgramError::InvalidArgument)?;
+        raffle_data.pool_principal = raffle_data.pool_principal.checked_add(raffle_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        raffle_data.consolation_pool = raffle_data.consolation_pool.checked_add(consolation_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        raffle_data.fees_collected = raffle_data.fees_collected.checked_add(fee_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if is_new_participant {
+            raffle_data.participant_count = raffle_data.participant_count.checked_add(1)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+
+        // A sold-out raffle can be drawn right away instead of waiting for end_time
+        if raffle_data.draw_on_sellout && raffle_data.is_sold_out() {
+            raffle_data.close_sales();
+            msg!("Raffle sold out, ready for randomness");
+        }
         Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
 
         msg!(
@@ -540,11 +1138,28 @@ impl Processor {
             raffle_data.ticket_price,
             total_price
         );
+
+        // Structured purchase event for analytics pipelines
+        msg!("PurchaseEvent: raffle_index={}, purchaser={}, ticket_count={}, total_price={}, fee_amount={}, new_tickets_sold={}",
+             raffle_data.raffle_index,
+             purchaser_info.key,
+             ticket_count,
+             total_price,
+             fee_amount,
+             raffle_data.tickets_sold);
+        if referral_infos.is_some() {
+            msg!("ReferralEvent: raffle_index={}, purchaser={}, referrer={}, referral_amount={}",
+                 raffle_data.raffle_index,
+                 purchaser_info.key,
+                 referrer,
+                 referral_amount);
+        }
         Ok(())
     }
 
     /// This function is deprecated in favor of process_complete_raffle_with_vrf
-    /// which uses Switchboard VRF for secure randomness
+    /// which uses Switchboard VRF for secure randomness. It must keep failing: a winner picked
+    /// without VRF could be predicted by whoever sends the transaction.
     fn process_complete_raffle(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
@@ -569,10 +1184,8 @@ impl Processor {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
-        // Check that config account is owned by our program
-        if config_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 2)?;
 
         // Get the config data
         let mut config_data = Config::unpack(&config_info.data.borrow())?;
@@ -606,10 +1219,8 @@ impl Processor {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
-        // Check that config account is owned by our program
-        if config_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 2)?;
 
         // Get the config data
         let mut config_data = Config::unpack(&config_info.data.borrow())?;
@@ -628,20 +1239,14 @@ impl Processor {
         Ok(())
     }
 
-    /// Process UpdateTicketPrice instruction
-    fn process_update_ticket_price(
+    /// Process UpdateOracleQueue instruction
+    fn process_update_oracle_queue(
         accounts: &[AccountInfo],
-        new_ticket_price: u64,
         program_id: &Pubkey,
     ) -> ProgramResult {
-        // Validate that ticket price is not zero
-        if new_ticket_price == 0 {
-            msg!("Ticket price must be greater than zero");
-            return Err(ProgramError::InvalidArgument);
-        }
-        
         let account_info_iter = &mut accounts.iter();
         let admin_info = next_account_info(account_info_iter)?;
+        let new_oracle_queue_info = next_account_info(account_info_iter)?;
         let config_info = next_account_info(account_info_iter)?;
 
         // Ensure the admin signed the transaction
@@ -650,178 +1255,2819 @@ impl Processor {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
-        // Check that config account is owned by our program
-        if config_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 2)?;
 
         // Get the config data
         let mut config_data = Config::unpack(&config_info.data.borrow())?;
 
         // Check if the caller is the admin
         if config_data.admin != *admin_info.key {
-            msg!("Only the admin can update ticket price");
+            msg!("Only the admin can update the oracle queue");
             return Err(ProgramError::InvalidAccountData);
         }
 
-        // No additional validation needed
-
-        // Update ticket price
-        config_data.ticket_price = new_ticket_price;
+        // Update the allowlisted oracle queue
+        config_data.oracle_queue = *new_oracle_queue_info.key;
         Config::pack(config_data, &mut config_info.data.borrow_mut())?;
 
-        msg!("Ticket price updated to {} lamports", config_data.ticket_price);
-
+        msg!("Oracle queue updated successfully to: {}", new_oracle_queue_info.key);
         Ok(())
     }
 
-    /// Process UpdateFeePercentage instruction
-    fn process_update_fee_percentage(
+    /// Process SetPermissionlessCompletion instruction
+    fn process_set_permissionless_completion(
         accounts: &[AccountInfo],
-        new_fee_basis_points: u16,
+        enabled: bool,
         program_id: &Pubkey,
     ) -> ProgramResult {
-        // Fee can be any value - no validation
-
         let account_info_iter = &mut accounts.iter();
         let admin_info = next_account_info(account_info_iter)?;
         let config_info = next_account_info(account_info_iter)?;
-        
-        // Check program ownership
-        if config_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
         }
-        
-        // Get config data
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
         let mut config_data = Config::unpack(&config_info.data.borrow())?;
-        
-        // Verify admin authority
+
+        // Check if the caller is the admin
         if config_data.admin != *admin_info.key {
-            msg!("Only the admin can update fee percentage");
+            msg!("Only the admin can change who may complete raffles");
             return Err(ProgramError::InvalidAccountData);
         }
-        
-        // Verify the admin signed the transaction
-        if !admin_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        
-        // Validate input
-        if new_fee_basis_points > 10000 {
-            msg!("Fee basis points cannot exceed 10000 (100%)");
-            return Err(ProgramError::InvalidArgument);
-        }
-        
-        // Update fee basis points
-        config_data.fee_basis_points = new_fee_basis_points;
-        
-        // Save updated config
+
+        config_data.permissionless_completion = enabled;
         Config::pack(config_data, &mut config_info.data.borrow_mut())?;
-        
-        msg!("Fee percentage updated to {}%", new_fee_basis_points as f32 / 100.0);
+
+        msg!("Permissionless completion set to {}", enabled);
         Ok(())
     }
 
-    /// Process RequestRandomness instruction - Step 1 of the raffle completion process
-    /// This initiates a VRF request to get random bytes for winner selection
-    fn process_request_randomness(
+    /// Process SetAllowedDurations instruction
+    fn process_set_allowed_durations(
         accounts: &[AccountInfo],
+        durations: [u64; MAX_ALLOWED_DURATIONS],
         program_id: &Pubkey,
     ) -> ProgramResult {
-        
         let account_info_iter = &mut accounts.iter();
-        let authority_info = next_account_info(account_info_iter)?;
-        let raffle_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the allowed durations");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Durations that can't be represented as a timestamp offset could never be created
+        if durations.iter().any(|duration| i64::try_from(*duration).is_err()) {
+            msg!("Allowed durations must fit in an i64");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        config_data.allowed_durations = durations;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        msg!("Allowed durations set to {:?}", durations);
+        Ok(())
+    }
+
+    /// Process SetBulkFee instruction
+    fn process_set_bulk_fee(
+        accounts: &[AccountInfo],
+        bulk_threshold: u64,
+        bulk_fee_bps: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the bulk fee");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // A bulk "discount" above the standard rate would penalize large purchases
+        if bulk_fee_bps > config_data.fee_basis_points {
+            msg!("Bulk fee {} bps exceeds the standard fee {} bps", bulk_fee_bps, config_data.fee_basis_points);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        config_data.bulk_threshold = bulk_threshold;
+        config_data.bulk_fee_bps = bulk_fee_bps;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        let (fee_pct, fee_frac) = crate::utils::format_bps(bulk_fee_bps);
+        msg!("Bulk fee set to {}.{:02}% from {} tickets", fee_pct, fee_frac, bulk_threshold);
+        Ok(())
+    }
+
+    /// Process SetMaxActiveRaffles instruction
+    fn process_set_max_active_raffles(
+        accounts: &[AccountInfo],
+        max_active_raffles: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the active raffle limit");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Lowering the limit below an authority's current count only blocks their new raffles
+        config_data.max_active_raffles = max_active_raffles;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        msg!("Active raffles per authority limited to {} (0 = no limit)", max_active_raffles);
+        Ok(())
+    }
+
+    /// Process SetReferralReward instruction
+    fn process_set_referral_reward(
+        accounts: &[AccountInfo],
+        referral_bps: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the referral reward");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if referral_bps as u64 > crate::raffle_core::BASIS_POINTS_DENOMINATOR {
+            msg!("Referral reward of {} bps exceeds the whole fee", referral_bps);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        config_data.referral_bps = referral_bps;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        let (reward_pct, reward_frac) = crate::utils::format_bps(referral_bps);
+        msg!("Referral reward set to {}.{:02}% of the treasury fee", reward_pct, reward_frac);
+        Ok(())
+    }
+
+    /// Process SetKeeperReward instruction
+    fn process_set_keeper_reward(
+        accounts: &[AccountInfo],
+        keeper_reward_bps: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the keeper reward");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if keeper_reward_bps > MAX_KEEPER_REWARD_BPS {
+            msg!("Keeper reward {} bps exceeds the {} bps cap", keeper_reward_bps, MAX_KEEPER_REWARD_BPS);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        config_data.keeper_reward_bps = keeper_reward_bps;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        let (reward_pct, reward_frac) = crate::utils::format_bps(keeper_reward_bps);
+        msg!("Keeper reward set to {}.{:02}% of the prize pool", reward_pct, reward_frac);
+        Ok(())
+    }
+
+    /// Process SetRequestReward instruction
+    fn process_set_request_reward(
+        accounts: &[AccountInfo],
+        request_reward_bps: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the request reward");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if request_reward_bps > MAX_KEEPER_REWARD_BPS {
+            msg!("Request reward {} bps exceeds the {} bps cap", request_reward_bps, MAX_KEEPER_REWARD_BPS);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        config_data.request_reward_bps = request_reward_bps;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        let (reward_pct, reward_frac) = crate::utils::format_bps(request_reward_bps);
+        msg!("Request reward set to {}.{:02}% of the prize pool", reward_pct, reward_frac);
+        Ok(())
+    }
+
+    /// Process UpdateRaffleTitle instruction
+    fn process_update_raffle_title(
+        accounts: &[AccountInfo],
+        title: [u8; 32],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let authority_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+
+        // Ensure the authority signed the transaction
+        if !authority_info.is_signer {
+            msg!("Authority must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // Only the raffle creator can rename it
+        if raffle_data.authority != *authority_info.key {
+            msg!("Only the raffle authority can update the title");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if raffle_data.status != RaffleStatus::Active {
+            msg!("Raffle is not active");
+            return Err(RaffleError::RaffleNotActive.into());
+        }
+
+        raffle_data.title = Self::canonical_title(title)?;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        msg!("Raffle {} title updated", raffle_data.raffle_index);
+        Ok(())
+    }
+
+    /// Process SetDrawDelay instruction
+    fn process_set_draw_delay(
+        accounts: &[AccountInfo],
+        draw_delay: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can change the draw delay");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // A pending request can be replaced once it times out, so a longer delay would let
+        // anyone keep pushing the draw back
+        if draw_delay >= VRF_REQUEST_TIMEOUT_SECONDS as u64 {
+            msg!("Draw delay must be shorter than the {} second VRF request timeout", VRF_REQUEST_TIMEOUT_SECONDS);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        config_data.draw_delay = draw_delay;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        msg!("Draw delay set to {} seconds", draw_delay);
+        Ok(())
+    }
+
+    /// Process UpdateTicketPrice instruction
+    fn process_update_ticket_price(
+        accounts: &[AccountInfo],
+        new_ticket_price: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        // Validate that ticket price is not zero
+        if new_ticket_price == 0 {
+            msg!("Ticket price must be greater than zero");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get the config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can update ticket price");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Keep the price inside the configured band so buyers can't be priced out by mistake
+        if new_ticket_price < config_data.min_ticket_price || new_ticket_price > config_data.max_ticket_price {
+            msg!("Ticket price {} is outside the allowed range {}..={} lamports",
+                 new_ticket_price, config_data.min_ticket_price, config_data.max_ticket_price);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Update ticket price
+        config_data.ticket_price = new_ticket_price;
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        msg!("Ticket price updated to {} lamports", config_data.ticket_price);
+
+        Ok(())
+    }
+
+    /// Process UpdateFeePercentage instruction
+    fn process_update_fee_percentage(
+        accounts: &[AccountInfo],
+        new_fee_basis_points: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Get config data
+        let mut config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Verify admin authority
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can update fee percentage");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // A 100% fee would leave nothing of a ticket for the prize pool
+        if new_fee_basis_points as u64 >= crate::raffle_core::BASIS_POINTS_DENOMINATOR {
+            msg!("Fee basis points must be below {} (100%)", crate::raffle_core::BASIS_POINTS_DENOMINATOR);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Lowering the standard fee must not leave the bulk rate above it, as SetBulkFee enforces
+        if config_data.bulk_fee_bps > new_fee_basis_points {
+            msg!("Fee {} bps is below the bulk fee {} bps, lower the bulk fee first",
+                 new_fee_basis_points, config_data.bulk_fee_bps);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Update fee basis points
+        config_data.fee_basis_points = new_fee_basis_points;
+
+        // Save updated config
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        let (fee_pct, fee_frac) = crate::utils::format_bps(new_fee_basis_points);
+        msg!("Fee percentage updated to {}.{:02}%", fee_pct, fee_frac);
+        Ok(())
+    }
+
+    /// Process RequestRandomness instruction - Step 1 of the raffle completion process
+    /// This initiates a VRF request to get random bytes for winner selection
+    fn process_request_randomness(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        
+        let account_info_iter = &mut accounts.iter();
+        let authority_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vrf_account_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let switchboard_program_info = next_account_info(account_info_iter)?;
+        let oracle_queue_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        // Collect the remaining accounts to pass to the VRF function
+        let remaining_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+        
+        // Any user can create a raffle
+        if !authority_info.is_signer {
+            msg!("Initiator must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Ensure the payer signed the transaction
+        if !payer_info.is_signer {
+            msg!("Payer must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            msg!("Raffle account must be owned by the program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // Get the raffle data
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+        
+        // Anyone can request randomness for a raffle (fully decentralized approach)
+
+        // Check if raffle is in the correct state (ReadyForRandomness)
+        if raffle_data.status != RaffleStatus::ReadyForRandomness {
+            msg!("Raffle is not in ReadyForRandomness state. Current status: {:?}", raffle_data.status);
+            return Err(ProgramError::InvalidAccountData);
+        }
+        
+        // The first request stands until it times out, so repeat callers can't keep
+        // swapping the VRF account and pushing the draw back
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+        if raffle_data.vrf_request_in_progress {
+            let retry_at = raffle_data.vrf_requested_at.saturating_add(VRF_REQUEST_TIMEOUT_SECONDS);
+            if current_time < retry_at {
+                msg!("VRF request is already in progress, it can be replaced after {}", retry_at);
+                return Err(ProgramError::InvalidAccountData);
+            }
+            msg!("Replacing VRF request made at {} that never completed", raffle_data.vrf_requested_at);
+        }
+
+        // Check if any tickets were sold
+        if raffle_data.tickets_sold == 0 {
+            msg!("No tickets were sold, cannot complete raffle");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Sell-outs and slashed bonds close sales without PrepareRaffle, so the minimum is checked here too
+        if raffle_data.is_below_min_pool() {
+            msg!("Prize pool of {} lamports is below the {} lamport minimum, the raffle can only be cancelled",
+                 raffle_data.prize_pool_value(), raffle_data.min_pool_lamports);
+            return Err(RaffleError::PoolBelowMinimum.into());
+        }
+
+        // Randomness may only come from the oracle queue the admin has vetted
+        Self::check_config(program_id, config_info, 6)?;
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+        if config_data.oracle_queue == Pubkey::default() || *oracle_queue_info.key != config_data.oracle_queue {
+            msg!("Oracle queue {} is not the allowlisted queue", oracle_queue_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        // A raffle stays on the queue it first requested from, so a replacement request can't
+        // quietly switch oracles; moving it takes RetargetVrf
+        if raffle_data.oracle_queue != Pubkey::default() && *oracle_queue_info.key != raffle_data.oracle_queue {
+            msg!("Raffle requests randomness from oracle queue {}, retarget it before using {}",
+                 raffle_data.oracle_queue, oracle_queue_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::check_completion_permission(program_id, config_info, 6, &raffle_data, authority_info.key)?;
+
+        // The VRF account must be a separate account, otherwise completion would read
+        // the raffle's or treasury's own bytes as the "randomness"
+        if vrf_account_info.key == raffle_info.key || *vrf_account_info.key == raffle_data.treasury {
+            msg!("VRF account cannot be the raffle or treasury account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Request VRF randomness from Switchboard
+        vrf::request_vrf_randomness(
+            vrf_account_info,
+            payer_info, 
+            authority_info, // Now treated as initiator (can be any user)
+            switchboard_program_info,
+            oracle_queue_info,
+            None, // permission_account_info
+            None, // escrow_account_info
+            None, // payer_wallet_info
+            &remaining_accounts, // Pass the collected accounts
+        )?;
+
+        // Update raffle to indicate VRF request is in progress
+        raffle_data.vrf_account = *vrf_account_info.key;
+        raffle_data.vrf_request_in_progress = true;
+        raffle_data.vrf_requested_at = current_time;
+        raffle_data.vrf_requested_slot = clock.slot;
+        raffle_data.vrf_requester = *payer_info.key;
+        raffle_data.oracle_queue = *oracle_queue_info.key;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        msg!("VRF randomness requested successfully for raffle: {}", raffle_info.key);
+        Ok(())
+    }
+
+    /// Process CompleteRaffleWithVrf instruction - Step 2 of the raffle completion process
+    /// This uses the VRF random bytes to select a winner
+    fn process_complete_raffle_with_vrf(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        // Updated import to fix compiler errors
+        use crate::vrf::{verify_vrf_result, get_random_winner_index};
+        
+        let account_info_iter = &mut accounts.iter();
+        let authority_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vrf_account_info = next_account_info(account_info_iter)?;
+        let winner_info = next_account_info(account_info_iter)?;
+        let switchboard_program_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let raffle_authority_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let charity_info = next_account_info(account_info_iter)?;
+        let authority_state_info = next_account_info(account_info_iter)?;
+        let treasury_info = next_account_info(account_info_iter)?;
+        let vrf_requester_info = next_account_info(account_info_iter)?;
+        let winner_wallet_info = next_account_info(account_info_iter)?;
+        let consolation_wallet_info = next_account_info(account_info_iter)?;
+        let ticket_record_infos = account_info_iter.as_slice();
+
+        Self::check_system_program(system_program_info, 7)?;
+
+        // Any user can create a raffle
+        if !authority_info.is_signer {
+            msg!("Initiator must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // Get the raffle data
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // Anyone can complete the raffle (fully decentralized approach)
+
+        // Check if raffle is in ReadyForRandomness state
+        if raffle_data.status != RaffleStatus::ReadyForRandomness {
+            msg!("Raffle is not in ReadyForRandomness state. Current state: {:?}", raffle_data.status);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::check_completion_permission(program_id, config_info, 9, &raffle_data, authority_info.key)?;
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // The status check should already rule this out, but a drawn winner must never be overwritten
+        if raffle_data.winner != Pubkey::default() {
+            msg!("Raffle already has a winner: {}", raffle_data.winner);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        // Get the current time
+        let clock = Clock::from_account_info(clock_info)?;
+        let current_time = clock.unix_timestamp;
+
+        // Check if raffle has ended; a sold-out or soft-capped raffle may be drawn early
+        if current_time < raffle_data.end_time && !raffle_data.is_early_draw_eligible() {
+            msg!("Raffle has not ended yet");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // With the keypair approach, we verify the winner by checking the ticket purchase account
+        if winner_info.owner != program_id {
+            msg!("Winner account must be a valid ticket purchase account owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        
+        // The prize must never be routed to one of the program's own addresses for this raffle
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        let (config_pda, _) = crate::utils::find_config_address(program_id);
+        let (index_pointer_pda, _) = crate::utils::find_index_pointer_pda(program_id, raffle_data.raffle_index);
+        let (blacklist_pda, _) = crate::utils::find_blacklist_address(program_id);
+        let reserved_addresses = [
+            *raffle_info.key,
+            vault_pda,
+            raffle_data.treasury,
+            config_pda,
+            index_pointer_pda,
+            blacklist_pda,
+        ];
+        if reserved_addresses.contains(winner_info.key) {
+            msg!("Winner account {} is a program or treasury address, not a ticket purchase", winner_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Other program-owned accounts (e.g. a raffle) must not be mistaken for a ticket purchase
+        if winner_info.data_len() != TicketPurchase::LEN {
+            msg!("Winner account has {} bytes, expected a ticket purchase of {} bytes",
+                 winner_info.data_len(), TicketPurchase::LEN);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Fetch and verify the ticket purchase data
+        let ticket_data = TicketPurchase::unpack_unchecked(&winner_info.data.borrow())?;
+        
+        // Verify this is a valid ticket purchase for this raffle
+        if !ticket_data.is_initialized
+            || ticket_data.purchaser == Pubkey::default()
+            || ticket_data.raffle != *raffle_info.key
+            || ticket_data.ticket_count == 0
+        {
+            msg!("Invalid winner account - not a valid ticket purchase for this raffle");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        
+        msg!("Winner has {} tickets in the raffle", ticket_data.ticket_count);
+
+        // The record only proves the win; the prize belongs to the wallet that bought it
+        if *winner_wallet_info.key != ticket_data.purchaser {
+            msg!("Expected winning purchaser {} at account index 14, got {}",
+                 ticket_data.purchaser, winner_wallet_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // A sole buyer has nobody to forfeit to, so the runner-up stays unset
+        let mut runner_up = Pubkey::default();
+        // Likewise there is no other ticket to draw for the consolation pool
+        let mut consolation_draw = None;
+
+        // The draw is made over the totals snapshotted when sales closed
+        let tickets_sold = raffle_data.draw_tickets_sold();
+
+        // A record holding every ticket sold can only belong to the sole buyer, so the draw is skipped
+        if ticket_data.ticket_count == tickets_sold {
+            msg!("Single ticket holder owns all {} tickets, skipping the VRF draw", tickets_sold);
+        } else {
+            // Check if VRF request is in progress
+            if !raffle_data.vrf_request_in_progress {
+                msg!("VRF request has not been initiated yet");
+                return Err(RaffleError::VrfNotRequested.into());
+            }
+
+            // Check if VRF account matches
+            if raffle_data.vrf_account != *vrf_account_info.key {
+                msg!("VRF account does not match the one registered with this raffle");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            // The committed result must be public for the draw delay before it can be finalized
+            let draw_delay = i64::try_from(config_data.draw_delay).unwrap_or(i64::MAX);
+            let draw_at = raffle_data.vrf_requested_at.saturating_add(draw_delay);
+            if current_time < draw_at {
+                msg!("Raffle can be completed from {}", draw_at);
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            // Completion may not share a slot with the request it consumes
+            vrf::require_completion_after_request(raffle_data.vrf_requested_slot, &clock)?;
+
+            // Verify VRF result
+            let vrf_result = verify_vrf_result(vrf_account_info, switchboard_program_info)?;
+
+            // Get random winner index
+            let winner_index = get_random_winner_index(vrf_result, tickets_sold)?;
+            msg!("Random winner index: {}", winner_index);
+
+            // The full set of records is required; drawing from a partial set would bias the
+            // result toward the buyers whose records happened to be provided
+            let records = Self::assert_ticket_accounting(
+                raffle_info.key,
+                ticket_record_infos,
+                tickets_sold,
+                program_id,
+            )?;
+
+            // Walk the cumulative ticket ranges to find the record owning the winning index
+            let purchases: Vec<TicketPurchase> = records.iter().map(|(_, purchase)| *purchase).collect();
+            let winning_position = crate::utils::resolve_winner(&purchases, winner_index)
+                .ok_or(ProgramError::InvalidArgument)?;
+            if records[winning_position].0 != *winner_info.key {
+                msg!("Winner account does not own winning ticket index {}", winner_index);
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            // The runner-up is drawn from the same result with the winner's whole range excluded,
+            // so a dominant buyer cannot take both places
+            let ticket_counts: Vec<u64> = purchases.iter().map(|purchase| purchase.ticket_count).collect();
+            let runner_up_position = crate::raffle_core::draw_distinct_record(
+                &vrf_result,
+                &ticket_counts,
+                &[winning_position],
+                1,
+            )
+            .map_err(|err| {
+                msg!("{}", err);
+                ProgramError::InvalidArgument
+            })?;
+            runner_up = records[runner_up_position].0;
+            msg!("Runner-up record: {}", runner_up);
+
+            if raffle_data.consolation_pool > 0 {
+                let consolation_position = crate::raffle_core::draw_distinct_record(
+                    &vrf_result,
+                    &ticket_counts,
+                    &[winning_position],
+                    2,
+                )
+                .map_err(|err| {
+                    msg!("{}", err);
+                    ProgramError::InvalidArgument
+                })?;
+                let (consolation_record, consolation_purchase) = records[consolation_position];
+                msg!("Consolation record: {}", consolation_record);
+                consolation_draw = Some((consolation_record, consolation_purchase.purchaser));
+            }
+        }
+        
+        // Log the winner's ticket count and total tickets for transparency
+        msg!("Winner verification: Account owns {}/{} tickets", 
+             ticket_data.ticket_count, tickets_sold);
+        
+        // Legacy raffles still hold part of their pool in the raffle account
+        if !raffle_data.uses_vault {
+            msg!("Raffle pool must be migrated to the vault before completion");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // The prize is paid from the vault, a PDA only this program can sign for
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 6, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The creator's fixed prize only sweetens raffles that drew enough distinct buyers;
+        // otherwise it goes back to the creator and the winner gets the ticket pool alone
+        let fixed_prize_refund = if raffle_data.draw_participant_count() < raffle_data.min_unique_participants {
+            msg!("Only {} of {} required participants, fixed prize is returned to the creator",
+                 raffle_data.draw_participant_count(), raffle_data.min_unique_participants);
+            raffle_data.fixed_prize_lamports.min(vault_info.lamports())
+        } else {
+            0
+        };
+        // The creator bond is returned on completion unless it was slashed into the prize
+        let bond_refund = if raffle_data.creator_bond_slashed {
+            0
+        } else {
+            raffle_data.creator_bond.min(vault_info.lamports().saturating_sub(fixed_prize_refund))
+        };
+        let creator_refund = fixed_prize_refund + bond_refund;
+        if creator_refund > 0 && *raffle_authority_info.key != raffle_data.authority {
+            msg!("Raffle authority account does not match");
+            return Err(ProgramError::InvalidArgument);
+        }
+        // The consolation pool is paid out alongside the prize
+        let pool_amount = vault_info.lamports()
+            .saturating_sub(creator_refund)
+            .saturating_sub(raffle_data.consolation_pool);
+
+        // Both keepers are paid for keeping raffles moving: the completer, and whoever paid for
+        // the randomness request. A sole holder's raffle can complete without any request, and
+        // then that share stays in the prize. An advertised charity share then comes out of what
+        // is left before the winner's prize
+        let request_reward_bps = if raffle_data.vrf_requester == Pubkey::default() {
+            0
+        } else {
+            raffle_data.request_reward_bps
+        };
+        let (pool_amount, keeper_reward) = crate::utils::split_fee(pool_amount, raffle_data.complete_reward_bps)?;
+        let (pool_amount, request_reward) = crate::utils::split_fee(pool_amount, request_reward_bps)?;
+        if request_reward > 0 && *vrf_requester_info.key != raffle_data.vrf_requester {
+            msg!("Expected randomness requester {} at account index 13, got {}",
+                 raffle_data.vrf_requester, vrf_requester_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        let (prize_amount, charity_amount) = crate::utils::split_fee(pool_amount, raffle_data.charity_bps)?;
+        if charity_amount > 0 && *charity_info.key != raffle_data.charity {
+            msg!("Expected charity {} at account index 10, got {}", raffle_data.charity, charity_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // No single winner is paid more than the cap; the excess goes to the platform
+        let (prize_amount, capped_excess) = if raffle_data.max_prize_lamports > 0 {
+            let capped = prize_amount.min(raffle_data.max_prize_lamports);
+            (capped, prize_amount - capped)
+        } else {
+            (prize_amount, 0)
+        };
+        if capped_excess > 0 && *treasury_info.key != raffle_data.treasury {
+            msg!("Expected treasury {} at account index 12, got {}", raffle_data.treasury, treasury_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Paying the consolation pool now leaves nothing in the vault too small to stay rent-exempt.
+        // It goes to the treasury when there was no other ticket to draw, or when the drawn
+        // wallet no longer exists and the pool is too small to recreate it.
+        let consolation_amount = raffle_data.consolation_pool;
+        let (consolation_winner, consolation_recipient) = match consolation_draw {
+            Some((record, purchaser)) => {
+                if *consolation_wallet_info.key == purchaser
+                    && consolation_wallet_info.lamports() == 0
+                    && consolation_amount < Rent::get()?.minimum_balance(0)
+                {
+                    msg!("Consolation wallet {} does not exist and {} lamports cannot create it, paying the treasury",
+                         purchaser, consolation_amount);
+                    (raffle_data.treasury, raffle_data.treasury)
+                } else {
+                    (record, purchaser)
+                }
+            }
+            None => (raffle_data.treasury, raffle_data.treasury),
+        };
+        if consolation_amount > 0 && *consolation_wallet_info.key != consolation_recipient {
+            msg!("Expected consolation recipient {} at account index 15, got {}",
+                 consolation_recipient, consolation_wallet_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if consolation_amount > 0 {
+            raffle_data.consolation_winner = consolation_winner;
+            raffle_data.consolation_paid = consolation_amount;
+            raffle_data.consolation_pool = 0;
+        }
+
+        // Set the winner's pubkey and record the payout
+        raffle_data.winner = *winner_info.key;
+        raffle_data.prize_amount = prize_amount;
+        raffle_data.completed_at = current_time;
+        raffle_data.pool_principal = 0;
+
+        // With a claim window the prize stays in the vault until the winner claims it or it
+        // is forfeited to the runner-up; without a runner-up there is nobody to forfeit to.
+        // A prize paid in installments is also held and claimed one installment at a time.
+        let forfeitable = raffle_data.claim_window > 0 && runner_up != Pubkey::default();
+        let hold_for_claim = forfeitable || raffle_data.installment_count() > 1;
+        raffle_data.runner_up = runner_up;
+        raffle_data.prize_claimed = !hold_for_claim;
+        if forfeitable {
+            let claim_window = i64::try_from(raffle_data.claim_window)
+                .map_err(|_| ProgramError::InvalidArgument)?;
+            raffle_data.claim_deadline = current_time.checked_add(claim_window)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            msg!("Prize held for claim until {}", raffle_data.claim_deadline);
+        }
+
+        // Update raffle status
+        Self::release_active_raffle(program_id, authority_state_info, &raffle_data.authority, 11)?;
+        raffle_data.status = RaffleStatus::Complete;
+        raffle_data.vrf_request_in_progress = false;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        if creator_refund > 0 {
+            Self::check_wallet_payout(raffle_authority_info, creator_refund, 8)?;
+            msg!("Returning {} lamports of fixed prize and {} lamports of bond to the creator",
+                 fixed_prize_refund, bond_refund);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    raffle_authority_info.key,
+                    creator_refund,
+                ),
+                &[
+                    vault_info.clone(),
+                    raffle_authority_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        if keeper_reward > 0 {
+            msg!("Paying keeper reward of {} lamports to {}", keeper_reward, authority_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    authority_info.key,
+                    keeper_reward,
+                ),
+                &[
+                    vault_info.clone(),
+                    authority_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        if request_reward > 0 {
+            Self::check_wallet_payout(vrf_requester_info, request_reward, 13)?;
+            msg!("Paying request reward of {} lamports to {}", request_reward, vrf_requester_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    vrf_requester_info.key,
+                    request_reward,
+                ),
+                &[
+                    vault_info.clone(),
+                    vrf_requester_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        if charity_amount > 0 {
+            Self::check_wallet_payout(charity_info, charity_amount, 10)?;
+            msg!("Paying charity share of {} lamports to {}", charity_amount, charity_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    charity_info.key,
+                    charity_amount,
+                ),
+                &[
+                    vault_info.clone(),
+                    charity_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        if capped_excess > 0 {
+            msg!("Prize capped at {} lamports, sending {} lamports to treasury {}",
+                 prize_amount, capped_excess, treasury_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    treasury_info.key,
+                    capped_excess,
+                ),
+                &[
+                    vault_info.clone(),
+                    treasury_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        if consolation_amount > 0 {
+            msg!("Paying consolation pool of {} lamports to {}", consolation_amount, consolation_recipient);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    consolation_wallet_info.key,
+                    consolation_amount,
+                ),
+                &[
+                    vault_info.clone(),
+                    consolation_wallet_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        // Transfer the prize to the winning purchaser, signing as the vault PDA
+        if prize_amount > 0 && !hold_for_claim {
+            Self::check_wallet_payout(winner_wallet_info, prize_amount, 14)?;
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    winner_wallet_info.key,
+                    prize_amount,
+                ),
+                &[
+                    vault_info.clone(),
+                    winner_wallet_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        // Expose the result to CPI callers: winner pubkey (32 bytes) followed by prize amount (u64 LE)
+        let mut return_data = [0u8; 40];
+        return_data[..32].copy_from_slice(winner_info.key.as_ref());
+        return_data[32..].copy_from_slice(&prize_amount.to_le_bytes());
+        set_return_data(&return_data);
+
+        msg!("Raffle completed with VRF randomness! Winner: {}", winner_info.key);
+        Ok(())
+    }
+
+    /// Verify that the provided ticket records account for every ticket sold in a raffle
+    ///
+    /// Records must be distinct, belong to the raffle and be passed in strictly ascending
+    /// pubkey order so the ticket ranges used for the draw are canonical.
+    /// Returns each record's key and purchase data in that order.
+    fn assert_ticket_accounting(
+        raffle_key: &Pubkey,
+        record_infos: &[AccountInfo],
+        tickets_sold: u64,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, TicketPurchase)>, ProgramError> {
+        let mut records = Vec::with_capacity(record_infos.len());
+        let mut total_tickets = 0u64;
+        let mut previous_key: Option<&Pubkey> = None;
+
+        for record_info in record_infos {
+            if let Some(previous_key) = previous_key {
+                if record_info.key <= previous_key {
+                    msg!("Ticket records must be in strictly ascending pubkey order");
+                    return Err(RaffleError::TicketAccountingMismatch.into());
+                }
+            }
+            previous_key = Some(record_info.key);
+
+            if record_info.owner != program_id || record_info.data_len() != TicketPurchase::LEN {
+                msg!("Account {} is not a ticket purchase record", record_info.key);
+                return Err(RaffleError::TicketAccountingMismatch.into());
+            }
+
+            let ticket_data = TicketPurchase::unpack_unchecked(&record_info.data.borrow())?;
+            if !ticket_data.is_initialized || ticket_data.raffle != *raffle_key {
+                msg!("Ticket record {} does not belong to this raffle", record_info.key);
+                return Err(RaffleError::TicketAccountingMismatch.into());
+            }
+
+            total_tickets = total_tickets.checked_add(ticket_data.ticket_count)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            records.push((*record_info.key, ticket_data));
+        }
+
+        if total_tickets != tickets_sold {
+            msg!("Ticket records account for {} of {} tickets sold", total_tickets, tickets_sold);
+            return Err(RaffleError::TicketAccountingMismatch.into());
+        }
+
+        Ok(records)
+    }
+
+    /// Process IncreaseMaxTickets instruction
+    /// The ticket cap can only be raised, never lowered, and only while the raffle is active
+    fn process_increase_max_tickets(
+        accounts: &[AccountInfo],
+        new_max: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let authority_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+
+        // Ensure the authority signed the transaction
+        if !authority_info.is_signer {
+            msg!("Authority must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // Only the raffle creator can change the cap
+        if raffle_data.authority != *authority_info.key {
+            msg!("Only the raffle authority can increase max tickets");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if raffle_data.status != RaffleStatus::Active {
+            msg!("Raffle is not active");
+            return Err(RaffleError::RaffleNotActive.into());
+        }
+
+        // An uncapped raffle has nothing to raise
+        if raffle_data.max_tickets == 0 {
+            msg!("Raffle has no ticket cap");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if new_max <= raffle_data.max_tickets {
+            msg!("New max tickets {} must be greater than current max {}", new_max, raffle_data.max_tickets);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        raffle_data.max_tickets = new_max;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        msg!("Max tickets increased to {}", new_max);
+        Ok(())
+    }
+
+    /// Process CancelRaffle instruction
+    /// Buyer principal is kept in the vault for refunds; anything above it (funds that
+    /// were never paid by buyers) is swept to the treasury so buyers are never over-refunded
+    fn process_cancel_raffle(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let authority_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let treasury_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let authority_state_info = next_account_info(account_info_iter)?;
+        // The creator refund goes to the raffle authority, which is the signer unless passed separately
+        let creator_info = next_account_info(account_info_iter).unwrap_or(authority_info);
+
+        Self::check_system_program(system_program_info, 4)?;
+
+        // Ensure the caller signed the transaction
+        if !authority_info.is_signer {
+            msg!("Authority must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        if *creator_info.key != raffle_data.authority {
+            msg!("Expected raffle authority {} to receive the creator refund, got {}",
+                 raffle_data.authority, creator_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Only the raffle creator can cancel it, unless sales closed with a pool too small to draw
+        if raffle_data.authority != *authority_info.key {
+            let sales_closed = raffle_data.status == RaffleStatus::ReadyForRandomness
+                || Clock::get()?.unix_timestamp >= raffle_data.sales_close_time();
+            if !(sales_closed && raffle_data.is_below_min_pool()) {
+                msg!("Only the raffle authority can cancel the raffle");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            msg!("Pool of {} lamports is below the {} lamport minimum, cancelling for refunds",
+                 raffle_data.prize_pool_value(), raffle_data.min_pool_lamports);
+        }
+
+        // A raffle can only be cancelled before randomness has been requested
+        let cancellable = match raffle_data.status {
+            RaffleStatus::Active => true,
+            RaffleStatus::ReadyForRandomness => !raffle_data.vrf_request_in_progress,
+            _ => false,
+        };
+        if !cancellable {
+            msg!("Raffle can no longer be cancelled. Current status: {:?}", raffle_data.status);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Once the bond is slashed it belongs to the prize, so the raffle has to be drawn
+        if raffle_data.creator_bond_slashed {
+            msg!("Raffle was abandoned and its bond slashed, it can no longer be cancelled");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Refunds are paid from the vault, so legacy pools must be migrated first
+        if !raffle_data.uses_vault {
+            msg!("Raffle pool must be migrated to the vault before cancellation");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 2, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *treasury_info.key != raffle_data.treasury {
+            msg!("Expected treasury {} at account index 3, got {}", raffle_data.treasury, treasury_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let vault_seeds: &[&[u8]] = &[b"vault", raffle_info.key.as_ref(), &[vault_bump]];
+
+        // A creator-funded fixed prize goes back to the creator, along with any unslashed bond
+        let creator_refund = vault_info.lamports()
+            .saturating_sub(raffle_data.pool_principal)
+            .min(raffle_data.fixed_prize_lamports.saturating_add(raffle_data.creator_bond));
+        if creator_refund > 0 {
+            msg!("Returning fixed prize and bond of {} lamports to authority {}", creator_refund, creator_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    creator_info.key,
+                    creator_refund,
+                ),
+                &[
+                    vault_info.clone(),
+                    creator_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[vault_seeds],
+            )?;
+        }
+
+        // Anything else in the vault beyond buyer principal, including non-refundable donations,
+        // goes to the platform
+        let extra_amount = vault_info.lamports().saturating_sub(raffle_data.pool_principal);
+        if extra_amount > 0 {
+            msg!("Returning {} extra lamports to treasury {}", extra_amount, treasury_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    treasury_info.key,
+                    extra_amount,
+                ),
+                &[
+                    vault_info.clone(),
+                    treasury_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[vault_seeds],
+            )?;
+        }
+
+        Self::release_active_raffle(program_id, authority_state_info, &raffle_data.authority, 5)?;
+        raffle_data.status = RaffleStatus::Cancelled;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        msg!("Raffle cancelled. {} lamports of buyer principal available for refunds", raffle_data.pool_principal);
+        Ok(())
+    }
+
+    /// Process RefundTickets instruction
+    /// Returns a buyer's principal (ticket cost net of fees) from the vault of a cancelled raffle
+    fn process_refund_tickets(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let purchaser_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let ticket_purchase_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 4)?;
+
+        // Check that raffle and ticket accounts are owned by our program
+        if raffle_info.owner != program_id || ticket_purchase_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+        if raffle_data.status != RaffleStatus::Cancelled {
+            msg!("Refunds are only available for cancelled raffles");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut ticket_data = TicketPurchase::unpack(&ticket_purchase_info.data.borrow())?;
+        if !ticket_data.is_initialized || ticket_data.raffle != *raffle_info.key {
+            msg!("Ticket purchase record does not belong to this raffle");
+            return Err(RaffleError::TicketPurchaseMismatch.into());
+        }
+        if ticket_data.purchaser != *purchaser_info.key {
+            msg!("Refunds can only be paid to the recorded purchaser");
+            return Err(RaffleError::TicketPurchaseMismatch.into());
+        }
+        if ticket_data.ticket_count == 0 {
+            msg!("Tickets have already been refunded");
+            return Err(RaffleError::NoTicketsSold.into());
+        }
+
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 3, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Principal is what reached the pool: the ticket cost net of the fee taken at purchase.
+        // The record doesn't say which purchases got the bulk rate, so the standard rate is used;
+        // it never overpays, and whatever it leaves behind goes to the last refund.
+        let remaining_tickets = raffle_data.tickets_sold.checked_sub(ticket_data.ticket_count)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let refund_amount = if remaining_tickets == 0 {
+            // The last refund also returns any rounding remainder of buyer principal
+            raffle_data.pool_principal
+        } else {
+            let total_price = ticket_data.ticket_count.checked_mul(raffle_data.ticket_price)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let (principal, _) = crate::utils::split_fee(total_price, raffle_data.fee_basis_points)?;
+            principal.min(raffle_data.pool_principal)
+        };
+
+        // Update state before moving funds so the record can't be refunded twice
+        ticket_data.ticket_count = 0;
+        ticket_data.is_initialized = false;
+        TicketPurchase::pack(ticket_data, &mut ticket_purchase_info.data.borrow_mut())?;
+        raffle_data.tickets_sold = remaining_tickets;
+        raffle_data.pool_principal -= refund_amount;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        // Close the now useless ticket record and return its rent to the buyer.
+        // The record is program-owned, so it is debited directly rather than via the system program.
+        // Its rent goes first so a buyer wallet that has since been closed is recreated rent-exempt
+        // before the principal transfer, which on its own could fall below the minimum.
+        let rent_refund = ticket_purchase_info.lamports();
+        Self::move_program_lamports(program_id, ticket_purchase_info, purchaser_info, rent_refund)?;
+        ticket_purchase_info.data.borrow_mut().fill(0);
+
+        if refund_amount > 0 {
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    purchaser_info.key,
+                    refund_amount,
+                ),
+                &[
+                    vault_info.clone(),
+                    purchaser_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        msg!("Refunded {} lamports principal and {} lamports rent to {}",
+             refund_amount, rent_refund, purchaser_info.key);
+        Ok(())
+    }
+
+    /// Process MigrateToVault instruction
+    /// Grows a legacy raffle account to the current layout and moves the lamports it holds
+    /// above rent into its vault PDA
+    fn process_migrate_to_vault(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initiator_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 3)?;
+
+        // Verify the initiator signed the transaction
+        if !initiator_info.is_signer {
+            msg!("Initiator must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // Raffles from before the vault model were created with the shorter legacy layout
+        let legacy = raffle_info.data_len() == LEGACY_RAFFLE_LEN;
+        let mut raffle_data = if legacy {
+            Raffle::unpack_legacy(&raffle_info.data.borrow())?
+        } else {
+            Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+            Raffle::unpack(&raffle_info.data.borrow())?
+        };
+
+        if raffle_data.uses_vault {
+            msg!("Raffle pool is already held in the vault");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if raffle_data.status != RaffleStatus::Active && raffle_data.status != RaffleStatus::ReadyForRandomness {
+            msg!("Raffle cannot be migrated in its current state: {:?}", raffle_data.status);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (vault_pda, _) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 2, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Everything above the rent-exempt minimum is prize pool paid in by buyers
+        let rent = Rent::get()?;
+        let pool_amount = raffle_info.lamports()
+            .saturating_sub(rent.minimum_balance(raffle_info.data_len()));
+
+        // The account grows to the current layout; the initiator pays the extra rent so the
+        // buyers' pool moves to the vault whole
+        if legacy {
+            raffle_info.realloc(Raffle::LEN, true)?;
+            let rent_top_up = rent.minimum_balance(Raffle::LEN)
+                .saturating_sub(rent.minimum_balance(LEGACY_RAFFLE_LEN));
+            msg!("Resizing legacy raffle to {} bytes, initiator pays {} lamports of rent", Raffle::LEN, rent_top_up);
+            invoke(
+                &system_instruction::transfer(initiator_info.key, raffle_info.key, rent_top_up),
+                &[
+                    initiator_info.clone(),
+                    raffle_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+
+        raffle_data.uses_vault = true;
+        raffle_data.pool_principal = raffle_data.pool_principal.checked_add(pool_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        // The raffle account carries data and is program-owned, so the system program cannot
+        // debit it; the lamports are moved directly instead
+        Self::move_program_lamports(program_id, raffle_info, vault_info, pool_amount)?;
+
+        msg!("Migrated {} lamports from raffle {} to vault {}", pool_amount, raffle_info.key, vault_info.key);
+        Ok(())
+    }
+
+    /// Process MigrateConfig instruction
+    /// Grows a config account still in the legacy layout to the current one, so every handler
+    /// that reads the config accepts it again
+    fn process_migrate_config(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 2)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // check_config would reject the legacy size, so the address and owner are checked here
+        let (config_pda, _) = crate::utils::find_config_address(program_id);
+        if *config_info.key != config_pda {
+            msg!("Expected config {} at account index 1, got {}", config_pda, config_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if config_info.owner != program_id {
+            msg!("Config at account index 1 is not owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if config_info.data_len() != LEGACY_CONFIG_LEN {
+            if config_info.data_len() == Config::LEN {
+                msg!("Config is already in the current layout");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            return Self::check_data_len(config_info, Config::LEN, "Config");
+        }
+        let config_data = Config::unpack_legacy(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can migrate the config");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // The admin pays the extra rent for the larger account
+        let rent = Rent::get()?;
+        config_info.realloc(Config::LEN, true)?;
+        let rent_top_up = rent.minimum_balance(Config::LEN)
+            .saturating_sub(config_info.lamports());
+        msg!("Resizing legacy config to {} bytes, admin pays {} lamports of rent", Config::LEN, rent_top_up);
+        if rent_top_up > 0 {
+            invoke(
+                &system_instruction::transfer(admin_info.key, config_info.key, rent_top_up),
+                &[
+                    admin_info.clone(),
+                    config_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+
+        Config::pack(config_data, &mut config_info.data.borrow_mut())?;
+
+        msg!("Config migrated, next raffle index {}", config_data.next_raffle_index);
+        Ok(())
+    }
+
+    /// Process CloseEmptyRaffle instruction
+    /// Finalizes an ended raffle with no tickets sold and returns any fixed prize to its creator
+    fn process_close_empty_raffle(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initiator_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let authority_state_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 4)?;
+
+        // Verify the initiator signed the transaction
+        if !initiator_info.is_signer {
+            msg!("Initiator must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        if raffle_data.status != RaffleStatus::Active {
+            msg!("Raffle is not in Active state");
+            return Err(RaffleError::RaffleNotActive.into());
+        }
+
+        if raffle_data.tickets_sold > 0 {
+            msg!("Raffle sold {} tickets and must be drawn", raffle_data.tickets_sold);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let clock = Clock::from_account_info(clock_info)?;
+        if clock.unix_timestamp < raffle_data.end_time {
+            msg!("Raffle has not ended yet");
+            return Err(RaffleError::RaffleNotEnded.into());
+        }
+
+        if *authority_info.key != raffle_data.authority {
+            msg!("Fixed prize can only be returned to the raffle authority");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 2, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::release_active_raffle(program_id, authority_state_info, &raffle_data.authority, 6)?;
+        raffle_data.status = RaffleStatus::Cancelled;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        // With no buyers, everything in the vault was deposited by the creator
+        let prize_refund = vault_info.lamports();
+        if prize_refund > 0 {
+            Self::check_wallet_payout(authority_info, prize_refund, 3)?;
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    authority_info.key,
+                    prize_refund,
+                ),
+                &[
+                    vault_info.clone(),
+                    authority_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    raffle_info.key.as_ref(),
+                    &[vault_bump],
+                ]],
+            )?;
+        }
+
+        msg!("Empty raffle closed, returned {} lamports to authority {}", prize_refund, authority_info.key);
+        Ok(())
+    }
+
+    /// Process SetBlacklist instruction
+    /// Creates the blacklist account on first use, then adds or removes a wallet
+    fn process_set_blacklist(
+        accounts: &[AccountInfo],
+        add: bool,
+        pubkey: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let blacklist_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 3)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+
+        // Check if the caller is the admin
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can update the blacklist");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (blacklist_pda, bump_seed) = crate::utils::find_blacklist_address(program_id);
+        if *blacklist_info.key != blacklist_pda {
+            msg!("Blacklist account does not match expected PDA");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Create the blacklist account the first time it is used
+        let mut blacklist = if blacklist_info.owner != program_id {
+            let rent = Rent::get()?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin_info.key,
+                    blacklist_info.key,
+                    rent.minimum_balance(Blacklist::LEN),
+                    Blacklist::LEN as u64,
+                    program_id,
+                ),
+                &[admin_info.clone(), blacklist_info.clone(), system_program_info.clone()],
+                &[&[b"blacklist", &[bump_seed]]],
+            )?;
+            Blacklist {
+                is_initialized: true,
+                count: 0,
+                wallets: [Pubkey::default(); MAX_BLACKLIST_ENTRIES],
+            }
+        } else {
+            Blacklist::unpack(&blacklist_info.data.borrow())?
+        };
+
+        let count = blacklist.count as usize;
+        let position = blacklist.wallets[..count].iter().position(|wallet| *wallet == pubkey);
+        match (add, position) {
+            (true, Some(_)) => {
+                msg!("Wallet {} is already blacklisted", pubkey);
+            }
+            (true, None) => {
+                if count >= MAX_BLACKLIST_ENTRIES {
+                    msg!("Blacklist is full ({} entries)", MAX_BLACKLIST_ENTRIES);
+                    return Err(ProgramError::AccountDataTooSmall);
+                }
+                blacklist.wallets[count] = pubkey;
+                blacklist.count += 1;
+            }
+            (false, Some(index)) => {
+                // Keep the used entries contiguous by moving the last one into the gap
+                blacklist.wallets[index] = blacklist.wallets[count - 1];
+                blacklist.wallets[count - 1] = Pubkey::default();
+                blacklist.count -= 1;
+            }
+            (false, None) => {
+                msg!("Wallet {} is not blacklisted", pubkey);
+            }
+        }
+
+        Blacklist::pack(blacklist, &mut blacklist_info.data.borrow_mut())?;
+
+        msg!("Blacklist updated: wallet={}, banned={}, entries={}", pubkey, add, blacklist.count);
+        Ok(())
+    }
+
+    /// Process FreezeRaffle instruction
+    /// Blocks or resumes purchases for one raffle; either its authority or the admin may do this
+    fn process_freeze_raffle(
+        accounts: &[AccountInfo],
+        frozen: bool,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let signer_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        if !signer_info.is_signer {
+            msg!("Raffle authority or admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 2)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        if raffle_data.authority != *signer_info.key && config_data.admin != *signer_info.key {
+            msg!("Only the raffle authority or the admin can freeze a raffle");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if raffle_data.status != RaffleStatus::Active {
+            msg!("Raffle is not active");
+            return Err(RaffleError::RaffleNotActive.into());
+        }
+
+        raffle_data.frozen = frozen;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        msg!("Raffle {} frozen: {}", raffle_data.raffle_index, frozen);
+        Ok(())
+    }
+
+    /// Process ReconcileRaffle instruction
+    /// Sends lamports that arrived outside of purchases to a recipient chosen by the admin,
+    /// leaving exactly the buyer principal and fixed prize in the vault
+    fn process_reconcile_raffle(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let recipient_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 5)?;
+
+        // Ensure the admin signed the transaction
+        if !admin_info.is_signer {
+            msg!("Admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 1)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+        if config_data.admin != *admin_info.key {
+            msg!("Only the admin can reconcile a raffle");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // Once a raffle is drawn or cancelled the vault is being paid out and nothing is excess
+        if raffle_data.status != RaffleStatus::Active && raffle_data.status != RaffleStatus::ReadyForRandomness {
+            msg!("Only open raffles can be reconciled. Current status: {:?}", raffle_data.status);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Legacy raffles keep their pool in the raffle account and must be migrated first
+        if !raffle_data.uses_vault {
+            msg!("Raffle pool must be migrated to the vault before reconciling");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_info.key);
+        if *vault_info.key != vault_pda {
+            msg!("Expected vault {} at account index 3, got {}", vault_pda, vault_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if recipient_info.key == raffle_info.key || recipient_info.key == vault_info.key {
+            msg!("Recipient cannot be the raffle or its vault");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The vault should hold exactly the buyer principal, the creator's fixed prize and bond,
+        // sponsor donations, and the consolation share of fees
+        let expected_pool = raffle_data.pool_principal.checked_add(raffle_data.fixed_prize_lamports)
+            .and_then(|amount| amount.checked_add(raffle_data.creator_bond))
+            .and_then(|amount| amount.checked_add(raffle_data.donated_amount))
+            .and_then(|amount| amount.checked_add(raffle_data.consolation_pool))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let vault_excess = vault_info.lamports().saturating_sub(expected_pool);
+        if vault_excess > 0 {
+            msg!("Moving {} excess lamports from vault to {}", vault_excess, recipient_info.key);
+            invoke_signed(
+                &system_instruction::transfer(
+                    vault_info.key,
+                    recipient_info.key,
+                    vault_excess,
+                ),
+                &[
+                    vault_info.clone(),
+                    recipient_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&[b"vault", raffle_info.key.as_ref(), &[vault_bump]]],
+            )?;
+        }
+
+        // The raffle account itself only needs its rent-exempt reserve. It is program-owned
+        // data, so the system program can't debit it and the excess is moved directly.
+        let rent = Rent::get()?;
+        let raffle_excess = raffle_info.lamports().saturating_sub(rent.minimum_balance(raffle_info.data_len()));
+        if raffle_excess > 0 {
+            msg!("Moving {} excess lamports from raffle account to {}", raffle_excess, recipient_info.key);
+            Self::move_program_lamports(program_id, raffle_info, recipient_info, raffle_excess)?;
+        }
+
+        msg!("Raffle {} reconciled: {} lamports in pool, {} lamports moved out",
+             raffle_data.raffle_index, expected_pool, vault_excess + raffle_excess);
+        Ok(())
+    }
+
+    /// Process ClaimPrize instruction
+    /// The winning purchaser collects a held prize before the claim deadline
+    fn process_claim_prize(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let purchaser_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let winner_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 5)?;
+
+        if !purchaser_info.is_signer {
+            msg!("Winning purchaser must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that accounts are owned by our program
+        if raffle_info.owner != program_id || winner_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+        if raffle_data.status != RaffleStatus::Complete || raffle_data.prize_claimed {
+            msg!("Raffle has no prize waiting to be claimed");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if *winner_info.key != raffle_data.winner {
+            msg!("Account is not the winning ticket record");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let ticket_data = TicketPurchase::unpack(&winner_info.data.borrow())?;
+        if ticket_data.purchaser != *purchaser_info.key {
+            msg!("Only the winning purchaser can claim the prize");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Only the first claim races the forfeit deadline; later installments follow the interval
+        let clock = Clock::from_account_info(clock_info)?;
+        if raffle_data.installments_claimed == 0 {
+            if raffle_data.claim_deadline != 0 && clock.unix_timestamp > raffle_data.claim_deadline {
+                msg!("Claim deadline {} has passed", raffle_data.claim_deadline);
+                return Err(ProgramError::InvalidArgument);
+            }
+        } else {
+            let payout_interval = i64::try_from(raffle_data.payout_interval)
+                .map_err(|_| ProgramError::InvalidArgument)?;
+            let next_claim_at = raffle_data.last_installment_at.saturating_add(payout_interval);
+            if clock.unix_timestamp < next_claim_at {
+                msg!("Next installment can be claimed at {}", next_claim_at);
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+
+        let vault_bump = Self::check_vault(program_id, raffle_info.key, vault_info.key, 2)?;
+
+        let installment = raffle_data.installments_claimed;
+        let amount = raffle_data.installment_amount(installment);
+        raffle_data.installments_claimed = installment + 1;
+        raffle_data.last_installment_at = clock.unix_timestamp;
+        raffle_data.prize_claimed = raffle_data.installments_claimed >= raffle_data.installment_count();
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        Self::check_wallet_payout(purchaser_info, amount, 0)?;
+        invoke_signed(
+            &system_instruction::transfer(
+                vault_info.key,
+                purchaser_info.key,
+                amount,
+            ),
+            &[
+                vault_info.clone(),
+                purchaser_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"vault", raffle_info.key.as_ref(), &[vault_bump]]],
+        )?;
+
+        msg!("Installment {} of {} ({} lamports) claimed by {}",
+             installment + 1, raffle_data.installment_count(), amount, purchaser_info.key);
+        Ok(())
+    }
+
+    /// Process ForfeitToRunnerUp instruction
+    /// After the claim deadline an unclaimed prize goes to the runner-up record's purchaser instead
+    fn process_forfeit_to_runner_up(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initiator_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let runner_up_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let runner_up_wallet_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 5)?;
+
+        if !initiator_info.is_signer {
+            msg!("Initiator must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+        if raffle_data.status != RaffleStatus::Complete || raffle_data.prize_claimed {
+            msg!("Raffle has no prize waiting to be claimed");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if raffle_data.runner_up == Pubkey::default() || *runner_up_info.key != raffle_data.runner_up {
+            msg!("Account is not the runner-up ticket record");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if runner_up_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let runner_up_data = TicketPurchase::unpack(&runner_up_info.data.borrow())?;
+        if *runner_up_wallet_info.key != runner_up_data.purchaser {
+            msg!("Expected runner-up purchaser {} at account index 6, got {}",
+                 runner_up_data.purchaser, runner_up_wallet_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Without a claim window, or once the winner has started claiming, there is nothing to forfeit
+        if raffle_data.claim_deadline == 0 || raffle_data.installments_claimed > 0 {
+            msg!("Prize is not forfeitable");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let clock = Clock::from_account_info(clock_info)?;
+        if clock.unix_timestamp <= raffle_data.claim_deadline {
+            msg!("Winner can still claim until {}", raffle_data.claim_deadline);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let vault_bump = Self::check_vault(program_id, raffle_info.key, vault_info.key, 2)?;
+
+        msg!("Winner {} did not claim, prize passes to runner-up {}", raffle_data.winner, raffle_data.runner_up);
+        raffle_data.winner = raffle_data.runner_up;
+        raffle_data.prize_claimed = true;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        Self::check_wallet_payout(runner_up_wallet_info, raffle_data.prize_amount, 6)?;
+        invoke_signed(
+            &system_instruction::transfer(
+                vault_info.key,
+                runner_up_wallet_info.key,
+                raffle_data.prize_amount,
+            ),
+            &[
+                vault_info.clone(),
+                runner_up_wallet_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"vault", raffle_info.key.as_ref(), &[vault_bump]]],
+        )?;
+
+        msg!("Prize of {} lamports paid to runner-up {}", raffle_data.prize_amount, runner_up_wallet_info.key);
+        Ok(())
+    }
+
+    /// Move lamports out of an account this program owns without going through the system program
+    ///
+    /// The runtime only lets the owning program debit an account, so the owner is re-checked
+    /// right before the debit rather than trusting an earlier check in the caller. Crediting the
+    /// destination is always allowed regardless of its owner.
+    fn move_program_lamports(
+        program_id: &Pubkey,
+        from_info: &AccountInfo,
+        to_info: &AccountInfo,
+        amount: u64,
+    ) -> ProgramResult {
+        if from_info.owner != program_id {
+            msg!("Account {} is not owned by this program and cannot be debited directly", from_info.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        **from_info.lamports.borrow_mut() = from_info.lamports().checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        **to_info.lamports.borrow_mut() = to_info.lamports().checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Check that the caller may request randomness for or complete a raffle
+    ///
+    /// Anyone may while `Config::permissionless_completion` is set; otherwise only the raffle
+    /// authority and the admin can.
+    fn check_completion_permission(
+        program_id: &Pubkey,
+        config_info: &AccountInfo,
+        config_index: usize,
+        raffle_data: &Raffle,
+        caller: &Pubkey,
+    ) -> ProgramResult {
+        Self::check_config(program_id, config_info, config_index)?;
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+
+        if !config_data.permissionless_completion
+            && *caller != raffle_data.authority
+            && *caller != config_data.admin
+        {
+            msg!("Only the raffle authority or admin can complete this raffle");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    /// Check that an account is the program's config PDA
+    ///
+    /// `index` is the account's position in the instruction, so a client that passed the
+    /// accounts in the wrong order is told where the config was expected.
+    fn check_config(program_id: &Pubkey, config_info: &AccountInfo, index: usize) -> ProgramResult {
+        let (config_pda, _) = crate::utils::find_config_address(program_id);
+        if *config_info.key != config_pda {
+            msg!("Expected config {} at account index {}, got {}", config_pda, index, config_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if config_info.owner != program_id {
+            msg!("Config at account index {} is not owned by this program", index);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Self::check_data_len(config_info, Config::LEN, "Config")
+    }
+
+    /// Validate a raffle title and return it in canonical form
+    ///
+    /// The logical title ends at the first null, must be valid UTF-8 and at most `MAX_TITLE_LEN`
+    /// bytes; everything after it is zeroed so equal titles are stored as equal bytes.
+    fn canonical_title(title: [u8; 32]) -> Result<[u8; 32], ProgramError> {
+        let title_len = crate::utils::title_len(&title);
+        if std::str::from_utf8(&title[..title_len]).is_err() {
+            msg!("Raffle title must be valid UTF-8");
+            return Err(RaffleError::InvalidTitle.into());
+        }
+        if title_len > MAX_TITLE_LEN {
+            msg!("Raffle title is {} bytes, the limit is {}", title_len, MAX_TITLE_LEN);
+            return Err(RaffleError::InvalidTitle.into());
+        }
+
+        let mut title = title;
+        title[title_len..].fill(0);
+        Ok(title)
+    }
+
+    /// Check that an account is exactly the size of a packed `kind` of `len` bytes
+    ///
+    /// A mismatch is logged with both sizes rather than left to a bare InvalidAccountData from
+    /// Pack. Only the legacy raffle and config layouts have a migration instruction, so only
+    /// those are told to migrate; any other size is reported as unrecognised. Accounts smaller
+    /// than this build's layout fail with AccountDataTooSmall.
+    fn check_data_len(account_info: &AccountInfo, len: usize, kind: &str) -> ProgramResult {
+        let data_len = account_info.data_len();
+        if data_len != len {
+            let migration = match kind {
+                "Raffle" if data_len == LEGACY_RAFFLE_LEN => Some("MigrateToVault"),
+                "Config" if data_len == LEGACY_CONFIG_LEN => Some("MigrateConfig"),
+                _ => None,
+            };
+            match migration {
+                Some(instruction) => msg!(
+                    "{} account {} size {}, expected {} - migration required, run {}",
+                    kind,
+                    account_info.key,
+                    data_len,
+                    len,
+                    instruction
+                ),
+                None => msg!(
+                    "{} account {} size {}, expected {} - unrecognised layout, no migration available",
+                    kind,
+                    account_info.key,
+                    data_len,
+                    len
+                ),
+            }
+            return Err(if data_len < len {
+                ProgramError::AccountDataTooSmall
+            } else {
+                ProgramError::InvalidAccountData
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that the account passed for the system program really is it
+    ///
+    /// A wrong account would otherwise only surface as an opaque failure inside a transfer CPI.
+    fn check_system_program(system_program_info: &AccountInfo, index: usize) -> ProgramResult {
+        if *system_program_info.key != system_program::id() {
+            msg!("Expected the system program at account index {}, got {}", index, system_program_info.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    /// Check that an account is the raffle's vault PDA, returning its bump seed
+    fn check_vault(
+        program_id: &Pubkey,
+        raffle_key: &Pubkey,
+        vault_key: &Pubkey,
+        index: usize,
+    ) -> Result<u8, ProgramError> {
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_key);
+        if *vault_key != vault_pda {
+            msg!("Expected vault {} at account index {}, got {}", vault_pda, index, vault_key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(vault_bump)
+    }
+
+    /// Check that `amount` lamports can be sent to a wallet with a system transfer
+    ///
+    /// A transfer to a wallet that doesn't exist yet creates it, but the runtime only accepts
+    /// the new account if the transfer alone makes it rent-exempt.
+    fn check_wallet_payout(wallet_info: &AccountInfo, amount: u64, index: usize) -> ProgramResult {
+        if wallet_info.lamports() == 0 && amount > 0 {
+            let rent_minimum = Rent::get()?.minimum_balance(0);
+            if amount < rent_minimum {
+                msg!("Wallet {} at account index {} does not exist and {} lamports is below the {} lamports needed to create it",
+                     wallet_info.key, index, amount, rent_minimum);
+                return Err(ProgramError::InsufficientFunds);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that an account is the authority's open raffle counter PDA, returning its bump seed
+    fn check_authority_state(
+        program_id: &Pubkey,
+        authority_state_info: &AccountInfo,
+        authority: &Pubkey,
+        index: usize,
+    ) -> Result<u8, ProgramError> {
+        let (authority_state_pda, bump) = crate::utils::find_authority_state_address(program_id, authority);
+        if *authority_state_info.key != authority_state_pda {
+            msg!("Expected authority state {} at account index {}, got {}",
+                 authority_state_pda, index, authority_state_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(bump)
+    }
+
+    /// Take a raffle that reached a final state off its authority's open raffle count
+    ///
+    /// Raffles created before the counter existed were never counted; an authority without a
+    /// counter account has nothing to release, and the count never drops below zero.
+    fn release_active_raffle(
+        program_id: &Pubkey,
+        authority_state_info: &AccountInfo,
+        authority: &Pubkey,
+        index: usize,
+    ) -> ProgramResult {
+        Self::check_authority_state(program_id, authority_state_info, authority, index)?;
+        if authority_state_info.owner != program_id {
+            return Ok(());
+        }
+        let mut authority_state = AuthorityState::unpack(&authority_state_info.data.borrow())?;
+        authority_state.active_raffle_count = authority_state.active_raffle_count.saturating_sub(1);
+        AuthorityState::pack(authority_state, &mut authority_state_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Process GetPdaInfo instruction
+    /// Lets CPI callers sign as the raffle or vault PDA without re-deriving the bumps
+    fn process_get_pda_info(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_info = next_account_info(account_info_iter)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        let (raffle_pda, raffle_bump) =
+            crate::utils::find_raffle_pda(program_id, &raffle_data.authority, raffle_data.nonce);
+        if *raffle_info.key != raffle_pda {
+            msg!("Raffle account does not match the PDA of its authority and nonce");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (vault_pda, vault_bump) = crate::utils::find_vault_address(program_id, raffle_info.key);
+
+        let mut return_data = [0u8; 8 + 1 + 32 + 1 + 32];
+        return_data[..8].copy_from_slice(&raffle_data.nonce.to_le_bytes());
+        return_data[8] = raffle_bump;
+        return_data[9..41].copy_from_slice(raffle_pda.as_ref());
+        return_data[41] = vault_bump;
+        return_data[42..].copy_from_slice(vault_pda.as_ref());
+        set_return_data(&return_data);
+
+        msg!("PdaInfo: raffle={}, nonce={}, raffle_bump={}, vault={}, vault_bump={}",
+             raffle_pda, raffle_data.nonce, raffle_bump, vault_pda, vault_bump);
+        Ok(())
+    }
+
+    /// Process SimulatePurchase instruction
+    /// Prices a purchase exactly as PurchaseTickets would, without moving funds or writing state
+    fn process_simulate_purchase(
+        accounts: &[AccountInfo],
+        ticket_count: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if ticket_count == 0 {
+            msg!("Ticket count must be greater than zero");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let account_info_iter = &mut accounts.iter();
+        let raffle_info = next_account_info(account_info_iter)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        if raffle_data.status != RaffleStatus::Active {
+            msg!("Raffle is not active");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if raffle_data.max_tickets > 0 {
+            let remaining = raffle_data.max_tickets.saturating_sub(raffle_data.tickets_sold);
+            if ticket_count > remaining {
+                msg!("Only {} tickets remaining, requested {}", remaining, ticket_count);
+                return Err(RaffleError::InsufficientTickets.into());
+            }
+        }
+
+        let total_price = ticket_count.checked_mul(raffle_data.ticket_price)
+            .ok_or(ProgramError::InvalidArgument)?;
+        let (pool_amount, fee_amount) = crate::utils::split_fee(total_price, raffle_data.fee_bps_for(ticket_count))?;
+        let fee_over_cap = raffle_data.fee_over_cap(fee_amount);
+        let (pool_amount, fee_amount) = (pool_amount + fee_over_cap, fee_amount - fee_over_cap);
+
+        // Share of all tickets these would hold once bought, ignoring any the buyer already owns
+        let tickets_after = raffle_data.tickets_sold.checked_add(ticket_count)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let odds_bps = (ticket_count as u128 * crate::raffle_core::BASIS_POINTS_DENOMINATOR as u128
+            / tickets_after as u128) as u16;
+
+        let mut return_data = [0u8; 8 + 8 + 8 + 2];
+        return_data[..8].copy_from_slice(&total_price.to_le_bytes());
+        return_data[8..16].copy_from_slice(&fee_amount.to_le_bytes());
+        return_data[16..24].copy_from_slice(&pool_amount.to_le_bytes());
+        return_data[24..].copy_from_slice(&odds_bps.to_le_bytes());
+        set_return_data(&return_data);
+
+        msg!("PurchasePreview: raffle_index={}, ticket_count={}, ticket_price={}, total_price={}, fee_amount={}, pool_amount={}, odds_bps={}",
+             raffle_data.raffle_index,
+             ticket_count,
+             raffle_data.ticket_price,
+             total_price,
+             fee_amount,
+             pool_amount,
+             odds_bps);
+        Ok(())
+    }
+
+    /// Process GetBuyerSpend instruction
+    /// Reports a buyer's tickets, spend and fees on a raffle without modifying anything
+    fn process_get_buyer_spend(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_info = next_account_info(account_info_iter)?;
+        let ticket_purchase_info = next_account_info(account_info_iter)?;
+        let buyer_info = next_account_info(account_info_iter)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // A buyer who never purchased has no record yet, which simply means nothing was spent
+        let ticket_count = if ticket_purchase_info.owner == program_id {
+            if ticket_purchase_info.data_len() != TicketPurchase::LEN {
+                msg!("Account {} is not a ticket purchase record", ticket_purchase_info.key);
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let ticket_data = TicketPurchase::unpack_unchecked(&ticket_purchase_info.data.borrow())?;
+            if ticket_data.raffle != *raffle_info.key || ticket_data.purchaser != *buyer_info.key {
+                msg!("Ticket record does not match raffle and buyer");
+                return Err(RaffleError::TicketPurchaseMismatch.into());
+            }
+            // Refunded records are cleared and count as nothing spent
+            if ticket_data.is_initialized { ticket_data.ticket_count } else { 0 }
+        } else {
+            0
+        };
+
+        let total_spent = ticket_count.checked_mul(raffle_data.ticket_price)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let fees_paid = crate::utils::calculate_fee(total_spent, raffle_data.fee_basis_points)?;
+
+        let mut return_data = [0u8; 8 + 8 + 8];
+        return_data[..8].copy_from_slice(&ticket_count.to_le_bytes());
+        return_data[8..16].copy_from_slice(&total_spent.to_le_bytes());
+        return_data[16..].copy_from_slice(&fees_paid.to_le_bytes());
+        set_return_data(&return_data);
+
+        msg!("BuyerSpend: raffle_index={}, buyer={}, ticket_count={}, total_spent={}, fees_paid={}",
+             raffle_data.raffle_index,
+             buyer_info.key,
+             ticket_count,
+             total_spent,
+             fees_paid);
+        Ok(())
+    }
+
+    /// Process AuditRaffle instruction
+    /// Reports, rather than fails on, any disagreement between a raffle's recorded state and
+    /// what its purchases, vault and VRF result imply
+    fn process_audit_raffle(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
         let vrf_account_info = next_account_info(account_info_iter)?;
-        let payer_info = next_account_info(account_info_iter)?;
         let switchboard_program_info = next_account_info(account_info_iter)?;
-        let oracle_queue_info = next_account_info(account_info_iter)?;
+        let ticket_record_infos = account_info_iter.as_slice();
 
-        // Collect the remaining accounts to pass to the VRF function
-        let remaining_accounts: Vec<&AccountInfo> = account_info_iter.collect();
-        
-        // Any user can create a raffle
-        if !authority_info.is_signer {
-            msg!("Initiator must sign the transaction");
-            return Err(ProgramError::MissingRequiredSignature);
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
         }
 
-        // Ensure the payer signed the transaction
-        if !payer_info.is_signer {
-            msg!("Payer must sign the transaction");
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+        Self::check_vault(program_id, raffle_info.key, vault_info.key, 1)?;
+
+        let mut checks: u64 = 0;
+        let mut discrepancies: u64 = 0;
+        let mut check = |name: &str, ok: bool, expected: String, actual: String| {
+            checks += 1;
+            if !ok {
+                discrepancies += 1;
+                msg!("AuditDiscrepancy: raffle_index={}, check={}, expected={}, actual={}",
+                     raffle_data.raffle_index, name, expected, actual);
+            }
+        };
+
+        // Before completion the vault holds everything the raffle has taken in; stray
+        // transfers on top are reclaimed by ReconcileRaffle and are not a discrepancy
+        if matches!(raffle_data.status, RaffleStatus::Active | RaffleStatus::ReadyForRandomness) {
+            let expected_pool = raffle_data.pool_principal
+                .saturating_add(raffle_data.fixed_prize_lamports)
+                .saturating_add(raffle_data.creator_bond)
+                .saturating_add(raffle_data.donated_amount)
+                .saturating_add(raffle_data.consolation_pool);
+            check("vault_balance", vault_info.lamports() >= expected_pool,
+                  format!(">={}", expected_pool), vault_info.lamports().to_string());
+        }
+
+        // Fees are rounded down per purchase and bulk buyers pay less, so the recorded principal
+        // lies between the standard-rate principal of all tickets and their full price.
+        // Completion pays the principal out and zeroes it, and refunds of a cancelled raffle use
+        // their own rounding, so only open raffles are checked.
+        if matches!(raffle_data.status, RaffleStatus::Active | RaffleStatus::ReadyForRandomness) {
+            let gross = raffle_data.tickets_sold.saturating_mul(raffle_data.ticket_price);
+            let (min_principal, _) = crate::utils::split_fee(gross, raffle_data.fee_basis_points)?;
+            check("pool_principal",
+                  raffle_data.pool_principal >= min_principal && raffle_data.pool_principal <= gross,
+                  format!("{}..={}", min_principal, gross), raffle_data.pool_principal.to_string());
+        }
+
+        if !ticket_record_infos.is_empty() {
+            let mut records: Vec<(Pubkey, TicketPurchase)> = Vec::with_capacity(ticket_record_infos.len());
+            for record_info in ticket_record_infos {
+                if record_info.owner != program_id || record_info.data_len() != TicketPurchase::LEN {
+                    check("ticket_record", false, "ticket purchase record".to_string(), record_info.key.to_string());
+                    continue;
+                }
+                let record = TicketPurchase::unpack_unchecked(&record_info.data.borrow())?;
+                if record.raffle != *raffle_info.key {
+                    check("ticket_record", false, raffle_info.key.to_string(), record.raffle.to_string());
+                    continue;
+                }
+                // Refunded records are cleared and hold no tickets
+                if record.is_initialized {
+                    records.push((*record_info.key, record));
+                }
+            }
+            // Ticket ranges are laid out in ascending pubkey order
+            records.sort_by(|a, b| a.0.cmp(&b.0));
+            records.dedup_by(|a, b| a.0 == b.0);
+
+            let tickets_sold = if raffle_data.status == RaffleStatus::Cancelled {
+                raffle_data.tickets_sold
+            } else {
+                raffle_data.draw_tickets_sold()
+            };
+            let record_tickets = records.iter().fold(0u64, |total, (_, record)| total.saturating_add(record.ticket_count));
+            check("ticket_records", record_tickets == tickets_sold,
+                  tickets_sold.to_string(), record_tickets.to_string());
+
+            // Every purchase that opened a record counted one new participant
+            if raffle_data.status != RaffleStatus::Cancelled {
+                let participants = raffle_data.draw_participant_count();
+                check("participant_count", records.len() as u64 == participants,
+                      records.len().to_string(), participants.to_string());
+            }
+
+            if raffle_data.status == RaffleStatus::Complete && record_tickets == tickets_sold {
+                let expected_winner = if records.len() == 1 {
+                    // A sole holder wins without a draw
+                    Some(records[0].0)
+                } else if raffle_data.vrf_account == *vrf_account_info.key {
+                    // A draw that can't be recomputed is itself a finding, not a reason to stop the report
+                    let winner_index = vrf::verify_vrf_result(vrf_account_info, switchboard_program_info)
+                        .and_then(|vrf_result| vrf::get_random_winner_index(vrf_result, tickets_sold));
+                    match winner_index {
+                        Ok(winner_index) => {
+                            let purchases: Vec<TicketPurchase> = records.iter().map(|(_, purchase)| *purchase).collect();
+                            crate::utils::resolve_winner(&purchases, winner_index).map(|position| records[position].0)
+                        }
+                        Err(err) => {
+                            check("vrf_result", false, "a winner drawn from the VRF result".to_string(), err.to_string());
+                            None
+                        }
+                    }
+                } else {
+                    check("vrf_account", false, raffle_data.vrf_account.to_string(), vrf_account_info.key.to_string());
+                    None
+                };
+                if let Some(expected_winner) = expected_winner {
+                    // A forfeited prize moves the winner to the recorded runner-up
+                    let forfeited = raffle_data.runner_up != Pubkey::default()
+                        && raffle_data.winner == raffle_data.runner_up;
+                    check("winner", raffle_data.winner == expected_winner || forfeited,
+                          expected_winner.to_string(), raffle_data.winner.to_string());
+                }
+            }
+        }
+
+        let mut return_data = [0u8; 8 + 8];
+        return_data[..8].copy_from_slice(&checks.to_le_bytes());
+        return_data[8..].copy_from_slice(&discrepancies.to_le_bytes());
+        set_return_data(&return_data);
+
+        msg!("AuditReport: raffle_index={}, status={:?}, checks={}, discrepancies={}",
+             raffle_data.raffle_index, raffle_data.status, checks, discrepancies);
+        Ok(())
+    }
+
+    /// Process ConsolationDraw instruction
+    /// Draws a second ticket from the completed raffle's VRF result, excluding the winner's
+    /// range, and pays its purchaser the consolation pool held back from fees. Completion now
+    /// pays the pool itself, so this only finds a pool on raffles completed before it did.
+    fn process_consolation_draw(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initiator_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vrf_account_info = next_account_info(account_info_iter)?;
+        let switchboard_program_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let recipient_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let ticket_record_infos = account_info_iter.as_slice();
+
+        Self::check_system_program(system_program_info, 6)?;
+
+        if !initiator_info.is_signer {
+            msg!("Initiator must sign the transaction");
             return Err(ProgramError::MissingRequiredSignature);
         }
 
         // Check that raffle account is owned by our program
         if raffle_info.owner != program_id {
-            msg!("Raffle account must be owned by the program");
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        // Get the raffle data
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
         let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
-        
-        // Anyone can request randomness for a raffle (fully decentralized approach)
 
-        // Check if raffle is in the correct state (ReadyForRandomness)
-        if raffle_data.status != RaffleStatus::ReadyForRandomness {
-            msg!("Raffle is not in ReadyForRandomness state. Current status: {:?}", raffle_data.status);
+        if raffle_data.status != RaffleStatus::Complete {
+            msg!("Consolation draw happens after the main winner is chosen");
             return Err(ProgramError::InvalidAccountData);
         }
-        
-        // Check if VRF request is already in progress
-        if raffle_data.vrf_request_in_progress {
-            msg!("VRF request is already in progress");
+        if raffle_data.consolation_pool == 0 || raffle_data.consolation_winner != Pubkey::default() {
+            msg!("Raffle has no consolation pool to draw");
             return Err(ProgramError::InvalidAccountData);
         }
 
-        // Check if any tickets were sold
-        if raffle_data.tickets_sold == 0 {
-            msg!("No tickets were sold, cannot complete raffle");
-            return Err(ProgramError::InvalidAccountData);
+        let vault_bump = Self::check_vault(program_id, raffle_info.key, vault_info.key, 4)?;
+
+        let records = Self::assert_ticket_accounting(
+            raffle_info.key,
+            ticket_record_infos,
+            raffle_data.tickets_sold,
+            program_id,
+        )?;
+        let winning_position = records.iter().position(|(record_key, _)| *record_key == raffle_data.winner)
+            .ok_or(ProgramError::InvalidArgument)?;
+
+        // With a single buyer there are no non-winning tickets, so the fees go back to the treasury
+        let (consolation_winner, expected_recipient) = if records.len() == 1 {
+            msg!("Winner holds every ticket, consolation pool returns to the treasury");
+            (raffle_data.treasury, raffle_data.treasury)
+        } else {
+            if raffle_data.vrf_account != *vrf_account_info.key {
+                msg!("VRF account does not match the one registered with this raffle");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let vrf_result = vrf::verify_vrf_result(vrf_account_info, switchboard_program_info)?;
+
+            let ticket_counts: Vec<u64> = records.iter().map(|(_, purchase)| purchase.ticket_count).collect();
+            let consolation_position = crate::raffle_core::draw_distinct_record(
+                &vrf_result,
+                &ticket_counts,
+                &[winning_position],
+                2,
+            )
+            .map_err(|err| {
+                msg!("{}", err);
+                ProgramError::InvalidArgument
+            })?;
+            (records[consolation_position].0, records[consolation_position].1.purchaser)
+        };
+
+        if *recipient_info.key != expected_recipient {
+            msg!("Consolation recipient should be {}", expected_recipient);
+            return Err(ProgramError::InvalidArgument);
         }
 
-        // Request VRF randomness from Switchboard
-        vrf::request_vrf_randomness(
-            vrf_account_info,
-            payer_info, 
-            authority_info, // Now treated as initiator (can be any user)
-            switchboard_program_info,
-            oracle_queue_info,
-            None, // permission_account_info
-            None, // escrow_account_info
-            None, // payer_wallet_info
-            &remaining_accounts, // Pass the collected accounts
+        let consolation_amount = raffle_data.consolation_pool;
+        raffle_data.consolation_winner = consolation_winner;
+        raffle_data.consolation_paid = consolation_amount;
+        raffle_data.consolation_pool = 0;
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
+
+        Self::check_wallet_payout(recipient_info, consolation_amount, 5)?;
+
+        invoke_signed(
+            &system_instruction::transfer(
+                vault_info.key,
+                recipient_info.key,
+                consolation_amount,
+            ),
+            &[
+                vault_info.clone(),
+                recipient_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[b"vault", raffle_info.key.as_ref(), &[vault_bump]]],
         )?;
 
-        // Update raffle to indicate VRF request is in progress
-        raffle_data.vrf_account = *vrf_account_info.key;
-        raffle_data.vrf_request_in_progress = true;
+        msg!("Consolation prize of {} lamports paid to {}", consolation_amount, recipient_info.key);
+        Ok(())
+    }
+
+    /// Process DonateToPool instruction
+    /// Sponsors add to the prize directly; the amount is tracked apart from ticket revenue
+    fn process_donate_to_pool(
+        accounts: &[AccountInfo],
+        amount: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let donor_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        Self::check_system_program(system_program_info, 3)?;
+
+        if amount == 0 {
+            msg!("Donation must be greater than zero");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !donor_info.is_signer {
+            msg!("Donor must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_vault(program_id, raffle_info.key, vault_info.key, 2)?;
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // Donations only make sense while the prize is still to be drawn
+        let accepting = match raffle_data.status {
+            RaffleStatus::Active | RaffleStatus::ReadyForRandomness => raffle_data.uses_vault,
+            _ => false,
+        };
+        if !accepting {
+            msg!("Raffle is not accepting donations. Current status: {:?}", raffle_data.status);
+            return Err(RaffleError::RaffleNotActive.into());
+        }
+
+        raffle_data.donated_amount = raffle_data.donated_amount.checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
         Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
 
-        msg!("VRF randomness requested successfully for raffle: {}", raffle_info.key);
+        invoke(
+            &system_instruction::transfer(
+                donor_info.key,
+                vault_info.key,
+                amount,
+            ),
+            &[
+                donor_info.clone(),
+                vault_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+
+        msg!("DonationEvent: raffle_index={}, donor={}, amount={}, total_donated={}",
+             raffle_data.raffle_index, donor_info.key, amount, raffle_data.donated_amount);
         Ok(())
     }
 
-    /// Process CompleteRaffleWithVrf instruction - Step 2 of the raffle completion process
-    /// This uses the VRF random bytes to select a winner
-    fn process_complete_raffle_with_vrf(
+    /// Process SlashCreatorBond instruction
+    /// Anyone but the creator can force an abandoned raffle forward, forfeiting the creator's
+    /// bond into the prize for the buyers
+    fn process_slash_creator_bond(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
     ) -> ProgramResult {
-        // Updated import to fix compiler errors
-        use crate::vrf::{verify_vrf_result, get_random_winner_index};
-        
         let account_info_iter = &mut accounts.iter();
-        let authority_info = next_account_info(account_info_iter)?;
+        let initiator_info = next_account_info(account_info_iter)?;
         let raffle_info = next_account_info(account_info_iter)?;
-        let vrf_account_info = next_account_info(account_info_iter)?;
-        let winner_info = next_account_info(account_info_iter)?;
-        let switchboard_program_info = next_account_info(account_info_iter)?;
         let clock_info = next_account_info(account_info_iter)?;
 
-        // Any user can create a raffle
-        if !authority_info.is_signer {
+        // Verify the initiator signed the transaction
+        if !initiator_info.is_signer {
             msg!("Initiator must sign the transaction");
             return Err(ProgramError::MissingRequiredSignature);
         }
@@ -831,96 +4077,178 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        // Get the raffle data
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
         let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
 
-        // Anyone can complete the raffle (fully decentralized approach)
-
-        // Check if raffle is in ReadyForRandomness state
-        if raffle_data.status != RaffleStatus::ReadyForRandomness {
-            msg!("Raffle is not in ReadyForRandomness state. Current state: {:?}", raffle_data.status);
+        // The creator can simply prepare the raffle themselves
+        if *initiator_info.key == raffle_data.authority {
+            msg!("The raffle authority cannot slash their own bond");
             return Err(ProgramError::InvalidArgument);
         }
 
-        // Check if VRF request is in progress
-        if !raffle_data.vrf_request_in_progress {
-            msg!("VRF request has not been initiated yet");
-            return Err(ProgramError::InvalidArgument);
+        // Only a raffle still waiting to be prepared has been abandoned
+        if raffle_data.status != RaffleStatus::Active {
+            msg!("Raffle is not in Active state");
+            return Err(RaffleError::RaffleNotActive.into());
         }
 
-        // Check if VRF account matches
-        if raffle_data.vrf_account != *vrf_account_info.key {
-            msg!("VRF account does not match the one registered with this raffle");
+        if raffle_data.creator_bond == 0 || raffle_data.creator_bond_slashed {
+            msg!("Raffle has no bond to slash");
             return Err(ProgramError::InvalidArgument);
         }
 
-        // Get the current time
-        let clock = Clock::from_account_info(clock_info)?;
-        let current_time = clock.unix_timestamp;
+        // With no buyers there is nobody for the bond to benefit
+        if raffle_data.tickets_sold == 0 {
+            msg!("No tickets were sold, the bond is returned when the empty raffle is closed");
+            return Err(RaffleError::NoTicketsSold.into());
+        }
 
-        // Check if raffle has ended
-        if current_time < raffle_data.end_time {
-            msg!("Raffle has not ended yet");
+        let clock = Clock::from_account_info(clock_info)?;
+        let slashable_at = raffle_data.sales_close_time().saturating_add(CREATOR_BOND_GRACE_SECONDS);
+        if clock.unix_timestamp < slashable_at {
+            msg!("Creator has until {} to prepare the raffle", slashable_at);
             return Err(ProgramError::InvalidArgument);
         }
 
-        // Verify VRF result
-        let vrf_result = verify_vrf_result(vrf_account_info, switchboard_program_info)?;
-        
-        // Get random winner index
-        let winner_index = get_random_winner_index(vrf_result, raffle_data.tickets_sold);
-        msg!("Random winner index: {}", winner_index);
+        // The bond stays in the vault and is paid out with the prize at completion
+        raffle_data.creator_bond_slashed = true;
+        raffle_data.close_sales();
+        Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
 
-        // With the keypair approach, we verify the winner by checking the ticket purchase account
-        if winner_info.owner != program_id {
-            msg!("Winner account must be a valid ticket purchase account owned by this program");
+        msg!("Creator bond of {} lamports slashed into the prize, raffle prepared for randomness by {}",
+             raffle_data.creator_bond, initiator_info.key);
+        Ok(())
+    }
+
+    /// Process RetargetVrf instruction
+    /// Clears a timed-out VRF request and moves the raffle to a new allowlisted oracle queue
+    fn process_retarget_vrf(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let signer_info = next_account_info(account_info_iter)?;
+        let raffle_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let oracle_queue_info = next_account_info(account_info_iter)?;
+
+        if !signer_info.is_signer {
+            msg!("Raffle authority or admin must sign the transaction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only the canonical config PDA carries admin authority
+        Self::check_config(program_id, config_info, 2)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
-        
-        // Fetch and verify the ticket purchase data
-        let ticket_data = TicketPurchase::unpack(&winner_info.data.borrow())?;
-        
-        // Verify this is a valid ticket purchase for this raffle
-        if !ticket_data.is_initialized || ticket_data.raffle != *raffle_info.key || ticket_data.ticket_count == 0 {
-            msg!("Invalid winner account - not a valid ticket purchase for this raffle");
+
+        let config_data = Config::unpack(&config_info.data.borrow())?;
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        if raffle_data.authority != *signer_info.key && config_data.admin != *signer_info.key {
+            msg!("Only the raffle authority or the admin can retarget a VRF request");
             return Err(ProgramError::InvalidAccountData);
         }
-        
-        msg!("Winner has {} tickets in the raffle", ticket_data.ticket_count);
-        
-        // In a real-world implementation with many ticket purchases, we would verify that
-        // this specific purchase account corresponds to the winning ticket index.
-        // 
-        // For our implementation with keypairs, where each user has their own ticket purchase account,
-        // we trust that the client has correctly submitted the winning account based on the random index.
-        
-        // Log the winner's ticket count and total tickets for transparency
-        msg!("Winner verification: Account owns {}/{} tickets", 
-             ticket_data.ticket_count, raffle_data.tickets_sold);
-        
-        // Set the winner's pubkey
-        raffle_data.winner = *winner_info.key;
 
-        // Update raffle status
-        raffle_data.status = RaffleStatus::Complete;
+        if raffle_data.status != RaffleStatus::ReadyForRandomness || !raffle_data.vrf_request_in_progress {
+            msg!("Raffle has no pending VRF request");
+            return Err(RaffleError::VrfNotRequested.into());
+        }
+
+        // Give the current request the same window RequestRandomness does before replacing it
+        let current_time = Clock::get()?.unix_timestamp;
+        let retry_at = raffle_data.vrf_requested_at.saturating_add(VRF_REQUEST_TIMEOUT_SECONDS);
+        if current_time < retry_at {
+            msg!("VRF request can be retargeted after {}", retry_at);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // The new queue must be the one the admin has vetted, and not the queue that stalled
+        if config_data.oracle_queue == Pubkey::default() || *oracle_queue_info.key != config_data.oracle_queue {
+            msg!("Oracle queue {} is not the allowlisted queue", oracle_queue_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *oracle_queue_info.key == raffle_data.oracle_queue {
+            msg!("Raffle already requests randomness from oracle queue {}", oracle_queue_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let stale_vrf_account = raffle_data.vrf_account;
+        let stale_oracle_queue = raffle_data.oracle_queue;
+        raffle_data.vrf_account = Pubkey::default();
         raffle_data.vrf_request_in_progress = false;
+        raffle_data.vrf_requested_at = 0;
+        raffle_data.vrf_requested_slot = 0;
+        raffle_data.vrf_requester = Pubkey::default();
+        raffle_data.oracle_queue = *oracle_queue_info.key;
         Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
 
-        // Transfer the prize to the winner
-        // Get the lamport balance to transfer
-        let prize_amount = raffle_info.lamports();
-        
-        **raffle_info.lamports.borrow_mut() = 0;
-        **winner_info.lamports.borrow_mut() = winner_info.lamports().checked_add(prize_amount)
-            .ok_or(ProgramError::InvalidArgument)?;
+        msg!("Dropped VRF request {} on oracle queue {} for raffle {}; request randomness again on oracle queue {}",
+             stale_vrf_account, stale_oracle_queue, raffle_data.raffle_index, raffle_data.oracle_queue);
+        Ok(())
+    }
 
-        msg!("Raffle completed with VRF randomness! Winner: {}", winner_info.key);
+    /// Process GetVersion instruction
+    /// Lets operators confirm which build is live after an upgrade
+    fn process_get_version() -> ProgramResult {
+        let mut return_data = Vec::with_capacity(1 + crate::PROGRAM_VERSION.len());
+        return_data.push(INSTRUCTION_SCHEMA_VERSION);
+        return_data.extend_from_slice(crate::PROGRAM_VERSION.as_bytes());
+        set_return_data(&return_data);
+
+        msg!("Program version: {}, instruction schema version: {}",
+             crate::PROGRAM_VERSION, INSTRUCTION_SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// Process GetWinnerInfo instruction
+    /// Logs the recorded outcome of a completed raffle for transparency pages
+    fn process_get_winner_info(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_info = next_account_info(account_info_iter)?;
+
+        // Check that raffle account is owned by our program
+        if raffle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
+        let raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
+
+        // Only completed raffles have a winner to report
+        if raffle_data.status != RaffleStatus::Complete {
+            msg!("WinnerInfo: raffle={}, index={}, status={:?}",
+                 raffle_info.key, raffle_data.raffle_index, raffle_data.status);
+            return Ok(());
+        }
+
+        // Every tier is reported: the winner, the runner-up who can take a forfeited prize, and
+        // the consolation draw. An undrawn consolation pool shows as consolation_pool
+        msg!("WinnerInfo: raffle={}, index={}, status={:?}, winner={}, prize_amount={}, completed_at={}, vrf_account={}, runner_up={}, consolation_winner={}, consolation_paid={}, consolation_pool={}",
+             raffle_info.key,
+             raffle_data.raffle_index,
+             raffle_data.status,
+             raffle_data.winner,
+             raffle_data.prize_amount,
+             raffle_data.completed_at,
+             raffle_data.vrf_account,
+             raffle_data.runner_up,
+             raffle_data.consolation_winner,
+             raffle_data.consolation_paid,
+             raffle_data.consolation_pool);
         Ok(())
     }
-}
 
     /// Process PrepareRaffle instruction
     /// This transitions a raffle from Active to ReadyForRandomness when the time has ended
+    /// or, for raffles with a soft cap, when the authority closes sales after reaching it
     fn process_prepare_raffle(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
@@ -942,6 +4270,7 @@ impl Processor {
         }
 
         // Get the raffle data
+        Self::check_data_len(raffle_info, Raffle::LEN, "Raffle")?;
         let mut raffle_data = Raffle::unpack(&raffle_info.data.borrow())?;
 
         // Check if raffle is active
@@ -954,10 +4283,21 @@ impl Processor {
         let clock = Clock::from_account_info(clock_info)?;
         let current_time = clock.unix_timestamp;
 
-        // Check if raffle has ended
-        if current_time < raffle_data.end_time {
-            msg!("Raffle has not ended yet");
-            return Err(ProgramError::InvalidArgument);
+        // Check if raffle has ended, including any purchase grace window; reaching the soft cap
+        // makes it eligible early
+        if current_time < raffle_data.sales_close_time() {
+            if !raffle_data.is_early_draw_eligible() {
+                msg!("Raffle has not ended yet");
+                return Err(ProgramError::InvalidArgument);
+            }
+            // Below the hard cap sales stay open after the soft cap; only the creator may cut
+            // them short to draw early
+            let sold_out = raffle_data.draw_on_sellout && raffle_data.is_sold_out();
+            if !sold_out && *authority_info.key != raffle_data.authority {
+                msg!("Only the raffle authority can close sales at the soft cap before {}",
+                     raffle_data.sales_close_time());
+                return Err(ProgramError::InvalidAccountData);
+            }
         }
 
         // Check if any tickets were sold
@@ -965,9 +4305,16 @@ impl Processor {
             msg!("No tickets were sold, cannot prepare raffle for randomness");
             return Err(ProgramError::InvalidArgument);
         }
+
+        // A pool not worth the VRF fee is never drawn; anyone may cancel it so buyers can refund
+        if raffle_data.is_below_min_pool() {
+            msg!("Prize pool of {} lamports is below the {} lamport minimum, the raffle can be cancelled for refunds",
+                 raffle_data.prize_pool_value(), raffle_data.min_pool_lamports);
+            return Err(RaffleError::PoolBelowMinimum.into());
+        }
         
-        // Update raffle status to ReadyForRandomness
-        raffle_data.status = RaffleStatus::ReadyForRandomness;
+        // Update raffle status to ReadyForRandomness, fixing the totals the draw will use
+        raffle_data.close_sales();
         
         // Save updated raffle data
         Raffle::pack(raffle_data, &mut raffle_info.data.borrow_mut())?;
@@ -975,3 +4322,4 @@ impl Processor {
         msg!("Raffle prepared for randomness request");
         Ok(())
     }
+}
@@ -8,6 +8,7 @@ use std::convert::TryFrom;
 
 /// Status of a raffle
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-state", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub enum RaffleStatus {
     /// Raffle is open for entries
     Active,
@@ -15,6 +16,8 @@ pub enum RaffleStatus {
     ReadyForRandomness,
     /// Raffle is complete and winner has been chosen
     Complete,
+    /// Raffle was cancelled by its authority and buyers can claim refunds
+    Cancelled,
 }
 
 impl TryFrom<u8> for RaffleStatus {
@@ -25,6 +28,7 @@ impl TryFrom<u8> for RaffleStatus {
             0 => Ok(RaffleStatus::Active),
             1 => Ok(RaffleStatus::ReadyForRandomness),
             2 => Ok(RaffleStatus::Complete),
+            3 => Ok(RaffleStatus::Cancelled),
             _ => Err("Invalid raffle status"),
         }
     }
@@ -36,18 +40,41 @@ impl From<RaffleStatus> for u8 {
             RaffleStatus::Active => 0,
             RaffleStatus::ReadyForRandomness => 1,
             RaffleStatus::Complete => 2,
+            RaffleStatus::Cancelled => 3,
         }
     }
 }
 
+/// Longest title, in bytes before the first null, accepted for display
+pub const MAX_TITLE_LEN: usize = 24;
+
+/// Size of a raffle account created before the vault model, holding the fields up to `raffle_index`
+pub const LEGACY_RAFFLE_LEN: usize = 205;
+
+/// Size of a config account created before the admin settings, holding the fields up to `next_raffle_index`
+pub const LEGACY_CONFIG_LEN: usize = 83;
+
+/// Number of raffle durations the admin can allowlist in the config
+pub const MAX_ALLOWED_DURATIONS: usize = 4;
+
+/// Seconds a pending VRF request blocks new requests before it can be replaced
+pub const VRF_REQUEST_TIMEOUT_SECONDS: i64 = 60 * 60;
+
+/// Largest keeper reward the admin may set, in basis points of the prize pool
+pub const MAX_KEEPER_REWARD_BPS: u16 = 500;
+
+/// Seconds after sales close the creator has to prepare the raffle before their bond can be slashed
+pub const CREATOR_BOND_GRACE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
 /// Raffle account data
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "borsh-state", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct Raffle {
     /// Is the account initialized
     pub is_initialized: bool,
     /// Creator of the raffle (but anyone can complete the raffle - fully decentralized)
     pub authority: Pubkey,
-    /// Title of the raffle (max 32 chars)
+    /// Title of the raffle (null-padded, at most `MAX_TITLE_LEN` bytes before the padding)
     pub title: [u8; 32],
     /// End time of the raffle (Unix timestamp)
     pub end_time: UnixTimestamp,
@@ -71,10 +98,104 @@ pub struct Raffle {
     pub nonce: u64,
     /// Sequential ID number for this raffle (1, 2, 3, etc.)
     pub raffle_index: u64,
+    /// Prize amount paid to the winner in lamports (zero if not completed)
+    pub prize_amount: u64,
+    /// Time the raffle was completed (Unix timestamp, zero if not completed)
+    pub completed_at: UnixTimestamp,
+    /// Maximum number of tickets that can be sold (zero means no cap)
+    pub max_tickets: u64,
+    /// Buyer principal held in the vault (ticket revenue net of fees)
+    pub pool_principal: u64,
+    /// Maximum number of distinct buyers (zero means no cap)
+    pub max_participants: u64,
+    /// Number of distinct ticket purchase records created
+    pub participant_count: u64,
+    /// Whether the prize pool is held in the vault PDA (false for legacy raffles)
+    pub uses_vault: bool,
+    /// Creator-funded prize deposited into the vault at creation
+    pub fixed_prize_lamports: u64,
+    /// Whether the raffle moves to ReadyForRandomness as soon as its supply sells out
+    pub draw_on_sellout: bool,
+    /// Tickets sold after which the authority may close sales and draw before end_time (zero for none)
+    pub soft_cap_tickets: u64,
+    /// Whether new purchases are paused for this raffle
+    pub frozen: bool,
+    /// Distinct buyers needed for the fixed prize to be paid out (zero for no minimum)
+    pub min_unique_participants: u64,
+    /// Seconds the winner has to claim the prize before it can pass to the runner-up (zero pays out at completion)
+    pub claim_window: u64,
+    /// Time after which an unclaimed prize can be forfeited to the runner-up
+    pub claim_deadline: UnixTimestamp,
+    /// Ticket purchase record holding the second-drawn ticket
+    pub runner_up: Pubkey,
+    /// Whether the prize has left the vault
+    pub prize_claimed: bool,
+    /// Seconds after end_time during which purchases are still accepted
+    pub purchase_grace_seconds: u64,
+    /// Share of each purchase fee, in basis points, held back for the consolation draw
+    pub consolation_bps: u16,
+    /// Fee lamports held in the vault for the consolation draw, zero once paid
+    pub consolation_pool: u64,
+    /// Ticket record drawn for the consolation pool, or the treasury (default until paid)
+    pub consolation_winner: Pubkey,
+    /// When the pending VRF request was made, used to let a stalled request be replaced
+    pub vrf_requested_at: UnixTimestamp,
+    /// Refundable bond held in the vault until completion
+    pub creator_bond: u64,
+    /// Whether the bond was slashed into the prize because the creator abandoned the raffle
+    pub creator_bond_slashed: bool,
+    /// Number of installments the prize is paid in, zero or one for a lump sum
+    pub payout_installments: u8,
+    /// Seconds between installment claims
+    pub payout_interval: u64,
+    /// Installments the winner has claimed so far
+    pub installments_claimed: u8,
+    /// When the last installment was claimed
+    pub last_installment_at: UnixTimestamp,
+    /// Tickets in one purchase from which the bulk fee applies, zero for no discount
+    pub bulk_threshold: u64,
+    /// Fee in basis points charged on bulk purchases
+    pub bulk_fee_bps: u16,
+    /// Lamports sponsors have donated to the prize, kept apart from ticket revenue
+    pub donated_amount: u64,
+    /// Address that receives the charity share of the prize
+    pub charity: Pubkey,
+    /// Share of the prize, in basis points, paid to the charity at completion
+    pub charity_bps: u16,
+    /// Share of the treasury fee, in basis points, redirected to a purchase's referrer
+    pub referral_bps: u16,
+    /// Slot of the pending VRF request; completion must land in a later slot
+    pub vrf_requested_slot: u64,
+    /// Tickets sold when sales closed; the draw is made over this total
+    pub closing_tickets_sold: u64,
+    /// Unique participants when sales closed
+    pub closing_participant_count: u64,
+    /// Smallest prize pool in lamports worth drawing; below it the raffle is cancelled for refunds
+    pub min_pool_lamports: u64,
+    /// Largest prize paid to the winner, zero for no cap; prize above it goes to the treasury
+    pub max_prize_lamports: u64,
+    /// Account that paid for the pending randomness request, rewarded at completion
+    pub vrf_requester: Pubkey,
+    /// Share of the prize pool, in basis points, paid to the randomness requester (fixed at creation)
+    pub request_reward_bps: u16,
+    /// Share of the prize pool, in basis points, paid to the completer (fixed at creation)
+    pub complete_reward_bps: u16,
+    /// Most fees taken across the whole raffle, zero for no cap; purchases past it pay no fee
+    pub max_total_fee_lamports: u64,
+    /// Fees taken from purchases so far, counted against max_total_fee_lamports
+    pub fees_collected: u64,
+    /// Lamports a purchaser must hold before buying, zero for no minimum
+    pub min_buyer_balance: u64,
+    /// Oracle queue randomness is requested from, unset until the first request; only
+    /// RetargetVrf moves it
+    pub oracle_queue: Pubkey,
+    /// Lamports paid from the consolation pool to consolation_winner, zero until it is drawn
+    pub consolation_paid: u64,
 }
 
 /// Program configuration account
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "borsh-state", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct Config {
     /// Is the account initialized
     pub is_initialized: bool,
@@ -88,6 +209,63 @@ pub struct Config {
     pub fee_basis_points: u16,
     /// Counter for sequential raffle IDs
     pub next_raffle_index: u64,
+    /// Lowest ticket price the admin may set, in lamports
+    pub min_ticket_price: u64,
+    /// Highest ticket price the admin may set, in lamports
+    pub max_ticket_price: u64,
+    /// The only Switchboard oracle queue randomness may be requested from
+    pub oracle_queue: Pubkey,
+    /// Whether anyone may request randomness and complete raffles, or only the raffle authority and admin
+    pub permissionless_completion: bool,
+    /// Durations in seconds raffles may be created with; zero slots are unused and all zeros allows any
+    pub allowed_durations: [u64; MAX_ALLOWED_DURATIONS],
+    /// Tickets in one purchase from which the bulk fee applies, zero for no discount
+    pub bulk_threshold: u64,
+    /// Discounted fee in basis points for purchases of at least bulk_threshold tickets
+    pub bulk_fee_bps: u16,
+    /// Seconds between requesting randomness and completing, so the committed result can be checked
+    pub draw_delay: u64,
+    /// Share of the prize pool, in basis points, paid to whoever completes a raffle
+    pub keeper_reward_bps: u16,
+    /// Share of the treasury fee, in basis points, paid to the referrer named in a purchase
+    pub referral_bps: u16,
+    /// Most raffles one authority may have open at once, zero for no limit
+    pub max_active_raffles: u64,
+    /// Share of the prize pool, in basis points, paid to whoever requested the randomness
+    pub request_reward_bps: u16,
+}
+
+impl Config {
+    /// Whether a raffle may be created with the given duration
+    pub fn is_duration_allowed(&self, duration: u64) -> bool {
+        self.allowed_durations.iter().all(|allowed| *allowed == 0)
+            || self.allowed_durations.iter().any(|allowed| *allowed != 0 && *allowed == duration)
+    }
+
+    /// Unpack a config account written with the `LEGACY_CONFIG_LEN` layout
+    ///
+    /// As with raffles, the legacy bytes are the prefix of the current layout and later fields
+    /// take their zero value. The exceptions keep a migrated program working as it did: the
+    /// ticket price band starts at its defaults, since an empty band would reject every
+    /// UpdateTicketPrice, and completion stays permissionless.
+    pub fn unpack_legacy(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        if src.len() != LEGACY_CONFIG_LEN {
+            return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+        }
+        let mut padded = [0u8; Config::LEN];
+        padded[..LEGACY_CONFIG_LEN].copy_from_slice(src);
+        let config = Self::unpack_from_slice(&padded)?;
+        if !config.is_initialized {
+            return Err(solana_program::program_error::ProgramError::UninitializedAccount);
+        }
+        let defaults = Config::default();
+        Ok(Config {
+            min_ticket_price: defaults.min_ticket_price,
+            max_ticket_price: defaults.max_ticket_price,
+            permissionless_completion: defaults.permissionless_completion,
+            ..config
+        })
+    }
 }
 
 impl Default for Config {
@@ -108,12 +286,25 @@ impl Default for Config {
             treasury: Pubkey::new_from_array(treasury_bytes),
             ticket_price: 25_000_000, // 0.025 SOL
             fee_basis_points: 1000,    // 10%
+            min_ticket_price: 1_000_000,      // 0.001 SOL
+            max_ticket_price: 10_000_000_000, // 10 SOL
+            oracle_queue: Pubkey::default(), // Must be set by the admin before randomness can be requested
+            permissionless_completion: true,
+            allowed_durations: [0; MAX_ALLOWED_DURATIONS], // Any duration
+            bulk_threshold: 0, // No bulk discount
+            bulk_fee_bps: 0,
+            draw_delay: 0, // Completion may follow the randomness request immediately
+            keeper_reward_bps: 0,
+            referral_bps: 0, // Referrals earn nothing until the admin sets a share
+            max_active_raffles: 0, // No limit
+            request_reward_bps: 0,
         }
     }
 }
 
 /// Ticket purchase record
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "borsh-state", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct TicketPurchase {
     /// Is the account initialized
     pub is_initialized: bool,
@@ -127,9 +318,178 @@ pub struct TicketPurchase {
     pub purchase_time: UnixTimestamp,
 }
 
+/// Pointer from a sequential raffle_index to the raffle's nonce-derived address
+#[derive(Debug, Clone, Copy)]
+pub struct RaffleIndexPointer {
+    /// Is the account initialized
+    pub is_initialized: bool,
+    /// The raffle with this index
+    pub raffle: Pubkey,
+}
+
+/// Running referral earnings for one referrer wallet
+#[derive(Debug, Clone, Copy)]
+pub struct Referral {
+    /// Is the account initialized
+    pub is_initialized: bool,
+    /// The wallet that receives referral rewards
+    pub referrer: Pubkey,
+    /// Total lamports paid to the referrer
+    pub total_earned: u64,
+    /// Number of referred purchases
+    pub referral_count: u64,
+}
+
+/// Number of raffles an authority currently has open
+#[derive(Debug, Clone, Copy)]
+pub struct AuthorityState {
+    /// Is the account initialized
+    pub is_initialized: bool,
+    /// The raffle authority this account counts for
+    pub authority: Pubkey,
+    /// Raffles created and not yet completed, cancelled or closed
+    pub active_raffle_count: u64,
+}
+
+/// Maximum number of wallets the blacklist can hold
+pub const MAX_BLACKLIST_ENTRIES: usize = 16;
+
+/// Wallets banned from participating in raffles
+#[derive(Debug, Clone, Copy)]
+pub struct Blacklist {
+    /// Is the account initialized
+    pub is_initialized: bool,
+    /// Number of entries in use
+    pub count: u8,
+    /// Banned wallets (only the first `count` entries are meaningful)
+    pub wallets: [Pubkey; MAX_BLACKLIST_ENTRIES],
+}
+
+impl Raffle {
+    /// Unpack a raffle account written with the `LEGACY_RAFFLE_LEN` layout
+    ///
+    /// The current layout only appends fields, so the legacy bytes are read as its prefix and
+    /// every later field takes its zero value; in particular `uses_vault` is false.
+    pub fn unpack_legacy(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        if src.len() != LEGACY_RAFFLE_LEN {
+            return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+        }
+        let mut padded = [0u8; Raffle::LEN];
+        padded[..LEGACY_RAFFLE_LEN].copy_from_slice(src);
+        let raffle = Self::unpack_from_slice(&padded)?;
+        if !raffle.is_initialized {
+            return Err(solana_program::program_error::ProgramError::UninitializedAccount);
+        }
+        Ok(raffle)
+    }
+
+    /// Whether every ticket of a capped raffle has been sold
+    pub fn is_sold_out(&self) -> bool {
+        self.max_tickets > 0 && self.tickets_sold >= self.max_tickets
+    }
+
+    /// Last moment a purchase is accepted, end_time plus the grace window
+    pub fn sales_close_time(&self) -> UnixTimestamp {
+        let grace = i64::try_from(self.purchase_grace_seconds).unwrap_or(i64::MAX);
+        self.end_time.saturating_add(grace)
+    }
+
+    /// Whether the raffle may be drawn before its end_time
+    pub fn is_early_draw_eligible(&self) -> bool {
+        (self.draw_on_sellout && self.is_sold_out())
+            || (self.soft_cap_tickets > 0 && self.tickets_sold >= self.soft_cap_tickets)
+    }
+
+    /// Close sales for the draw, snapshotting the totals it will be made over
+    pub fn close_sales(&mut self) {
+        self.status = RaffleStatus::ReadyForRandomness;
+        self.closing_tickets_sold = self.tickets_sold;
+        self.closing_participant_count = self.participant_count;
+    }
+
+    /// Ticket total to draw over: the snapshot taken when sales closed, or the live count
+    /// for raffles that closed before snapshots were recorded
+    pub fn draw_tickets_sold(&self) -> u64 {
+        if self.closing_tickets_sold > 0 {
+            self.closing_tickets_sold
+        } else {
+            self.tickets_sold
+        }
+    }
+
+    /// Participant count at the close of sales, falling back to the live count like `draw_tickets_sold`
+    pub fn draw_participant_count(&self) -> u64 {
+        if self.closing_tickets_sold > 0 {
+            self.closing_participant_count
+        } else {
+            self.participant_count
+        }
+    }
+
+    /// Lamports the draw would be worth: buyer principal, donations and any fixed prize
+    pub fn prize_pool_value(&self) -> u64 {
+        self.pool_principal
+            .saturating_add(self.donated_amount)
+            .saturating_add(self.fixed_prize_lamports)
+    }
+
+    /// Whether the pool falls short of the creator's minimum for a draw.
+    /// A raffle whose bond was slashed is drawn regardless, since the bond is now part of the prize.
+    pub fn is_below_min_pool(&self) -> bool {
+        !self.creator_bond_slashed
+            && self.min_pool_lamports > 0
+            && self.prize_pool_value() < self.min_pool_lamports
+    }
+
+    /// Part of a purchase's `fee` above what the fee cap still allows; it goes to the pool instead
+    pub fn fee_over_cap(&self, fee: u64) -> u64 {
+        if self.max_total_fee_lamports == 0 {
+            return 0;
+        }
+        let allowance = self.max_total_fee_lamports.saturating_sub(self.fees_collected);
+        fee.saturating_sub(allowance)
+    }
+
+    /// Fee rate for a purchase of `ticket_count` tickets, discounted at or above the bulk threshold
+    pub fn fee_bps_for(&self, ticket_count: u64) -> u16 {
+        if self.bulk_threshold > 0 && ticket_count >= self.bulk_threshold {
+            self.bulk_fee_bps.min(self.fee_basis_points)
+        } else {
+            self.fee_basis_points
+        }
+    }
+
+    /// Number of installments the prize is paid in, at least one
+    pub fn installment_count(&self) -> u8 {
+        self.payout_installments.max(1)
+    }
+
+    /// Lamports paid by the given zero-based installment; the last one carries the remainder
+    pub fn installment_amount(&self, installment: u8) -> u64 {
+        let count = self.installment_count() as u64;
+        let per_installment = self.prize_amount / count;
+        if installment as u64 + 1 >= count {
+            self.prize_amount - per_installment * (count - 1)
+        } else {
+            per_installment
+        }
+    }
+}
+
+impl Blacklist {
+    /// Whether the wallet is banned
+    pub fn contains(&self, wallet: &Pubkey) -> bool {
+        self.wallets[..self.count as usize].contains(wallet)
+    }
+}
+
 impl Sealed for Raffle {}
 impl Sealed for Config {}
 impl Sealed for TicketPurchase {}
+impl Sealed for RaffleIndexPointer {}
+impl Sealed for Blacklist {}
+impl Sealed for Referral {}
+impl Sealed for AuthorityState {}
 
 impl IsInitialized for Raffle {
     fn is_initialized(&self) -> bool {
@@ -149,8 +509,32 @@ impl IsInitialized for TicketPurchase {
     }
 }
 
+impl IsInitialized for RaffleIndexPointer {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Blacklist {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Referral {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for AuthorityState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
 impl Pack for Raffle {
-    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 1 + 32 + 8 + 2 + 32 + 32 + 1 + 8 + 8; // Added 8 bytes for raffle_index
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 1 + 32 + 8 + 2 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 1 + 8 + 8 + 8 + 32 + 1 + 8 + 2 + 8 + 32 + 8 + 8 + 1 + 1 + 8 + 1 + 8 + 8 + 2 + 8 + 32 + 2 + 2 + 8 + 8 + 8 + 8 + 8 + 32 + 2 + 2 + 8 + 8 + 8 + 32 + 8;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
         let src = array_ref![src, 0, Raffle::LEN];
@@ -169,8 +553,54 @@ impl Pack for Raffle {
             vrf_request_in_progress,
             nonce,
             raffle_index,
+            prize_amount,
+            completed_at,
+            max_tickets,
+            pool_principal,
+            max_participants,
+            participant_count,
+            uses_vault,
+            fixed_prize_lamports,
+            draw_on_sellout,
+            soft_cap_tickets,
+            frozen,
+            min_unique_participants,
+            claim_window,
+            claim_deadline,
+            runner_up,
+            prize_claimed,
+            purchase_grace_seconds,
+            consolation_bps,
+            consolation_pool,
+            consolation_winner,
+            vrf_requested_at,
+            creator_bond,
+            creator_bond_slashed,
+            payout_installments,
+            payout_interval,
+            installments_claimed,
+            last_installment_at,
+            bulk_threshold,
+            bulk_fee_bps,
+            donated_amount,
+            charity,
+            charity_bps,
+            referral_bps,
+            vrf_requested_slot,
+            closing_tickets_sold,
+            closing_participant_count,
+            min_pool_lamports,
+            max_prize_lamports,
+            vrf_requester,
+            request_reward_bps,
+            complete_reward_bps,
+            max_total_fee_lamports,
+            fees_collected,
+            min_buyer_balance,
+            oracle_queue,
+            consolation_paid,
         ) = array_refs![
-            src, 1, 32, 32, 8, 8, 1, 32, 8, 2, 32, 32, 1, 8, 8
+            src, 1, 32, 32, 8, 8, 1, 32, 8, 2, 32, 32, 1, 8, 8, 8, 8, 8, 8, 8, 8, 1, 8, 1, 8, 1, 8, 8, 8, 32, 1, 8, 2, 8, 32, 8, 8, 1, 1, 8, 1, 8, 8, 2, 8, 32, 2, 2, 8, 8, 8, 8, 8, 32, 2, 2, 8, 8, 8, 32, 8
         ];
 
         let status = match RaffleStatus::try_from(status[0]) {
@@ -193,6 +623,52 @@ impl Pack for Raffle {
             vrf_request_in_progress: vrf_request_in_progress[0] != 0,
             nonce: u64::from_le_bytes(*nonce),
             raffle_index: u64::from_le_bytes(*raffle_index),
+            prize_amount: u64::from_le_bytes(*prize_amount),
+            completed_at: UnixTimestamp::from_le_bytes(*completed_at),
+            max_tickets: u64::from_le_bytes(*max_tickets),
+            pool_principal: u64::from_le_bytes(*pool_principal),
+            max_participants: u64::from_le_bytes(*max_participants),
+            participant_count: u64::from_le_bytes(*participant_count),
+            uses_vault: uses_vault[0] != 0,
+            fixed_prize_lamports: u64::from_le_bytes(*fixed_prize_lamports),
+            draw_on_sellout: draw_on_sellout[0] != 0,
+            soft_cap_tickets: u64::from_le_bytes(*soft_cap_tickets),
+            frozen: frozen[0] != 0,
+            min_unique_participants: u64::from_le_bytes(*min_unique_participants),
+            claim_window: u64::from_le_bytes(*claim_window),
+            claim_deadline: UnixTimestamp::from_le_bytes(*claim_deadline),
+            runner_up: Pubkey::new_from_array(*runner_up),
+            prize_claimed: prize_claimed[0] != 0,
+            purchase_grace_seconds: u64::from_le_bytes(*purchase_grace_seconds),
+            consolation_bps: u16::from_le_bytes(*consolation_bps),
+            consolation_pool: u64::from_le_bytes(*consolation_pool),
+            consolation_winner: Pubkey::new_from_array(*consolation_winner),
+            vrf_requested_at: UnixTimestamp::from_le_bytes(*vrf_requested_at),
+            creator_bond: u64::from_le_bytes(*creator_bond),
+            creator_bond_slashed: creator_bond_slashed[0] != 0,
+            payout_installments: payout_installments[0],
+            payout_interval: u64::from_le_bytes(*payout_interval),
+            installments_claimed: installments_claimed[0],
+            last_installment_at: UnixTimestamp::from_le_bytes(*last_installment_at),
+            bulk_threshold: u64::from_le_bytes(*bulk_threshold),
+            bulk_fee_bps: u16::from_le_bytes(*bulk_fee_bps),
+            donated_amount: u64::from_le_bytes(*donated_amount),
+            charity: Pubkey::new_from_array(*charity),
+            charity_bps: u16::from_le_bytes(*charity_bps),
+            referral_bps: u16::from_le_bytes(*referral_bps),
+            vrf_requested_slot: u64::from_le_bytes(*vrf_requested_slot),
+            closing_tickets_sold: u64::from_le_bytes(*closing_tickets_sold),
+            closing_participant_count: u64::from_le_bytes(*closing_participant_count),
+            min_pool_lamports: u64::from_le_bytes(*min_pool_lamports),
+            max_prize_lamports: u64::from_le_bytes(*max_prize_lamports),
+            vrf_requester: Pubkey::new_from_array(*vrf_requester),
+            request_reward_bps: u16::from_le_bytes(*request_reward_bps),
+            complete_reward_bps: u16::from_le_bytes(*complete_reward_bps),
+            max_total_fee_lamports: u64::from_le_bytes(*max_total_fee_lamports),
+            fees_collected: u64::from_le_bytes(*fees_collected),
+            min_buyer_balance: u64::from_le_bytes(*min_buyer_balance),
+            oracle_queue: Pubkey::new_from_array(*oracle_queue),
+            consolation_paid: u64::from_le_bytes(*consolation_paid),
         })
     }
 
@@ -213,7 +689,53 @@ impl Pack for Raffle {
             vrf_request_in_progress_dst,
             nonce_dst,
             raffle_index_dst,
-        ) = mut_array_refs![dst, 1, 32, 32, 8, 8, 1, 32, 8, 2, 32, 32, 1, 8, 8];
+            prize_amount_dst,
+            completed_at_dst,
+            max_tickets_dst,
+            pool_principal_dst,
+            max_participants_dst,
+            participant_count_dst,
+            uses_vault_dst,
+            fixed_prize_lamports_dst,
+            draw_on_sellout_dst,
+            soft_cap_tickets_dst,
+            frozen_dst,
+            min_unique_participants_dst,
+            claim_window_dst,
+            claim_deadline_dst,
+            runner_up_dst,
+            prize_claimed_dst,
+            purchase_grace_seconds_dst,
+            consolation_bps_dst,
+            consolation_pool_dst,
+            consolation_winner_dst,
+            vrf_requested_at_dst,
+            creator_bond_dst,
+            creator_bond_slashed_dst,
+            payout_installments_dst,
+            payout_interval_dst,
+            installments_claimed_dst,
+            last_installment_at_dst,
+            bulk_threshold_dst,
+            bulk_fee_bps_dst,
+            donated_amount_dst,
+            charity_dst,
+            charity_bps_dst,
+            referral_bps_dst,
+            vrf_requested_slot_dst,
+            closing_tickets_sold_dst,
+            closing_participant_count_dst,
+            min_pool_lamports_dst,
+            max_prize_lamports_dst,
+            vrf_requester_dst,
+            request_reward_bps_dst,
+            complete_reward_bps_dst,
+            max_total_fee_lamports_dst,
+            fees_collected_dst,
+            min_buyer_balance_dst,
+            oracle_queue_dst,
+            consolation_paid_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 8, 8, 1, 32, 8, 2, 32, 32, 1, 8, 8, 8, 8, 8, 8, 8, 8, 1, 8, 1, 8, 1, 8, 8, 8, 32, 1, 8, 2, 8, 32, 8, 8, 1, 1, 8, 1, 8, 8, 2, 8, 32, 2, 2, 8, 8, 8, 8, 8, 32, 2, 2, 8, 8, 8, 32, 8];
 
         is_initialized_dst[0] = self.is_initialized as u8;
         authority_dst.copy_from_slice(self.authority.as_ref());
@@ -229,16 +751,67 @@ impl Pack for Raffle {
         vrf_request_in_progress_dst[0] = self.vrf_request_in_progress as u8;
         *nonce_dst = self.nonce.to_le_bytes();
         *raffle_index_dst = self.raffle_index.to_le_bytes();
+        *prize_amount_dst = self.prize_amount.to_le_bytes();
+        *completed_at_dst = self.completed_at.to_le_bytes();
+        *max_tickets_dst = self.max_tickets.to_le_bytes();
+        *pool_principal_dst = self.pool_principal.to_le_bytes();
+        *max_participants_dst = self.max_participants.to_le_bytes();
+        *participant_count_dst = self.participant_count.to_le_bytes();
+        uses_vault_dst[0] = self.uses_vault as u8;
+        *fixed_prize_lamports_dst = self.fixed_prize_lamports.to_le_bytes();
+        draw_on_sellout_dst[0] = self.draw_on_sellout as u8;
+        *soft_cap_tickets_dst = self.soft_cap_tickets.to_le_bytes();
+        frozen_dst[0] = self.frozen as u8;
+        *min_unique_participants_dst = self.min_unique_participants.to_le_bytes();
+        *claim_window_dst = self.claim_window.to_le_bytes();
+        *claim_deadline_dst = self.claim_deadline.to_le_bytes();
+        runner_up_dst.copy_from_slice(self.runner_up.as_ref());
+        prize_claimed_dst[0] = self.prize_claimed as u8;
+        *purchase_grace_seconds_dst = self.purchase_grace_seconds.to_le_bytes();
+        *consolation_bps_dst = self.consolation_bps.to_le_bytes();
+        *consolation_pool_dst = self.consolation_pool.to_le_bytes();
+        consolation_winner_dst.copy_from_slice(self.consolation_winner.as_ref());
+        *vrf_requested_at_dst = self.vrf_requested_at.to_le_bytes();
+        *creator_bond_dst = self.creator_bond.to_le_bytes();
+        creator_bond_slashed_dst[0] = self.creator_bond_slashed as u8;
+        payout_installments_dst[0] = self.payout_installments;
+        *payout_interval_dst = self.payout_interval.to_le_bytes();
+        installments_claimed_dst[0] = self.installments_claimed;
+        *last_installment_at_dst = self.last_installment_at.to_le_bytes();
+        *bulk_threshold_dst = self.bulk_threshold.to_le_bytes();
+        *bulk_fee_bps_dst = self.bulk_fee_bps.to_le_bytes();
+        *donated_amount_dst = self.donated_amount.to_le_bytes();
+        charity_dst.copy_from_slice(self.charity.as_ref());
+        *charity_bps_dst = self.charity_bps.to_le_bytes();
+        *referral_bps_dst = self.referral_bps.to_le_bytes();
+        *vrf_requested_slot_dst = self.vrf_requested_slot.to_le_bytes();
+        *closing_tickets_sold_dst = self.closing_tickets_sold.to_le_bytes();
+        *closing_participant_count_dst = self.closing_participant_count.to_le_bytes();
+        *min_pool_lamports_dst = self.min_pool_lamports.to_le_bytes();
+        *max_prize_lamports_dst = self.max_prize_lamports.to_le_bytes();
+        vrf_requester_dst.copy_from_slice(self.vrf_requester.as_ref());
+        *request_reward_bps_dst = self.request_reward_bps.to_le_bytes();
+        *complete_reward_bps_dst = self.complete_reward_bps.to_le_bytes();
+        *max_total_fee_lamports_dst = self.max_total_fee_lamports.to_le_bytes();
+        *fees_collected_dst = self.fees_collected.to_le_bytes();
+        *min_buyer_balance_dst = self.min_buyer_balance.to_le_bytes();
+        oracle_queue_dst.copy_from_slice(self.oracle_queue.as_ref());
+        *consolation_paid_dst = self.consolation_paid.to_le_bytes();
     }
 }
 
 impl Pack for Config {
-    const LEN: usize = 1 + 32 + 32 + 8 + 2 + 8; // Added 8 bytes for next_raffle_index
+    const LEN: usize = 1 + 32 + 32 + 8 + 2 + 8 + 8 + 8 + 32 + 1 + 8 * MAX_ALLOWED_DURATIONS + 8 + 2 + 8 + 2 + 2 + 8 + 2;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
         let src = array_ref![src, 0, Config::LEN];
-        let (is_initialized, admin, treasury, ticket_price, fee_basis_points, next_raffle_index) = 
-            array_refs![src, 1, 32, 32, 8, 2, 8];
+        let (is_initialized, admin, treasury, ticket_price, fee_basis_points, next_raffle_index, min_ticket_price, max_ticket_price, oracle_queue, permissionless_completion, allowed_durations_src, bulk_threshold, bulk_fee_bps, draw_delay, keeper_reward_bps, referral_bps, max_active_raffles, request_reward_bps) = 
+            array_refs![src, 1, 32, 32, 8, 2, 8, 8, 8, 32, 1, 8 * MAX_ALLOWED_DURATIONS, 8, 2, 8, 2, 2, 8, 2];
+
+        let mut allowed_durations = [0u64; MAX_ALLOWED_DURATIONS];
+        for (duration, bytes) in allowed_durations.iter_mut().zip(allowed_durations_src.chunks_exact(8)) {
+            *duration = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
 
         Ok(Config {
             is_initialized: is_initialized[0] != 0,
@@ -247,13 +820,25 @@ impl Pack for Config {
             ticket_price: u64::from_le_bytes(*ticket_price),
             fee_basis_points: u16::from_le_bytes(*fee_basis_points),
             next_raffle_index: u64::from_le_bytes(*next_raffle_index),
+            min_ticket_price: u64::from_le_bytes(*min_ticket_price),
+            max_ticket_price: u64::from_le_bytes(*max_ticket_price),
+            oracle_queue: Pubkey::new_from_array(*oracle_queue),
+            permissionless_completion: permissionless_completion[0] != 0,
+            allowed_durations,
+            bulk_threshold: u64::from_le_bytes(*bulk_threshold),
+            bulk_fee_bps: u16::from_le_bytes(*bulk_fee_bps),
+            draw_delay: u64::from_le_bytes(*draw_delay),
+            keeper_reward_bps: u16::from_le_bytes(*keeper_reward_bps),
+            referral_bps: u16::from_le_bytes(*referral_bps),
+            max_active_raffles: u64::from_le_bytes(*max_active_raffles),
+            request_reward_bps: u16::from_le_bytes(*request_reward_bps),
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, Config::LEN];
-        let (is_initialized_dst, admin_dst, treasury_dst, ticket_price_dst, fee_basis_points_dst, next_raffle_index_dst) = 
-            mut_array_refs![dst, 1, 32, 32, 8, 2, 8];
+        let (is_initialized_dst, admin_dst, treasury_dst, ticket_price_dst, fee_basis_points_dst, next_raffle_index_dst, min_ticket_price_dst, max_ticket_price_dst, oracle_queue_dst, permissionless_completion_dst, allowed_durations_dst, bulk_threshold_dst, bulk_fee_bps_dst, draw_delay_dst, keeper_reward_bps_dst, referral_bps_dst, max_active_raffles_dst, request_reward_bps_dst) = 
+            mut_array_refs![dst, 1, 32, 32, 8, 2, 8, 8, 8, 32, 1, 8 * MAX_ALLOWED_DURATIONS, 8, 2, 8, 2, 2, 8, 2];
 
         is_initialized_dst[0] = self.is_initialized as u8;
         admin_dst.copy_from_slice(self.admin.as_ref());
@@ -261,6 +846,20 @@ impl Pack for Config {
         *ticket_price_dst = self.ticket_price.to_le_bytes();
         *fee_basis_points_dst = self.fee_basis_points.to_le_bytes();
         *next_raffle_index_dst = self.next_raffle_index.to_le_bytes();
+        *min_ticket_price_dst = self.min_ticket_price.to_le_bytes();
+        *max_ticket_price_dst = self.max_ticket_price.to_le_bytes();
+        oracle_queue_dst.copy_from_slice(self.oracle_queue.as_ref());
+        permissionless_completion_dst[0] = self.permissionless_completion as u8;
+        for (bytes, duration) in allowed_durations_dst.chunks_exact_mut(8).zip(self.allowed_durations.iter()) {
+            bytes.copy_from_slice(&duration.to_le_bytes());
+        }
+        *bulk_threshold_dst = self.bulk_threshold.to_le_bytes();
+        *bulk_fee_bps_dst = self.bulk_fee_bps.to_le_bytes();
+        *draw_delay_dst = self.draw_delay.to_le_bytes();
+        *keeper_reward_bps_dst = self.keeper_reward_bps.to_le_bytes();
+        *referral_bps_dst = self.referral_bps.to_le_bytes();
+        *max_active_raffles_dst = self.max_active_raffles.to_le_bytes();
+        *request_reward_bps_dst = self.request_reward_bps.to_le_bytes();
     }
 }
 
@@ -293,3 +892,389 @@ impl Pack for TicketPurchase {
         *purchase_time_dst = self.purchase_time.to_le_bytes();
     }
 }
+
+impl Pack for RaffleIndexPointer {
+    const LEN: usize = 1 + 32;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        let src = array_ref![src, 0, RaffleIndexPointer::LEN];
+        let (is_initialized, raffle) = array_refs![src, 1, 32];
+
+        Ok(RaffleIndexPointer {
+            is_initialized: is_initialized[0] != 0,
+            raffle: Pubkey::new_from_array(*raffle),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, RaffleIndexPointer::LEN];
+        let (is_initialized_dst, raffle_dst) = mut_array_refs![dst, 1, 32];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        raffle_dst.copy_from_slice(self.raffle.as_ref());
+    }
+}
+
+impl Pack for Referral {
+    const LEN: usize = 1 + 32 + 8 + 8;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        let src = array_ref![src, 0, Referral::LEN];
+        let (is_initialized, referrer, total_earned, referral_count) = array_refs![src, 1, 32, 8, 8];
+
+        Ok(Referral {
+            is_initialized: is_initialized[0] != 0,
+            referrer: Pubkey::new_from_array(*referrer),
+            total_earned: u64::from_le_bytes(*total_earned),
+            referral_count: u64::from_le_bytes(*referral_count),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Referral::LEN];
+        let (is_initialized_dst, referrer_dst, total_earned_dst, referral_count_dst) =
+            mut_array_refs![dst, 1, 32, 8, 8];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        referrer_dst.copy_from_slice(self.referrer.as_ref());
+        *total_earned_dst = self.total_earned.to_le_bytes();
+        *referral_count_dst = self.referral_count.to_le_bytes();
+    }
+}
+
+impl Pack for AuthorityState {
+    const LEN: usize = 1 + 32 + 8;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        let src = array_ref![src, 0, AuthorityState::LEN];
+        let (is_initialized, authority, active_raffle_count) = array_refs![src, 1, 32, 8];
+
+        Ok(AuthorityState {
+            is_initialized: is_initialized[0] != 0,
+            authority: Pubkey::new_from_array(*authority),
+            active_raffle_count: u64::from_le_bytes(*active_raffle_count),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, AuthorityState::LEN];
+        let (is_initialized_dst, authority_dst, active_raffle_count_dst) = mut_array_refs![dst, 1, 32, 8];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        authority_dst.copy_from_slice(self.authority.as_ref());
+        *active_raffle_count_dst = self.active_raffle_count.to_le_bytes();
+    }
+}
+
+impl Pack for Blacklist {
+    const LEN: usize = 1 + 1 + 32 * MAX_BLACKLIST_ENTRIES;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        let src = array_ref![src, 0, Blacklist::LEN];
+        let (is_initialized, count, wallets_src) = array_refs![src, 1, 1, 32 * MAX_BLACKLIST_ENTRIES];
+
+        if count[0] as usize > MAX_BLACKLIST_ENTRIES {
+            return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+        }
+
+        let mut wallets = [Pubkey::default(); MAX_BLACKLIST_ENTRIES];
+        for (wallet, bytes) in wallets.iter_mut().zip(wallets_src.chunks_exact(32)) {
+            *wallet = Pubkey::new(bytes);
+        }
+
+        Ok(Blacklist {
+            is_initialized: is_initialized[0] != 0,
+            count: count[0],
+            wallets,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Blacklist::LEN];
+        let (is_initialized_dst, count_dst, wallets_dst) =
+            mut_array_refs![dst, 1, 1, 32 * MAX_BLACKLIST_ENTRIES];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        count_dst[0] = self.count;
+        for (bytes, wallet) in wallets_dst.chunks_exact_mut(32).zip(self.wallets.iter()) {
+            bytes.copy_from_slice(wallet.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every field set to a sentinel whose bytes are all equal to the field's 1-based position
+    fn sample_raffle() -> Raffle {
+        Raffle {
+            is_initialized: true,
+            authority: Pubkey::new_from_array([2; 32]),
+            title: [3; 32],
+            end_time: 0x0404_0404_0404_0404,
+            ticket_price: 0x0505_0505_0505_0505,
+            status: RaffleStatus::Cancelled,
+            winner: Pubkey::new_from_array([7; 32]),
+            tickets_sold: 0x0808_0808_0808_0808,
+            fee_basis_points: 0x0909,
+            treasury: Pubkey::new_from_array([10; 32]),
+            vrf_account: Pubkey::new_from_array([11; 32]),
+            vrf_request_in_progress: true,
+            nonce: 0x0d0d_0d0d_0d0d_0d0d,
+            raffle_index: 0x0e0e_0e0e_0e0e_0e0e,
+            prize_amount: 0x0f0f_0f0f_0f0f_0f0f,
+            completed_at: 0x1010_1010_1010_1010,
+            max_tickets: 0x1111_1111_1111_1111,
+            pool_principal: 0x1212_1212_1212_1212,
+            max_participants: 0x1313_1313_1313_1313,
+            participant_count: 0x1414_1414_1414_1414,
+            uses_vault: true,
+            fixed_prize_lamports: 0x1616_1616_1616_1616,
+            draw_on_sellout: true,
+            soft_cap_tickets: 0x1818_1818_1818_1818,
+            frozen: true,
+            min_unique_participants: 0x1a1a_1a1a_1a1a_1a1a,
+            claim_window: 0x1b1b_1b1b_1b1b_1b1b,
+            claim_deadline: 0x1c1c_1c1c_1c1c_1c1c,
+            runner_up: Pubkey::new_from_array([29; 32]),
+            prize_claimed: true,
+            purchase_grace_seconds: 0x1f1f_1f1f_1f1f_1f1f,
+            consolation_bps: 0x2020,
+            consolation_pool: 0x2121_2121_2121_2121,
+            consolation_winner: Pubkey::new_from_array([34; 32]),
+            vrf_requested_at: 0x2323_2323_2323_2323,
+            creator_bond: 0x2424_2424_2424_2424,
+            creator_bond_slashed: true,
+            payout_installments: 38,
+            payout_interval: 0x2727_2727_2727_2727,
+            installments_claimed: 40,
+            last_installment_at: 0x2929_2929_2929_2929,
+            bulk_threshold: 0x2a2a_2a2a_2a2a_2a2a,
+            bulk_fee_bps: 0x2b2b,
+            donated_amount: 0x2c2c_2c2c_2c2c_2c2c,
+            charity: Pubkey::new_from_array([45; 32]),
+            charity_bps: 0x2e2e,
+            referral_bps: 0x2f2f,
+            vrf_requested_slot: 0x3030_3030_3030_3030,
+            closing_tickets_sold: 0x3131_3131_3131_3131,
+            closing_participant_count: 0x3232_3232_3232_3232,
+            min_pool_lamports: 0x3333_3333_3333_3333,
+            max_prize_lamports: 0x3434_3434_3434_3434,
+            vrf_requester: Pubkey::new_from_array([53; 32]),
+            request_reward_bps: 0x3636,
+            complete_reward_bps: 0x3737,
+            max_total_fee_lamports: 0x3838_3838_3838_3838,
+            fees_collected: 0x3939_3939_3939_3939,
+            min_buyer_balance: 0x3a3a_3a3a_3a3a_3a3a,
+            oracle_queue: Pubkey::new_from_array([59; 32]),
+            consolation_paid: 0x3c3c_3c3c_3c3c_3c3c,
+        }
+    }
+
+    fn sample_config() -> Config {
+        Config {
+            is_initialized: true,
+            admin: Pubkey::new_from_array([2; 32]),
+            treasury: Pubkey::new_from_array([3; 32]),
+            ticket_price: 0x0404_0404_0404_0404,
+            fee_basis_points: 0x0505,
+            next_raffle_index: 0x0606_0606_0606_0606,
+            min_ticket_price: 0x0707_0707_0707_0707,
+            max_ticket_price: 0x0808_0808_0808_0808,
+            oracle_queue: Pubkey::new_from_array([9; 32]),
+            permissionless_completion: true,
+            allowed_durations: [0x0b0b_0b0b_0b0b_0b0b; MAX_ALLOWED_DURATIONS],
+            bulk_threshold: 0x0c0c_0c0c_0c0c_0c0c,
+            bulk_fee_bps: 0x0d0d,
+            draw_delay: 0x0e0e_0e0e_0e0e_0e0e,
+            keeper_reward_bps: 0x0f0f,
+            referral_bps: 0x1010,
+            max_active_raffles: 0x1111_1111_1111_1111,
+            request_reward_bps: 0x1212,
+        }
+    }
+
+    fn sample_ticket_purchase() -> TicketPurchase {
+        TicketPurchase {
+            is_initialized: true,
+            raffle: Pubkey::new_from_array([2; 32]),
+            purchaser: Pubkey::new_from_array([3; 32]),
+            ticket_count: 0x0404_0404_0404_0404,
+            purchase_time: 0x0505_0505_0505_0505,
+        }
+    }
+
+    /// Check that `layout` tiles `len` bytes exactly and that `data` holds each field's sentinel at its offset
+    fn assert_layout(data: &[u8], len: usize, layout: &[(&str, usize, usize, u8)]) {
+        assert_eq!(data.len(), len);
+        assert_eq!(layout.iter().map(|(_, _, size, _)| size).sum::<usize>(), len);
+        let mut expected_offset = 0;
+        for &(field, offset, size, sentinel) in layout {
+            assert_eq!(offset, expected_offset, "{} offset", field);
+            assert!(
+                data[offset..offset + size].iter().all(|&byte| byte == sentinel),
+                "{} at {}..{} is {:?}, expected all {}",
+                field,
+                offset,
+                offset + size,
+                &data[offset..offset + size],
+                sentinel
+            );
+            expected_offset += size;
+        }
+    }
+
+    #[test]
+    fn raffle_layout_matches_offsets() {
+        let layout = [
+            ("is_initialized", 0, 1, 1),
+            ("authority", 1, 32, 2),
+            ("title", 33, 32, 3),
+            ("end_time", 65, 8, 4),
+            ("ticket_price", 73, 8, 5),
+            ("status", 81, 1, 3),
+            ("winner", 82, 32, 7),
+            ("tickets_sold", 114, 8, 8),
+            ("fee_basis_points", 122, 2, 9),
+            ("treasury", 124, 32, 10),
+            ("vrf_account", 156, 32, 11),
+            ("vrf_request_in_progress", 188, 1, 1),
+            ("nonce", 189, 8, 13),
+            ("raffle_index", 197, 8, 14),
+            ("prize_amount", 205, 8, 15),
+            ("completed_at", 213, 8, 16),
+            ("max_tickets", 221, 8, 17),
+            ("pool_principal", 229, 8, 18),
+            ("max_participants", 237, 8, 19),
+            ("participant_count", 245, 8, 20),
+            ("uses_vault", 253, 1, 1),
+            ("fixed_prize_lamports", 254, 8, 22),
+            ("draw_on_sellout", 262, 1, 1),
+            ("soft_cap_tickets", 263, 8, 24),
+            ("frozen", 271, 1, 1),
+            ("min_unique_participants", 272, 8, 26),
+            ("claim_window", 280, 8, 27),
+            ("claim_deadline", 288, 8, 28),
+            ("runner_up", 296, 32, 29),
+            ("prize_claimed", 328, 1, 1),
+            ("purchase_grace_seconds", 329, 8, 31),
+            ("consolation_bps", 337, 2, 32),
+            ("consolation_pool", 339, 8, 33),
+            ("consolation_winner", 347, 32, 34),
+            ("vrf_requested_at", 379, 8, 35),
+            ("creator_bond", 387, 8, 36),
+            ("creator_bond_slashed", 395, 1, 1),
+            ("payout_installments", 396, 1, 38),
+            ("payout_interval", 397, 8, 39),
+            ("installments_claimed", 405, 1, 40),
+            ("last_installment_at", 406, 8, 41),
+            ("bulk_threshold", 414, 8, 42),
+            ("bulk_fee_bps", 422, 2, 43),
+            ("donated_amount", 424, 8, 44),
+            ("charity", 432, 32, 45),
+            ("charity_bps", 464, 2, 46),
+            ("referral_bps", 466, 2, 47),
+            ("vrf_requested_slot", 468, 8, 48),
+            ("closing_tickets_sold", 476, 8, 49),
+            ("closing_participant_count", 484, 8, 50),
+            ("min_pool_lamports", 492, 8, 51),
+            ("max_prize_lamports", 500, 8, 52),
+            ("vrf_requester", 508, 32, 53),
+            ("request_reward_bps", 540, 2, 54),
+            ("complete_reward_bps", 542, 2, 55),
+            ("max_total_fee_lamports", 544, 8, 56),
+            ("fees_collected", 552, 8, 57),
+            ("min_buyer_balance", 560, 8, 58),
+            ("oracle_queue", 568, 32, 59),
+            ("consolation_paid", 600, 8, 60),
+        ];
+        let mut data = vec![0u8; Raffle::LEN];
+        Raffle::pack(sample_raffle(), &mut data).unwrap();
+        assert_layout(&data, Raffle::LEN, &layout);
+
+        let mut repacked = vec![0u8; Raffle::LEN];
+        Raffle::pack(Raffle::unpack(&data).unwrap(), &mut repacked).unwrap();
+        assert_eq!(repacked, data);
+    }
+
+    #[test]
+    fn config_layout_matches_offsets() {
+        let layout = [
+            ("is_initialized", 0, 1, 1),
+            ("admin", 1, 32, 2),
+            ("treasury", 33, 32, 3),
+            ("ticket_price", 65, 8, 4),
+            ("fee_basis_points", 73, 2, 5),
+            ("next_raffle_index", 75, 8, 6),
+            ("min_ticket_price", 83, 8, 7),
+            ("max_ticket_price", 91, 8, 8),
+            ("oracle_queue", 99, 32, 9),
+            ("permissionless_completion", 131, 1, 1),
+            ("allowed_durations", 132, 32, 11),
+            ("bulk_threshold", 164, 8, 12),
+            ("bulk_fee_bps", 172, 2, 13),
+            ("draw_delay", 174, 8, 14),
+            ("keeper_reward_bps", 182, 2, 15),
+            ("referral_bps", 184, 2, 16),
+            ("max_active_raffles", 186, 8, 17),
+            ("request_reward_bps", 194, 2, 18),
+        ];
+        let mut data = vec![0u8; Config::LEN];
+        Config::pack(sample_config(), &mut data).unwrap();
+        assert_layout(&data, Config::LEN, &layout);
+
+        let mut repacked = vec![0u8; Config::LEN];
+        Config::pack(Config::unpack(&data).unwrap(), &mut repacked).unwrap();
+        assert_eq!(repacked, data);
+    }
+
+    #[test]
+    fn ticket_purchase_layout_matches_offsets() {
+        let layout = [
+            ("is_initialized", 0, 1, 1),
+            ("raffle", 1, 32, 2),
+            ("purchaser", 33, 32, 3),
+            ("ticket_count", 65, 8, 4),
+            ("purchase_time", 73, 8, 5),
+        ];
+        let mut data = vec![0u8; TicketPurchase::LEN];
+        TicketPurchase::pack(sample_ticket_purchase(), &mut data).unwrap();
+        assert_layout(&data, TicketPurchase::LEN, &layout);
+
+        let mut repacked = vec![0u8; TicketPurchase::LEN];
+        TicketPurchase::pack(TicketPurchase::unpack(&data).unwrap(), &mut repacked).unwrap();
+        assert_eq!(repacked, data);
+    }
+
+    /// Borsh encodes every field in declaration order with no padding, so it must equal the Pack layout
+    #[cfg(feature = "borsh-state")]
+    #[test]
+    fn borsh_round_trips_and_matches_pack() {
+        use borsh::{BorshDeserialize, BorshSerialize};
+
+        let raffle = sample_raffle();
+        let encoded = raffle.try_to_vec().unwrap();
+        let mut packed = vec![0u8; Raffle::LEN];
+        Raffle::pack(raffle, &mut packed).unwrap();
+        assert_eq!(encoded.len(), Raffle::LEN);
+        assert_eq!(encoded, packed);
+        assert_eq!(Raffle::try_from_slice(&encoded).unwrap().try_to_vec().unwrap(), encoded);
+
+        let config = sample_config();
+        let encoded = config.try_to_vec().unwrap();
+        let mut packed = vec![0u8; Config::LEN];
+        Config::pack(config, &mut packed).unwrap();
+        assert_eq!(encoded.len(), Config::LEN);
+        assert_eq!(encoded, packed);
+        assert_eq!(Config::try_from_slice(&encoded).unwrap().try_to_vec().unwrap(), encoded);
+
+        let ticket_purchase = sample_ticket_purchase();
+        let encoded = ticket_purchase.try_to_vec().unwrap();
+        let mut packed = vec![0u8; TicketPurchase::LEN];
+        TicketPurchase::pack(ticket_purchase, &mut packed).unwrap();
+        assert_eq!(encoded.len(), TicketPurchase::LEN);
+        assert_eq!(encoded, packed);
+        assert_eq!(TicketPurchase::try_from_slice(&encoded).unwrap().try_to_vec().unwrap(), encoded);
+    }
+}
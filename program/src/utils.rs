@@ -1,11 +1,67 @@
 // Pot of Green Raffle Program - Utility Functions
-use solana_program::pubkey::Pubkey;
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
 
 // Removed pseudo-random value generation in favor of VRF
 
 /// Calculate fee amount based on input amount and basis points
-pub fn calculate_fee(amount: u64, basis_points: u16) -> u64 {
-    (amount * basis_points as u64) / 10000
+/// (rounded according to `raffle_core::FEE_ROUNDING`)
+pub fn calculate_fee(amount: u64, basis_points: u16) -> Result<u64, ProgramError> {
+    crate::raffle_core::calculate_fee(amount, basis_points).map_err(|err| {
+        msg!("{}", err);
+        ProgramError::InvalidArgument
+    })
+}
+
+/// Split an amount into (pool, fee) with no lamports created or destroyed
+pub fn split_fee(amount: u64, basis_points: u16) -> Result<(u64, u64), ProgramError> {
+    crate::raffle_core::split_fee(amount, basis_points).map_err(|err| {
+        msg!("{}", err);
+        ProgramError::InvalidArgument
+    })
+}
+
+/// Check that the combined fee components of a raffle leave a nonzero prize pool
+///
+/// Every basis-point cut taken from a purchase must be summed here as it is added.
+pub fn validate_fee_configuration(
+    config: &crate::raffle_state::Config,
+    raffle: &crate::raffle_state::Raffle,
+) -> Result<(), ProgramError> {
+    if raffle.fee_basis_points > config.fee_basis_points {
+        msg!("Raffle fee {} bps exceeds config fee {} bps", raffle.fee_basis_points, config.fee_basis_points);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let total_fee_bps = raffle.fee_basis_points as u64;
+    if total_fee_bps >= crate::raffle_core::BASIS_POINTS_DENOMINATOR {
+        msg!("Combined fees of {} bps leave no prize pool", total_fee_bps);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The charity share is taken from the pool at completion rather than from each purchase
+    if raffle.charity_bps as u64 > crate::raffle_core::BASIS_POINTS_DENOMINATOR {
+        msg!("Charity share of {} bps exceeds the whole prize", raffle.charity_bps);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if raffle.charity_bps > 0 && raffle.charity == Pubkey::default() {
+        msg!("A charity share requires a charity address");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+/// Position of the ticket purchase record whose cumulative ticket range contains `winner_index`
+///
+/// Records are taken in the order given, which on-chain is ascending pubkey order.
+pub fn resolve_winner(records: &[crate::raffle_state::TicketPurchase], winner_index: u64) -> Option<usize> {
+    let ticket_counts: Vec<u64> = records.iter().map(|record| record.ticket_count).collect();
+    crate::raffle_core::record_for_ticket(&ticket_counts, winner_index)
+}
+
+/// Length of a null-padded title (number of bytes before the first null)
+pub fn title_len(title: &[u8; 32]) -> usize {
+    title.iter().position(|&b| b == 0).unwrap_or(title.len())
 }
 
 /// Calculate number of entries based on SOL amount
@@ -20,6 +76,44 @@ pub fn find_raffle_address(program_id: &Pubkey, raffle_id: u64) -> (Pubkey, u8)
     Pubkey::find_program_address(&[b"raffle", &raffle_id_bytes], program_id)
 }
 
+/// Find the program derived address of the global config account
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], program_id)
+}
+
+/// Find the program derived address of a raffle created by `authority` with `nonce`
+/// (matches the seeds used by InitializeRaffle)
+pub fn find_raffle_pda(program_id: &Pubkey, authority: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    let nonce_bytes = nonce.to_le_bytes();
+    Pubkey::find_program_address(&[b"raffle", authority.as_ref(), &nonce_bytes], program_id)
+}
+
+/// Find the program derived address of the vault holding a raffle's prize pool
+pub fn find_vault_address(program_id: &Pubkey, raffle: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", raffle.as_ref()], program_id)
+}
+
+/// Find the program derived address of the pointer resolving a raffle_index to its raffle
+pub fn find_index_pointer_pda(program_id: &Pubkey, raffle_index: u64) -> (Pubkey, u8) {
+    let raffle_index_bytes = raffle_index.to_le_bytes();
+    Pubkey::find_program_address(&[b"index", &raffle_index_bytes], program_id)
+}
+
+/// Find the program derived address of the wallet blacklist
+pub fn find_blacklist_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"blacklist"], program_id)
+}
+
+/// Find the program derived address tracking a referrer's earnings
+pub fn find_referral_address(program_id: &Pubkey, referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"referral", referrer.as_ref()], program_id)
+}
+
+/// Find the program derived address counting an authority's open raffles
+pub fn find_authority_state_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"authority", authority.as_ref()], program_id)
+}
+
 /// Find a program derived address for a raffle entry
 pub fn find_entry_address(program_id: &Pubkey, raffle_id: u64, user: &Pubkey) -> (Pubkey, u8) {
     let raffle_id_bytes = raffle_id.to_le_bytes();
@@ -31,7 +125,100 @@ pub fn lamports_to_sol(lamports: u64) -> f64 {
     lamports as f64 / 1_000_000_000.0
 }
 
+/// Split basis points into whole percent and hundredths for display (1050 -> (10, 50))
+pub fn format_bps(bps: u16) -> (u16, u16) {
+    (bps / 100, bps % 100)
+}
+
 /// Convert SOL to lamports
 pub fn sol_to_lamports(sol: f64) -> u64 {
     (sol * 1_000_000_000.0) as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raffle_state::{Config, Raffle, TicketPurchase};
+    use solana_program::program_pack::Pack;
+
+    fn records(ticket_counts: &[u64]) -> Vec<TicketPurchase> {
+        ticket_counts
+            .iter()
+            .map(|&ticket_count| TicketPurchase {
+                is_initialized: true,
+                raffle: Pubkey::new_unique(),
+                purchaser: Pubkey::new_unique(),
+                ticket_count,
+                purchase_time: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolve_winner_covers_every_range_boundary() {
+        for ticket_counts in [&[1u64][..], &[1, 1, 1], &[3, 1, 4], &[10, 0, 2], &[1, 99], &[u64::MAX / 2, 7]] {
+            let records = records(ticket_counts);
+            let mut range_start = 0u64;
+            for (position, &ticket_count) in ticket_counts.iter().enumerate() {
+                if ticket_count == 0 {
+                    continue;
+                }
+                let range_end = range_start + ticket_count - 1;
+                assert_eq!(resolve_winner(&records, range_start), Some(position), "{:?} first", ticket_counts);
+                assert_eq!(resolve_winner(&records, range_end), Some(position), "{:?} last", ticket_counts);
+                range_start += ticket_count;
+            }
+            // tickets_sold itself is one past the last ticket
+            assert_eq!(resolve_winner(&records, range_start), None, "{:?} total", ticket_counts);
+        }
+    }
+
+    #[test]
+    fn resolve_winner_without_records_is_none() {
+        assert_eq!(resolve_winner(&[], 0), None);
+        assert_eq!(resolve_winner(&records(&[0, 0]), 0), None);
+    }
+
+    /// A blank raffle charging `fee_basis_points`, under a config that allows the same fee
+    fn fee_setup(fee_basis_points: u16) -> (Config, Raffle) {
+        let config = Config { fee_basis_points, ..Config::default() };
+        let mut raffle = Raffle::unpack_unchecked(&[0u8; Raffle::LEN]).unwrap();
+        raffle.fee_basis_points = fee_basis_points;
+        (config, raffle)
+    }
+
+    #[test]
+    fn validate_fee_configuration_accepts_fees_that_leave_a_pool() {
+        let (config, mut raffle) = fee_setup(1_000);
+        raffle.charity = Pubkey::new_unique();
+        raffle.charity_bps = 500;
+        assert_eq!(validate_fee_configuration(&config, &raffle), Ok(()));
+
+        let (config, raffle) = fee_setup(9_999);
+        assert_eq!(validate_fee_configuration(&config, &raffle), Ok(()));
+    }
+
+    #[test]
+    fn validate_fee_configuration_rejects_fees_of_100_percent_or_more() {
+        for fee_basis_points in [10_000, 10_500, u16::MAX] {
+            let (config, raffle) = fee_setup(fee_basis_points);
+            assert_eq!(
+                validate_fee_configuration(&config, &raffle),
+                Err(ProgramError::InvalidArgument),
+                "{} bps",
+                fee_basis_points
+            );
+        }
+    }
+
+    #[test]
+    fn format_bps_splits_percent_and_hundredths() {
+        assert_eq!(format_bps(0), (0, 0));
+        assert_eq!(format_bps(1), (0, 1));
+        assert_eq!(format_bps(99), (0, 99));
+        assert_eq!(format_bps(1050), (10, 50));
+        assert_eq!(format_bps(1005), (10, 5));
+        assert_eq!(format_bps(10000), (100, 0));
+        assert_eq!(format_bps(u16::MAX), (655, 35));
+    }
+}
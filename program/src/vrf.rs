@@ -4,6 +4,7 @@
 //! For production deployment, this should be replaced with full Switchboard VRF integration.
 //! See https://docs.switchboard.xyz/randomness for more information.
 
+use crate::raffle_error::RaffleError;
 use solana_program::{
     account_info::AccountInfo,
     entrypoint::ProgramResult,
@@ -11,6 +12,7 @@ use solana_program::{
     program_error::ProgramError,
     pubkey::Pubkey,
     system_program,
+    clock::Slot,
     sysvar::{clock::Clock, Sysvar},
 };
 
@@ -66,6 +68,77 @@ pub fn verify_vrf_result<'a>(
     Ok(result)
 }
 
+/// Checks that completion happens in a slot after the randomness request.
+///
+/// This only orders completion after the request; it says nothing about when the VRF result
+/// itself was produced. The simplified VRF account records no fulfillment slot, so result
+/// freshness is not checked here.
+///
+/// # Production Implementation Notes
+/// In a production environment the fulfillment slot should be read from the Switchboard VRF
+/// account's current round and compared against `requested_slot` instead.
+pub fn require_completion_after_request(requested_slot: Slot, clock: &Clock) -> ProgramResult {
+    if clock.slot <= requested_slot {
+        msg!(
+            "Completion at slot {} must come after the randomness request at slot {}",
+            clock.slot,
+            requested_slot
+        );
+        return Err(RaffleError::CompletedInRequestSlot.into());
+    }
+    Ok(())
+}
+
+/// Switchboard accounts the randomness request CPI needs after the fixed instruction accounts, in order
+pub const VRF_REQUEST_REMAINING_ACCOUNTS: [&str; 8] = [
+    "queue authority",
+    "data buffer",
+    "permission",
+    "escrow",
+    "payer token wallet",
+    "recent blockhashes sysvar",
+    "program state",
+    "token program",
+];
+
+/// Positions in `VRF_REQUEST_REMAINING_ACCOUNTS` whose address is fixed, with that address
+#[allow(deprecated)]
+fn fixed_vrf_account_keys() -> [(usize, Pubkey); 2] {
+    [
+        (5, solana_program::sysvar::recent_blockhashes::id()),
+        (7, spl_token::id()),
+    ]
+}
+
+/// Checks that every Switchboard account needed for the randomness request was passed.
+///
+/// Accounts are positional, so a short list is reported by naming each missing role, and a
+/// role with a fixed address (the recent blockhashes sysvar, the token program) is reported
+/// by name when the account at its position has any other key.
+pub fn check_remaining_vrf_accounts(remaining_accounts: &[&AccountInfo]) -> ProgramResult {
+    if remaining_accounts.len() < VRF_REQUEST_REMAINING_ACCOUNTS.len() {
+        msg!(
+            "Missing {} Switchboard account(s) for the randomness request: {}",
+            VRF_REQUEST_REMAINING_ACCOUNTS.len() - remaining_accounts.len(),
+            VRF_REQUEST_REMAINING_ACCOUNTS[remaining_accounts.len()..].join(", ")
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    for (position, expected_key) in fixed_vrf_account_keys() {
+        if *remaining_accounts[position].key != expected_key {
+            msg!(
+                "Expected {} {} at Switchboard account {}, got {}",
+                VRF_REQUEST_REMAINING_ACCOUNTS[position],
+                expected_key,
+                position,
+                remaining_accounts[position].key
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    Ok(())
+}
+
 /// Requests randomness from the Switchboard VRF.
 /// This is the first step of a two-step process to get verifiable randomness.
 /// After requesting, you must wait for the VRF to be fulfilled off-chain.
@@ -79,7 +152,7 @@ pub fn verify_vrf_result<'a>(
 /// * `permission_account_info` - Permission account (if required)
 /// * `escrow_account_info` - Escrow account for payment (if required)
 /// * `payer_wallet_info` - Payer's token wallet (if required)
-/// * `remaining_accounts` - Additional accounts required by Switchboard
+/// * `remaining_accounts` - Additional accounts required by Switchboard, see `VRF_REQUEST_REMAINING_ACCOUNTS`
 ///
 /// # Returns
 /// * `ProgramResult` - Success or error
@@ -90,7 +163,7 @@ pub fn verify_vrf_result<'a>(
 /// 2. Make a CPI call to the Switchboard program to request randomness
 /// 3. Update the raffle account to mark the VRF request as in progress
 /// 4. Store the VRF account in the raffle for later verification
-/// A simplified version that doesn't care about the remaining accounts
+/// The simplified version checks the remaining accounts are all present but makes no CPI
 pub fn request_vrf_randomness<'a>(
     vrf_account_info: &AccountInfo<'a>,
     payer_account_info: &AccountInfo<'a>, 
@@ -100,7 +173,7 @@ pub fn request_vrf_randomness<'a>(
     permission_account_info: Option<&AccountInfo<'a>>,
     escrow_account_info: Option<&AccountInfo<'a>>,
     payer_wallet_info: Option<&AccountInfo<'a>>,
-    _remaining_accounts: &[&AccountInfo<'a>],
+    remaining_accounts: &[&AccountInfo<'a>],
 ) -> ProgramResult {
     // Validate signers
     if !payer_account_info.is_signer {
@@ -118,6 +191,9 @@ pub fn request_vrf_randomness<'a>(
         msg!("Invalid Switchboard program ID provided");
         return Err(ProgramError::InvalidArgument);
     }
+
+    // Fail before the CPI with a clear list rather than deep inside Switchboard
+    check_remaining_vrf_accounts(remaining_accounts)?;
     
     // In production, we would use a CPI call to the Switchboard program here
     // to request randomness using the VRF account
@@ -141,32 +217,16 @@ pub fn request_vrf_randomness<'a>(
 /// * `total_tickets` - Total number of tickets sold in the raffle
 /// 
 /// # Returns
-/// * A random ticket index between 0 and (total_tickets - 1)
+/// * A random ticket index between 0 and (total_tickets - 1), or `NoTicketsSold`
 /// 
 /// # Security Considerations
 /// This function implements a uniform distribution over the ticket range.
 /// It's important to use the full 8 bytes of entropy to ensure an unbiased selection.
-pub fn get_random_winner_index(vrf_result: [u8; 32], total_tickets: u64) -> u64 {
-    // Handle edge case of no tickets sold
-    if total_tickets == 0 {
-        return 0;
-    }
-
-    // Convert first 8 bytes of VRF result to u64
-    // This provides full 64 bits of entropy for the random selection
-    let random_bytes = &vrf_result[0..8];
-    let mut random_value = 0u64;
-    for (i, byte) in random_bytes.iter().enumerate() {
-        random_value |= (*byte as u64) << (8 * i);
-    }
-
-    // To ensure an unbiased selection when total_tickets is not a power of 2,
-    // we reject samples that would introduce bias and try again with a different
-    // portion of the VRF result.
-    // 
-    // For testing, we'll use a simple modulo approach, but production would
-    // implement a more sophisticated rejection sampling algorithm.
-    
-    // Get random index based on ticket count
-    random_value % total_tickets
+/// The selection itself lives in `raffle_core::winner_index` so off-chain verifiers
+/// can reproduce it exactly.
+pub fn get_random_winner_index(vrf_result: [u8; 32], total_tickets: u64) -> Result<u64, ProgramError> {
+    crate::raffle_core::winner_index(&vrf_result, total_tickets).map_err(|err| {
+        msg!("{}", err);
+        RaffleError::NoTicketsSold.into()
+    })
 }
@@ -0,0 +1,74 @@
+//! Accounts passed in the wrong position are rejected with a log naming the expected account
+//! and its index
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::raffle_instruction;
+
+#[tokio::test]
+async fn a_purchase_with_treasury_and_vault_swapped_names_the_vault() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 1).await.unwrap();
+
+    let mut instruction = raffle_instruction::purchase_tickets(
+        &env.program_id,
+        &buyer.pubkey(),
+        &raffle.raffle,
+        &record.pubkey(),
+        &raffle_data.treasury,
+        2,
+        raffle_data.ticket_price,
+        None,
+    )
+    .unwrap();
+    assert_eq!(instruction.accounts[3].pubkey, raffle_data.treasury);
+    assert_eq!(instruction.accounts[6].pubkey, raffle.vault);
+    instruction.accounts.swap(3, 6);
+
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&buyer, &record]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    let expected = format!("Expected vault {} at account index 6, got {}", raffle.vault, raffle_data.treasury);
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.tickets_sold, 1);
+}
+
+#[tokio::test]
+async fn a_randomness_request_with_oracle_queue_and_config_swapped_names_the_config() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+
+    let vrf = Pubkey::new_unique();
+    let mut instruction = raffle_instruction::request_randomness(
+        &env.program_id,
+        &keeper.pubkey(),
+        &raffle.raffle,
+        &vrf,
+        &keeper.pubkey(),
+        &env.switchboard,
+        &env.oracle_queue,
+        &env.config,
+        &vrf_remaining_accounts(),
+    )
+    .unwrap();
+    assert_eq!(instruction.accounts[5].pubkey, env.oracle_queue);
+    assert_eq!(instruction.accounts[6].pubkey, env.config);
+    instruction.accounts.swap(5, 6);
+
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&keeper]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    let expected = format!("Expected config {} at account index 6, got {}", env.config, env.oracle_queue);
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+    assert!(!get_raffle(&mut env.context, &raffle.raffle).await.vrf_request_in_progress);
+}
@@ -0,0 +1,136 @@
+//! Authorization of the admin config updates
+
+mod common;
+
+use common::*;
+use solana_program::{instruction::Instruction, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_instruction, raffle_state::Config};
+
+/// The same instruction with the admin account no longer marked as a signer
+fn unsigned(mut instruction: Instruction) -> Instruction {
+    instruction.accounts[0].is_signer = false;
+    instruction
+}
+
+async fn config_bytes(env: &mut TestEnv) -> Vec<u8> {
+    get_account(&mut env.context, &env.config).await.unwrap().data
+}
+
+/// A stranger's instruction and the admin's unsigned one both fail and leave the config untouched
+async fn assert_rejected(env: &mut TestEnv, build: impl Fn(&Pubkey) -> Instruction) {
+    let before = config_bytes(env).await;
+
+    let stranger = new_wallet(&mut env.context);
+    let result = process(&mut env.context, &[build(&stranger.pubkey())], &[&stranger]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+
+    let result = process(&mut env.context, &[unsigned(build(&env.admin.pubkey()))], &[]).await;
+    assert_eq!(program_error(result), ProgramError::MissingRequiredSignature);
+
+    assert_eq!(config_bytes(env).await, before);
+}
+
+#[tokio::test]
+async fn update_admin_is_admin_only() {
+    let mut env = start().await;
+    let new_admin = Keypair::new();
+    let (program_id, config) = (env.program_id, env.config);
+    let build = |admin: &Pubkey| raffle_instruction::update_admin(&program_id, admin, &new_admin.pubkey(), &config).unwrap();
+    assert_rejected(&mut env, build).await;
+
+    process(&mut env.context, &[build(&env.admin.pubkey())], &[&env.admin]).await.unwrap();
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.admin, new_admin.pubkey());
+
+    // The previous admin has handed over its rights
+    let instruction =
+        raffle_instruction::update_admin(&program_id, &env.admin.pubkey(), &env.admin.pubkey(), &config).unwrap();
+    let result = process(&mut env.context, &[instruction], &[&env.admin]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn update_admin_ignores_a_look_alike_config() {
+    let mut env = start().await;
+    let before = config_bytes(&mut env).await;
+
+    // A program-owned copy of the config naming the attacker as admin, at an address they chose
+    let attacker = new_wallet(&mut env.context);
+    let mut forged = get_config(&mut env.context, &env.program_id).await;
+    forged.admin = attacker.pubkey();
+    let forged_config = Pubkey::new_unique();
+    let mut account = get_account(&mut env.context, &env.config).await.unwrap();
+    Config::pack(forged, &mut account.data).unwrap();
+    env.context.set_account(&forged_config, &account.into());
+
+    let instruction =
+        raffle_instruction::update_admin(&env.program_id, &attacker.pubkey(), &attacker.pubkey(), &forged_config)
+            .unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&attacker]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    let expected = format!("Expected config {} at account index 2, got {}", env.config, forged_config);
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+
+    assert_eq!(config_bytes(&mut env).await, before);
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.admin, env.admin.pubkey());
+}
+
+#[tokio::test]
+async fn update_fee_address_is_admin_only() {
+    let mut env = start().await;
+    let new_treasury = Pubkey::new_unique();
+    let (program_id, config) = (env.program_id, env.config);
+    let build = |admin: &Pubkey| raffle_instruction::update_fee_address(&program_id, admin, &new_treasury, &config).unwrap();
+    assert_rejected(&mut env, build).await;
+
+    process(&mut env.context, &[build(&env.admin.pubkey())], &[&env.admin]).await.unwrap();
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.treasury, new_treasury);
+}
+
+#[tokio::test]
+async fn update_ticket_price_is_admin_only() {
+    let mut env = start().await;
+    let new_price = get_config(&mut env.context, &env.program_id).await.ticket_price * 2;
+    let (program_id, config) = (env.program_id, env.config);
+    let build = |admin: &Pubkey| raffle_instruction::update_ticket_price(&program_id, admin, &config, new_price).unwrap();
+    assert_rejected(&mut env, build).await;
+
+    process(&mut env.context, &[build(&env.admin.pubkey())], &[&env.admin]).await.unwrap();
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.ticket_price, new_price);
+}
+
+#[tokio::test]
+async fn update_fee_percentage_is_admin_only() {
+    let mut env = start().await;
+    let (program_id, config) = (env.program_id, env.config);
+    let build = |admin: &Pubkey| raffle_instruction::update_fee_percentage(&program_id, admin, &config, 2_500).unwrap();
+    assert_rejected(&mut env, build).await;
+
+    process(&mut env.context, &[build(&env.admin.pubkey())], &[&env.admin]).await.unwrap();
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.fee_basis_points, 2_500);
+}
+
+#[tokio::test]
+async fn update_fee_percentage_stays_below_100_percent_and_at_or_above_the_bulk_fee() {
+    let mut env = start().await;
+    let (program_id, config, admin) = (env.program_id, env.config, env.admin.pubkey());
+    let update = |fee_bps: u16| raffle_instruction::update_fee_percentage(&program_id, &admin, &config, fee_bps).unwrap();
+
+    // A 100% fee would leave nothing for the prize pool
+    let before = config_bytes(&mut env).await;
+    let result = process(&mut env.context, &[update(10_000)], &[&env.admin]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    assert_eq!(config_bytes(&mut env).await, before);
+    process(&mut env.context, &[update(9_999)], &[&env.admin]).await.unwrap();
+    assert_eq!(get_config(&mut env.context, &program_id).await.fee_basis_points, 9_999);
+
+    // The standard fee can't drop below the bulk rate
+    let bulk = raffle_instruction::set_bulk_fee(&program_id, &admin, &config, 10, 500).unwrap();
+    process(&mut env.context, &[bulk], &[&env.admin]).await.unwrap();
+    let before = config_bytes(&mut env).await;
+    let result = process(&mut env.context, &[update(499)], &[&env.admin]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    assert_eq!(config_bytes(&mut env).await, before);
+    process(&mut env.context, &[update(500)], &[&env.admin]).await.unwrap();
+    assert_eq!(get_config(&mut env.context, &program_id).await.fee_basis_points, 500);
+}
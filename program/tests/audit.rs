@@ -0,0 +1,97 @@
+//! AuditRaffle reports disagreements between a raffle's recorded state and what its records,
+//! vault and VRF result imply
+
+mod common;
+
+use common::*;
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{
+    raffle_instruction,
+    raffle_state::{Raffle, RaffleStatus, TicketPurchase},
+};
+
+/// A completed raffle drawn over three buyers, with its VRF account and ticket records
+async fn completed_raffle(env: &mut TestEnv) -> (TestRaffle, Pubkey, Vec<Pubkey>) {
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(env, &authority, raffle_params()).await.unwrap();
+    let buyers: Vec<(Keypair, Keypair)> = (0..3).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+    for ((wallet, record), ticket_count) in buyers.iter().zip([3, 2, 4]) {
+        buy_tickets(env, &raffle.raffle, wallet, record, ticket_count).await.unwrap();
+    }
+    let records: Vec<Pubkey> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+    let (vrf, _) = draw_raffle(env, &raffle.raffle, &records).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.status, RaffleStatus::Complete);
+    (raffle, vrf, records)
+}
+
+async fn audit_logs(env: &mut TestEnv, raffle: &Pubkey, vrf: &Pubkey, records: &[Pubkey]) -> Vec<String> {
+    let instruction = raffle_instruction::audit_raffle(&env.program_id, raffle, vrf, &env.switchboard, records).unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[]).await;
+    result.unwrap();
+    logs
+}
+
+async fn rewrite_raffle(env: &mut TestEnv, raffle: &Pubkey, edit: impl FnOnce(&mut Raffle)) {
+    let mut account = get_account(&mut env.context, raffle).await.unwrap();
+    let mut raffle_data = Raffle::unpack(&account.data).unwrap();
+    edit(&mut raffle_data);
+    Raffle::pack(raffle_data, &mut account.data).unwrap();
+    env.context.set_account(raffle, &account.into());
+}
+
+#[tokio::test]
+async fn a_healthy_raffle_has_no_discrepancies() {
+    let mut env = start().await;
+    let (raffle, vrf, records) = completed_raffle(&mut env).await;
+    let raffle_index = raffle.raffle_index;
+
+    let logs = audit_logs(&mut env, &raffle.raffle, &vrf, &records).await;
+    assert!(!logged(&logs, "AuditDiscrepancy"), "{:#?}", logs);
+    let report = format!("AuditReport: raffle_index={}, status=Complete, checks=", raffle_index);
+    assert!(logs.iter().any(|line| line.contains(&report) && line.ends_with("discrepancies=0")), "{:#?}", logs);
+}
+
+#[tokio::test]
+async fn a_recorded_winner_that_the_draw_does_not_produce_is_reported() {
+    let mut env = start().await;
+    let (raffle, vrf, records) = completed_raffle(&mut env).await;
+    let recorded = get_raffle(&mut env.context, &raffle.raffle).await;
+    let impostor = Pubkey::new_unique();
+    rewrite_raffle(&mut env, &raffle.raffle, |raffle_data| raffle_data.winner = impostor).await;
+
+    let logs = audit_logs(&mut env, &raffle.raffle, &vrf, &records).await;
+    let discrepancy = format!(
+        "AuditDiscrepancy: raffle_index={}, check=winner, expected={}, actual={}",
+        raffle.raffle_index, recorded.winner, impostor
+    );
+    assert!(logged(&logs, &discrepancy), "{:#?}", logs);
+    assert!(logs.iter().any(|line| line.ends_with("discrepancies=1")), "{:#?}", logs);
+}
+
+#[tokio::test]
+async fn a_draw_that_cannot_be_recomputed_is_reported_instead_of_aborting() {
+    let mut env = start().await;
+    let (raffle, vrf, records) = completed_raffle(&mut env).await;
+
+    // Zero every ticket count on the raffle and its records: the totals still agree, but
+    // there is no ticket left to draw a winner from
+    rewrite_raffle(&mut env, &raffle.raffle, |raffle_data| {
+        raffle_data.tickets_sold = 0;
+        raffle_data.closing_tickets_sold = 0;
+    })
+    .await;
+    for record in &records {
+        let mut account = get_account(&mut env.context, record).await.unwrap();
+        let mut purchase = TicketPurchase::unpack(&account.data).unwrap();
+        purchase.ticket_count = 0;
+        TicketPurchase::pack(purchase, &mut account.data).unwrap();
+        env.context.set_account(record, &account.into());
+    }
+
+    let logs = audit_logs(&mut env, &raffle.raffle, &vrf, &records).await;
+    let discrepancy = format!("AuditDiscrepancy: raffle_index={}, check=vrf_result", raffle.raffle_index);
+    assert!(logged(&logs, &discrepancy), "{:#?}", logs);
+    assert!(!logged(&logs, "check=winner"), "{:#?}", logs);
+    assert!(logs.iter().any(|line| line.ends_with("discrepancies=1")), "{:#?}", logs);
+}
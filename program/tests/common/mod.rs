@@ -0,0 +1,498 @@
+//! Shared setup for the program-test integration tests
+//!
+//! Each test binary only uses part of this module.
+#![allow(dead_code)]
+
+use solana_program::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction, system_program,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+pub use solcino::raffle_instruction::RaffleParams;
+use solcino::{
+    raffle_core,
+    raffle_instruction,
+    raffle_state::{Config, Raffle, TicketPurchase},
+    utils,
+};
+
+/// Lamports every test wallet starts with
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+/// Program under test plus the accounts `init_config` set up
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub config: Pubkey,
+    pub admin: Keypair,
+    pub treasury: Pubkey,
+    pub oracle_queue: Pubkey,
+    pub switchboard: Pubkey,
+}
+
+/// Keys of a raffle created by `create_raffle`
+pub struct TestRaffle {
+    pub raffle: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub raffle_index: u64,
+}
+
+/// InitializeRaffle parameters for a plain one-hour raffle at the config fee
+pub fn raffle_params() -> RaffleParams {
+    let mut title = [0u8; 32];
+    title[..12].copy_from_slice(b"Test raffle!");
+    RaffleParams {
+        title,
+        duration: 3_600,
+        nonce: 0,
+        max_tickets: 0,
+        fee_override_bps: Config::default().fee_basis_points,
+        max_participants: 0,
+        fixed_prize_lamports: 0,
+        draw_on_sellout: false,
+        soft_cap_tickets: 0,
+        min_unique_participants: 0,
+        claim_window: 0,
+        purchase_grace_seconds: 0,
+        consolation_bps: 0,
+        creator_bond: 0,
+        payout_installments: 0,
+        payout_interval: 0,
+        charity: Pubkey::default(),
+        charity_bps: 0,
+        min_pool_lamports: 0,
+        max_prize_lamports: 0,
+        max_total_fee_lamports: 0,
+        min_buyer_balance: 0,
+    }
+}
+
+/// Start the program and initialize its config
+pub async fn start() -> TestEnv {
+    start_with(|_| {}).await
+}
+
+/// `start`, letting `setup` add other programs, such as a CPI caller, before the test starts
+pub async fn start_with(setup: impl FnOnce(&mut ProgramTest)) -> TestEnv {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("solcino", program_id, processor!(solcino::process_instruction));
+    setup(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let (config, admin, treasury, oracle_queue) = init_config(&mut context, &program_id).await;
+    TestEnv {
+        context,
+        program_id,
+        config,
+        admin,
+        treasury,
+        oracle_queue,
+        switchboard: Pubkey::new_unique(),
+    }
+}
+
+/// Run InitializeConfig, then point the config at a test admin, treasury and oracle queue
+///
+/// InitializeConfig always writes the hardcoded production admin and treasury, so the config
+/// account is rewritten afterwards to keys the tests control. Returns the config address, the
+/// admin keypair, the treasury and the oracle queue.
+pub async fn init_config(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+) -> (Pubkey, Keypair, Pubkey, Pubkey) {
+    let (config, _) = utils::find_config_address(program_id);
+    let payer = context.payer.pubkey();
+    let defaults = Config::default();
+    let instruction = raffle_instruction::initialize_config(
+        program_id,
+        &payer,
+        &config,
+        &defaults.treasury,
+        defaults.ticket_price,
+        defaults.fee_basis_points,
+    )
+    .unwrap();
+    process(context, &[instruction], &[]).await.unwrap();
+
+    let admin = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let oracle_queue = Pubkey::new_unique();
+    fund(context, &admin.pubkey(), WALLET_LAMPORTS);
+    fund(context, &treasury, WALLET_LAMPORTS);
+
+    let mut config_data = get_config(context, program_id).await;
+    config_data.admin = admin.pubkey();
+    config_data.treasury = treasury;
+    config_data.oracle_queue = oracle_queue;
+    set_config(context, program_id, config_data).await;
+
+    (config, admin, treasury, oracle_queue)
+}
+
+/// Create a raffle owned by `authority` and return its keys
+pub async fn create_raffle(
+    env: &mut TestEnv,
+    authority: &Keypair,
+    params: RaffleParams,
+) -> Result<TestRaffle, BanksClientError> {
+    let (raffle, _) = utils::find_raffle_pda(&env.program_id, &authority.pubkey(), params.nonce);
+    let raffle_index = get_config(&mut env.context, &env.program_id).await.next_raffle_index;
+    let instruction = create_raffle_instruction(env, &authority.pubkey(), raffle_index, params);
+    process(&mut env.context, &[instruction], &[authority]).await?;
+
+    let (vault, _) = utils::find_vault_address(&env.program_id, &raffle);
+    Ok(TestRaffle { raffle, vault, authority: authority.pubkey(), raffle_index })
+}
+
+/// InitializeRaffle for `authority`'s raffle at `params.nonce`, taking the given raffle index
+pub fn create_raffle_instruction(
+    env: &TestEnv,
+    authority: &Pubkey,
+    raffle_index: u64,
+    params: RaffleParams,
+) -> Instruction {
+    let (raffle, _) = utils::find_raffle_pda(&env.program_id, authority, params.nonce);
+    raffle_instruction::initialize_raffle(&env.program_id, authority, &raffle, &env.config, raffle_index, params)
+        .unwrap()
+}
+
+/// Buy tickets into `record`, creating the record first if it doesn't exist yet
+///
+/// New records are created owned by the program and empty, in the same transaction, which is
+/// how a client opens one; the record keypair signs every purchase.
+pub async fn buy_tickets(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    buyer: &Keypair,
+    record: &Keypair,
+    ticket_count: u64,
+) -> Result<(), BanksClientError> {
+    buy_referred_tickets(env, raffle, buyer, record, ticket_count, None).await
+}
+
+/// `buy_tickets` naming a referrer
+pub async fn buy_referred_tickets(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    buyer: &Keypair,
+    record: &Keypair,
+    ticket_count: u64,
+    referrer: Option<&Pubkey>,
+) -> Result<(), BanksClientError> {
+    let raffle_data = get_raffle(&mut env.context, raffle).await;
+    let mut instructions = Vec::new();
+    if get_account(&mut env.context, &record.pubkey()).await.is_none() {
+        let rent = env.context.banks_client.get_rent().await.unwrap();
+        instructions.push(system_instruction::create_account(
+            &buyer.pubkey(),
+            &record.pubkey(),
+            rent.minimum_balance(TicketPurchase::LEN),
+            TicketPurchase::LEN as u64,
+            &env.program_id,
+        ));
+    }
+    instructions.push(
+        raffle_instruction::purchase_tickets(
+            &env.program_id,
+            &buyer.pubkey(),
+            raffle,
+            &record.pubkey(),
+            &raffle_data.treasury,
+            ticket_count,
+            raffle_data.ticket_price,
+            referrer,
+        )
+        .unwrap(),
+    );
+    process(&mut env.context, &instructions, &[buyer, record]).await
+}
+
+/// A funded wallet
+pub fn new_wallet(context: &mut ProgramTestContext) -> Keypair {
+    let wallet = Keypair::new();
+    fund(context, &wallet.pubkey(), WALLET_LAMPORTS);
+    wallet
+}
+
+/// Give a system account exactly `lamports`
+pub fn fund(context: &mut ProgramTestContext, key: &Pubkey, lamports: u64) {
+    context.set_account(key, &AccountSharedData::new(lamports, 0, &system_program::id()));
+}
+
+/// A transaction of `instructions` paid for by the context payer and signed by `signers`
+async fn signed_transaction(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Transaction {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&context.payer.pubkey()));
+    transaction.partial_sign(&[&context.payer], blockhash);
+    for signer in signers {
+        transaction.partial_sign(&[*signer], blockhash);
+    }
+    transaction
+}
+
+/// Send `instructions` in one transaction paid for by the context payer
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let transaction = signed_transaction(context, instructions, signers).await;
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// `process`, also returning the transaction's log messages
+pub async fn process_with_logs(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> (Result<(), BanksClientError>, Vec<String>) {
+    let transaction = signed_transaction(context, instructions, signers).await;
+    let outcome = context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    let logs = outcome.metadata.map(|metadata| metadata.log_messages).unwrap_or_default();
+    (outcome.result.map_err(BanksClientError::TransactionError), logs)
+}
+
+/// Whether any log line contains `needle`
+pub fn logged(logs: &[String], needle: &str) -> bool {
+    logs.iter().any(|line| line.contains(needle))
+}
+
+/// Move to a later slot and `seconds` later in time
+///
+/// Warping recomputes the clock's timestamp, so it is written back explicitly to keep time
+/// moving forward only by the amount asked for.
+pub async fn advance_clock(context: &mut ProgramTestContext, seconds: i64) {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 2).unwrap();
+    let mut warped: Clock = context.banks_client.get_sysvar().await.unwrap();
+    warped.unix_timestamp = clock.unix_timestamp + seconds;
+    context.set_sysvar(&warped);
+}
+
+/// Move the clock past the raffle's sales close and have `initiator` prepare it for the draw
+pub async fn end_sales(env: &mut TestEnv, raffle: &Pubkey, initiator: &Keypair) -> Result<(), BanksClientError> {
+    let raffle_data = get_raffle(&mut env.context, raffle).await;
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    let remaining = raffle_data.sales_close_time() - clock.unix_timestamp;
+    advance_clock(&mut env.context, remaining.max(0) + 1).await;
+
+    let instruction = raffle_instruction::prepare_raffle(&env.program_id, &initiator.pubkey(), raffle).unwrap();
+    process(&mut env.context, &[instruction], &[initiator]).await
+}
+
+/// Switchboard accounts for a randomness request, with the fixed-address roles filled in
+#[allow(deprecated)]
+pub fn vrf_remaining_accounts() -> Vec<AccountMeta> {
+    (0..solcino::vrf::VRF_REQUEST_REMAINING_ACCOUNTS.len())
+        .map(|position| match position {
+            5 => AccountMeta::new_readonly(solana_program::sysvar::recent_blockhashes::id(), false),
+            7 => AccountMeta::new_readonly(spl_token::id(), false),
+            _ => AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        })
+        .collect()
+}
+
+/// Request randomness from `vrf`, paid for by `requester`
+pub async fn request_randomness(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    vrf: &Pubkey,
+    requester: &Keypair,
+) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::request_randomness(
+        &env.program_id,
+        &requester.pubkey(),
+        raffle,
+        vrf,
+        &requester.pubkey(),
+        &env.switchboard,
+        &env.oracle_queue,
+        &env.config,
+        &vrf_remaining_accounts(),
+    )
+    .unwrap();
+    process(&mut env.context, &[instruction], &[requester]).await
+}
+
+/// Position in `records` (ascending pubkey order) of the `draw_number`-th distinct record drawn
+/// from `vrf`, excluding `excluded`
+///
+/// The development VRF's result is the VRF account's own key bytes.
+pub fn drawn_position(
+    vrf: &Pubkey,
+    records: &[(Pubkey, TicketPurchase)],
+    excluded: &[usize],
+    draw_number: usize,
+) -> usize {
+    let ticket_counts: Vec<u64> = records.iter().map(|(_, record)| record.ticket_count).collect();
+    raffle_core::draw_distinct_record(&vrf.to_bytes(), &ticket_counts, excluded, draw_number).unwrap()
+}
+
+/// Ticket records sorted the way the program walks them
+pub async fn sorted_records(context: &mut ProgramTestContext, records: &[Pubkey]) -> Vec<(Pubkey, TicketPurchase)> {
+    let mut keys = records.to_vec();
+    keys.sort();
+    let mut sorted = Vec::with_capacity(keys.len());
+    for key in keys {
+        sorted.push((key, get_record(context, &key).await));
+    }
+    sorted
+}
+
+/// Complete a raffle whose randomness was requested from `vrf`, returning the winning record
+pub async fn complete_raffle(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    vrf: &Pubkey,
+    completer: &Keypair,
+    records: &[Pubkey],
+) -> Result<Pubkey, BanksClientError> {
+    let sorted = sorted_records(&mut env.context, records).await;
+    let winning_position = drawn_position(vrf, &sorted, &[], 0);
+    let (winner, winner_data) = sorted[winning_position];
+    let consolation_wallet = consolation_recipient(env, raffle, vrf, &sorted, winning_position).await;
+    let instruction = complete_instruction(
+        env,
+        raffle,
+        vrf,
+        completer,
+        &winner,
+        &winner_data.purchaser,
+        &consolation_wallet,
+        records,
+    )
+    .await;
+    process(&mut env.context, &[instruction], &[completer]).await?;
+    Ok(winner)
+}
+
+/// Wallet completion pays the consolation pool to: the purchaser of the record drawn second,
+/// or the treasury when the winner holds every ticket or that wallet can't be recreated
+pub async fn consolation_recipient(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    vrf: &Pubkey,
+    records: &[(Pubkey, TicketPurchase)],
+    winning_position: usize,
+) -> Pubkey {
+    let raffle_data = get_raffle(&mut env.context, raffle).await;
+    if records.len() < 2 {
+        return raffle_data.treasury;
+    }
+    let purchaser = records[drawn_position(vrf, records, &[winning_position], 2)].1.purchaser;
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    if lamports(&mut env.context, &purchaser).await == 0 && raffle_data.consolation_pool < rent.minimum_balance(0) {
+        return raffle_data.treasury;
+    }
+    purchaser
+}
+
+/// CompleteRaffleWithVrf naming the winner and payout wallets as given
+#[allow(clippy::too_many_arguments)]
+pub async fn complete_instruction(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    vrf: &Pubkey,
+    completer: &Keypair,
+    winner: &Pubkey,
+    winner_wallet: &Pubkey,
+    consolation_wallet: &Pubkey,
+    records: &[Pubkey],
+) -> Instruction {
+    let raffle_data = get_raffle(&mut env.context, raffle).await;
+    let charity = if raffle_data.charity == Pubkey::default() {
+        raffle_data.treasury
+    } else {
+        raffle_data.charity
+    };
+    raffle_instruction::complete_raffle_with_vrf(
+        &env.program_id,
+        &completer.pubkey(),
+        raffle,
+        vrf,
+        winner,
+        &env.switchboard,
+        &raffle_data.authority,
+        &env.config,
+        &charity,
+        &raffle_data.treasury,
+        &raffle_data.vrf_requester,
+        winner_wallet,
+        consolation_wallet,
+        records,
+    )
+    .unwrap()
+}
+
+/// Have `keeper` end sales and request randomness, returning the VRF account
+pub async fn ready_to_complete(env: &mut TestEnv, raffle: &Pubkey, keeper: &Keypair) -> Result<Pubkey, BanksClientError> {
+    end_sales(env, raffle, keeper).await?;
+    let vrf = Pubkey::new_unique();
+    request_randomness(env, raffle, &vrf, keeper).await?;
+    // Completion has to land in a slot after the request
+    advance_clock(&mut env.context, 1).await;
+    Ok(vrf)
+}
+
+/// End sales, request randomness and complete, returning the VRF account and winning record
+pub async fn draw_raffle(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    records: &[Pubkey],
+) -> Result<(Pubkey, Pubkey), BanksClientError> {
+    let keeper = new_wallet(&mut env.context);
+    let vrf = ready_to_complete(env, raffle, &keeper).await?;
+    let winner = complete_raffle(env, raffle, &vrf, &keeper, records).await?;
+    Ok((vrf, winner))
+}
+
+pub async fn get_account(context: &mut ProgramTestContext, key: &Pubkey) -> Option<Account> {
+    context.banks_client.get_account(*key).await.unwrap()
+}
+
+/// Lamports held by `key`, zero if the account doesn't exist
+pub async fn lamports(context: &mut ProgramTestContext, key: &Pubkey) -> u64 {
+    get_account(context, key).await.map_or(0, |account| account.lamports)
+}
+
+pub async fn get_raffle(context: &mut ProgramTestContext, raffle: &Pubkey) -> Raffle {
+    Raffle::unpack(&get_account(context, raffle).await.expect("raffle account").data).unwrap()
+}
+
+pub async fn get_record(context: &mut ProgramTestContext, record: &Pubkey) -> TicketPurchase {
+    TicketPurchase::unpack(&get_account(context, record).await.expect("ticket record").data).unwrap()
+}
+
+pub async fn get_config(context: &mut ProgramTestContext, program_id: &Pubkey) -> Config {
+    let (config, _) = utils::find_config_address(program_id);
+    Config::unpack(&get_account(context, &config).await.expect("config account").data).unwrap()
+}
+
+/// Overwrite the config account's data in place
+pub async fn set_config(context: &mut ProgramTestContext, program_id: &Pubkey, config_data: Config) {
+    let (config, _) = utils::find_config_address(program_id);
+    let mut account = get_account(context, &config).await.expect("config account");
+    Config::pack(config_data, &mut account.data).unwrap();
+    context.set_account(&config, &account.into());
+}
+
+/// The program error a failed transaction's instruction returned
+pub fn program_error(result: Result<(), BanksClientError>) -> ProgramError {
+    match result.expect_err("transaction should have failed").unwrap() {
+        TransactionError::InstructionError(_, error) => ProgramError::try_from(error).unwrap(),
+        error => panic!("expected an instruction error, got {:?}", error),
+    }
+}
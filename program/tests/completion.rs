@@ -0,0 +1,244 @@
+//! CompleteRaffleWithVrf account checks and payouts, its ordering against RequestRandomness,
+//! and the deprecated CompleteRaffle
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_program};
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+};
+use solcino::{
+    raffle_error::RaffleError,
+    raffle_instruction,
+    raffle_state::{RaffleStatus, TicketPurchase, VRF_REQUEST_TIMEOUT_SECONDS},
+    utils,
+};
+
+#[tokio::test]
+async fn an_all_zero_winner_record_is_rejected() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let keeper = new_wallet(&mut env.context);
+    let vrf = ready_to_complete(&mut env, &raffle.raffle, &keeper).await.unwrap();
+
+    // Program-owned and the right size, but never written: it unpacks with every field zero
+    let zeroed = Pubkey::new_unique();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    env.context.set_account(
+        &zeroed,
+        &AccountSharedData::new(rent.minimum_balance(TicketPurchase::LEN), TicketPurchase::LEN, &env.program_id),
+    );
+
+    let (treasury, wallet) = (env.treasury, Pubkey::new_unique());
+    let instruction =
+        complete_instruction(&mut env, &raffle.raffle, &vrf, &keeper, &zeroed, &wallet, &treasury, &records).await;
+    let result = process(&mut env.context, &[instruction], &[&keeper]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::ReadyForRandomness);
+    assert_eq!(raffle_data.winner, Pubkey::default());
+}
+
+#[tokio::test]
+async fn a_program_address_named_as_the_winner_is_rejected() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let keeper = new_wallet(&mut env.context);
+    let vrf = ready_to_complete(&mut env, &raffle.raffle, &keeper).await.unwrap();
+
+    // Both are owned by the program, so only the reserved-address check stands between them
+    // and the prize
+    let (treasury, wallet) = (env.treasury, Pubkey::new_unique());
+    for reserved in [raffle.raffle, env.config] {
+        let instruction =
+            complete_instruction(&mut env, &raffle.raffle, &vrf, &keeper, &reserved, &wallet, &treasury, &records)
+                .await;
+        let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&keeper]).await;
+        assert_eq!(program_error(result), ProgramError::InvalidArgument);
+        let expected = format!("Winner account {} is a program or treasury address", reserved);
+        assert!(logged(&logs, &expected), "{:#?}", logs);
+    }
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::ReadyForRandomness);
+    assert_eq!(raffle_data.winner, Pubkey::default());
+}
+
+#[tokio::test]
+async fn a_sole_holder_completes_without_a_request_and_keeps_the_request_reward() {
+    let mut env = start().await;
+    let mut config_data = get_config(&mut env.context, &env.program_id).await;
+    config_data.keeper_reward_bps = 300;
+    config_data.request_reward_bps = 200;
+    set_config(&mut env.context, &env.program_id, config_data).await;
+
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 4).await.unwrap();
+
+    // The single-holder path skips RequestRandomness, so nobody paid for randomness
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.vrf_requester, Pubkey::default());
+
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    let buyer_before = lamports(&mut env.context, &buyer.pubkey()).await;
+    let keeper_before = lamports(&mut env.context, &keeper.pubkey()).await;
+    let system_program_before = lamports(&mut env.context, &system_program::id()).await;
+    complete_raffle(&mut env, &raffle.raffle, &Pubkey::new_unique(), &keeper, &[record.pubkey()]).await.unwrap();
+
+    let (prize, keeper_reward) = utils::split_fee(pool, config_data.keeper_reward_bps).unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::Complete);
+    assert_eq!(raffle_data.prize_amount, prize);
+    assert_eq!(lamports(&mut env.context, &buyer.pubkey()).await, buyer_before + prize);
+    assert_eq!(lamports(&mut env.context, &keeper.pubkey()).await, keeper_before + keeper_reward);
+    assert_eq!(lamports(&mut env.context, &system_program::id()).await, system_program_before);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+}
+
+#[tokio::test]
+async fn completion_without_a_randomness_request_is_rejected() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    // Sales are closed, but nobody has asked the oracle for randomness
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let result = complete_raffle(&mut env, &raffle.raffle, &Pubkey::new_unique(), &keeper, &records).await;
+    assert_eq!(program_error(result.map(|_| ())), ProgramError::from(RaffleError::VrfNotRequested));
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.status, RaffleStatus::ReadyForRandomness);
+}
+
+#[tokio::test]
+async fn randomness_cannot_be_requested_for_a_completed_raffle() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let (vrf, _) = draw_raffle(&mut env, &raffle.raffle, &[buyers[0].1.pubkey(), buyers[1].1.pubkey()]).await.unwrap();
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+
+    let requester = new_wallet(&mut env.context);
+    let result = request_randomness(&mut env, &raffle.raffle, &Pubkey::new_unique(), &requester).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    let after = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(after.status, RaffleStatus::Complete);
+    assert_eq!(after.vrf_account, vrf);
+    assert_eq!(after.winner, completed.winner);
+}
+
+#[tokio::test]
+async fn a_second_randomness_request_waits_for_the_first_to_time_out() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+
+    let first = Pubkey::new_unique();
+    request_randomness(&mut env, &raffle.raffle, &first, &keeper).await.unwrap();
+    let requested = get_raffle(&mut env.context, &raffle.raffle).await;
+
+    // A second caller can't swap in their own VRF account while the first request is live
+    let second = Pubkey::new_unique();
+    let requester = new_wallet(&mut env.context);
+    let result = request_randomness(&mut env, &raffle.raffle, &second, &requester).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.vrf_account, first);
+    assert_eq!(raffle_data.vrf_requester, keeper.pubkey());
+    assert_eq!(raffle_data.vrf_requested_at, requested.vrf_requested_at);
+
+    // Once the first request has had its full window it can be replaced
+    advance_clock(&mut env.context, VRF_REQUEST_TIMEOUT_SECONDS).await;
+    request_randomness(&mut env, &raffle.raffle, &second, &requester).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.vrf_account, second);
+    assert_eq!(raffle_data.vrf_requester, requester.pubkey());
+}
+
+#[tokio::test]
+async fn the_raffle_or_treasury_cannot_stand_in_for_the_vrf_account() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+
+    // Either would have completion read known account bytes as the randomness
+    let treasury = get_raffle(&mut env.context, &raffle.raffle).await.treasury;
+    for vrf in [raffle.raffle, treasury] {
+        let result = request_randomness(&mut env, &raffle.raffle, &vrf, &keeper).await;
+        assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    }
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::ReadyForRandomness);
+    assert!(!raffle_data.vrf_request_in_progress);
+    assert_eq!(raffle_data.vrf_account, Pubkey::default());
+}
+
+#[tokio::test]
+#[allow(deprecated)]
+async fn the_deprecated_complete_raffle_is_rejected() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+    end_sales(&mut env, &raffle.raffle, &authority).await.unwrap();
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+
+    // Winner selection only goes through the VRF flow
+    let instruction =
+        raffle_instruction::complete_raffle(&env.program_id, &authority.pubkey(), &raffle.raffle, &record.pubkey())
+            .unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&authority]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidInstructionData);
+    assert!(logged(&logs, "Use CompleteRaffleWithVrf instruction instead"), "{:#?}", logs);
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::ReadyForRandomness);
+    assert_eq!(raffle_data.winner, Pubkey::default());
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, pool);
+}
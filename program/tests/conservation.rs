@@ -0,0 +1,225 @@
+//! No lamports are created or destroyed over a whole raffle, apart from transaction fees
+//!
+//! Transaction fees are all paid by the context payer, which is left out of the totals.
+
+mod common;
+
+use common::*;
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{
+    raffle_instruction,
+    raffle_state::{RaffleStatus, Referral, TicketPurchase},
+    utils,
+};
+
+struct Case {
+    ticket_price: u64,
+    fee_basis_points: u16,
+    /// (buyer, ticket_count) in purchase order; a buyer listed twice tops up their record
+    purchases: &'static [(usize, u64)],
+    buyer_count: usize,
+    claim_window: u64,
+}
+
+const CASES: [Case; 4] = [
+    Case { ticket_price: 25_000_000, fee_basis_points: 1_000, purchases: &[(0, 3), (1, 2)], buyer_count: 2, claim_window: 0 },
+    Case { ticket_price: 1_000_000, fee_basis_points: 0, purchases: &[(0, 1), (1, 1), (2, 5)], buyer_count: 3, claim_window: 3_600 },
+    Case { ticket_price: 7_777_777, fee_basis_points: 333, purchases: &[(0, 4), (1, 1), (0, 2)], buyer_count: 2, claim_window: 3_600 },
+    Case { ticket_price: 2_000_000_000, fee_basis_points: 2_500, purchases: &[(0, 1), (1, 3), (2, 2), (1, 1)], buyer_count: 3, claim_window: 0 },
+];
+
+async fn total_lamports(env: &mut TestEnv, accounts: &[Pubkey]) -> u64 {
+    let mut total = 0;
+    for account in accounts {
+        total += lamports(&mut env.context, account).await;
+    }
+    total
+}
+
+async fn run_case(case: &Case) {
+    let mut env = start().await;
+    let mut config_data = get_config(&mut env.context, &env.program_id).await;
+    config_data.ticket_price = case.ticket_price;
+    set_config(&mut env.context, &env.program_id, config_data).await;
+
+    let authority = new_wallet(&mut env.context);
+    let keeper = new_wallet(&mut env.context);
+    let buyers: Vec<(Keypair, Keypair)> =
+        (0..case.buyer_count).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+
+    // Every account the raffle can move lamports between, including those it creates
+    let raffle_index = config_data.next_raffle_index;
+    let (raffle_key, _) = utils::find_raffle_pda(&env.program_id, &authority.pubkey(), 0);
+    let (vault, _) = utils::find_vault_address(&env.program_id, &raffle_key);
+    let (index_pointer, _) = utils::find_index_pointer_pda(&env.program_id, raffle_index);
+    let (authority_state, _) = utils::find_authority_state_address(&env.program_id, &authority.pubkey());
+    let mut tracked = vec![
+        authority.pubkey(),
+        keeper.pubkey(),
+        env.treasury,
+        env.config,
+        raffle_key,
+        vault,
+        index_pointer,
+        authority_state,
+    ];
+    for (wallet, record) in &buyers {
+        tracked.push(wallet.pubkey());
+        tracked.push(record.pubkey());
+    }
+    let before = total_lamports(&mut env, &tracked).await;
+
+    let params = RaffleParams {
+        fee_override_bps: case.fee_basis_points,
+        claim_window: case.claim_window,
+        ..raffle_params()
+    };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    assert_eq!(raffle.raffle, raffle_key);
+    for &(buyer, ticket_count) in case.purchases {
+        let (wallet, record) = &buyers[buyer];
+        buy_tickets(&mut env, &raffle.raffle, wallet, record, ticket_count).await.unwrap();
+    }
+    assert_eq!(total_lamports(&mut env, &tracked).await, before, "after purchases");
+
+    let records: Vec<Pubkey> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+    let vrf = ready_to_complete(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let winner = complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await.unwrap();
+    assert_eq!(total_lamports(&mut env, &tracked).await, before, "after completion");
+
+    if case.claim_window > 0 {
+        let (wallet, _) = buyers.iter().find(|(_, record)| record.pubkey() == winner).unwrap();
+        let instruction =
+            raffle_instruction::claim_prize(&env.program_id, &wallet.pubkey(), &raffle.raffle, &winner).unwrap();
+        process(&mut env.context, &[instruction], &[wallet]).await.unwrap();
+        assert_eq!(total_lamports(&mut env, &tracked).await, before, "after claim");
+    }
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::Complete);
+    assert!(raffle_data.prize_claimed);
+    assert_eq!(lamports(&mut env.context, &vault).await, 0);
+}
+
+#[tokio::test]
+async fn lamports_are_conserved_across_the_raffle_lifecycle() {
+    for case in &CASES {
+        run_case(case).await;
+    }
+}
+
+async fn snapshot(env: &mut TestEnv, accounts: &[Pubkey]) -> Vec<(Pubkey, u64)> {
+    let mut balances = Vec::new();
+    for account in accounts {
+        balances.push((*account, lamports(&mut env.context, account).await));
+    }
+    balances
+}
+
+/// How much each account in a `snapshot` has gained since; payouts only ever add lamports
+async fn gains(env: &mut TestEnv, before: &[(Pubkey, u64)]) -> Vec<u64> {
+    let mut gains = Vec::new();
+    for (account, lamports_before) in before {
+        gains.push(lamports(&mut env.context, account).await - lamports_before);
+    }
+    gains
+}
+
+#[tokio::test]
+async fn every_fee_component_is_accounted_for() {
+    let mut env = start().await;
+    let mut config_data = get_config(&mut env.context, &env.program_id).await;
+    config_data.referral_bps = 2_000;
+    config_data.keeper_reward_bps = 300;
+    config_data.request_reward_bps = 200;
+    set_config(&mut env.context, &env.program_id, config_data).await;
+
+    let authority = new_wallet(&mut env.context);
+    let keeper = new_wallet(&mut env.context);
+    let referrer = new_wallet(&mut env.context);
+    let charity = new_wallet(&mut env.context);
+    let params = RaffleParams {
+        charity: charity.pubkey(),
+        charity_bps: 1_000,
+        consolation_bps: 2_500,
+        ..raffle_params()
+    };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let buyers: Vec<(Keypair, Keypair)> = (0..3).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+
+    // Purchase side: each buyer's outflow is split between treasury, referrer and vault
+    let treasury_before = lamports(&mut env.context, &env.treasury).await;
+    let (mut treasury_share, mut referral_share, mut pool_share, mut consolation_share) = (0, 0, 0, 0);
+    for (buyer, ticket_count, referred) in [(0, 3, true), (1, 2, true), (2, 4, false)] {
+        let (wallet, record) = &buyers[buyer];
+        let referred_by = referrer.pubkey();
+        buy_referred_tickets(&mut env, &raffle.raffle, wallet, record, ticket_count, referred.then_some(&referred_by))
+            .await
+            .unwrap();
+
+        let (principal, fee) = utils::split_fee(ticket_count * raffle_data.ticket_price, raffle_data.fee_basis_points).unwrap();
+        let consolation = utils::calculate_fee(fee, raffle_data.consolation_bps).unwrap();
+        let (treasury, referral) = if referred {
+            utils::split_fee(fee - consolation, raffle_data.referral_bps).unwrap()
+        } else {
+            (fee - consolation, 0)
+        };
+        treasury_share += treasury;
+        referral_share += referral;
+        pool_share += principal;
+        consolation_share += consolation;
+    }
+    assert!(referral_share > 0 && consolation_share > 0);
+
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let account_rent = 3 * rent.minimum_balance(TicketPurchase::LEN) + rent.minimum_balance(Referral::LEN);
+    let mut outflow = 0;
+    for (wallet, _) in &buyers {
+        outflow += WALLET_LAMPORTS - lamports(&mut env.context, &wallet.pubkey()).await;
+    }
+    let treasury_gain = lamports(&mut env.context, &env.treasury).await - treasury_before;
+    let referrer_gain = lamports(&mut env.context, &referrer.pubkey()).await - WALLET_LAMPORTS;
+    let vault = lamports(&mut env.context, &raffle.vault).await;
+    assert_eq!(treasury_gain, treasury_share);
+    assert_eq!(referrer_gain, referral_share);
+    assert_eq!(vault, pool_share + consolation_share);
+    assert_eq!(outflow - account_rent, treasury_gain + referrer_gain + vault);
+    let (referral, _) = utils::find_referral_address(&env.program_id, &referrer.pubkey());
+    assert_eq!(get_account(&mut env.context, &referral).await.unwrap().lamports, rent.minimum_balance(Referral::LEN));
+
+    // Completion side: the vault is split between winner, charity, keeper and consolation winner.
+    // The keeper both requested the randomness and completes, so it earns both rewards.
+    let records: Vec<Pubkey> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+    let vrf = ready_to_complete(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let mut payees = vec![charity.pubkey(), keeper.pubkey(), env.treasury];
+    payees.extend(buyers.iter().map(|(wallet, _)| wallet.pubkey()));
+    let before = snapshot(&mut env, &payees).await;
+    let winner = complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await.unwrap();
+    let paid = gains(&mut env, &before).await;
+
+    let (pool, keeper_reward) = utils::split_fee(pool_share, raffle_data.complete_reward_bps).unwrap();
+    let (pool, request_reward) = utils::split_fee(pool, raffle_data.request_reward_bps).unwrap();
+    let (prize, charity_share) = utils::split_fee(pool, raffle_data.charity_bps).unwrap();
+    assert!(keeper_reward > 0 && request_reward > 0 && charity_share > 0);
+
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    let winner_wallet = get_record(&mut env.context, &winner).await.purchaser;
+    let consolation_wallet = get_record(&mut env.context, &completed.consolation_winner).await.purchaser;
+    assert_ne!(winner_wallet, consolation_wallet);
+    assert_eq!(completed.prize_amount, prize);
+    assert_eq!(paid[0], charity_share);
+    assert_eq!(paid[1], keeper_reward + request_reward);
+    assert_eq!(paid[2], 0);
+    for ((wallet, _), gain) in buyers.iter().zip(&paid[3..]) {
+        let expected = match wallet.pubkey() {
+            key if key == winner_wallet => prize,
+            key if key == consolation_wallet => consolation_share,
+            _ => 0,
+        };
+        assert_eq!(*gain, expected);
+    }
+    assert_eq!(paid.iter().sum::<u64>(), vault);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+}
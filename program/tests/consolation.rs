@@ -0,0 +1,117 @@
+//! The fee-funded consolation pool is drawn and paid out when the raffle completes
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_instruction, utils};
+
+/// Buy `ticket_counts` tickets each from fresh wallets, returning (wallet, record) pairs and the
+/// consolation share of the fees they paid
+async fn buy_from(env: &mut TestEnv, raffle: &Pubkey, ticket_counts: &[u64]) -> (Vec<(Keypair, Keypair)>, u64) {
+    let raffle_data = get_raffle(&mut env.context, raffle).await;
+    let mut buyers = Vec::new();
+    let mut expected_pool = 0;
+    for &ticket_count in ticket_counts {
+        let (wallet, record) = (new_wallet(&mut env.context), Keypair::new());
+        buy_tickets(env, raffle, &wallet, &record, ticket_count).await.unwrap();
+        let (_, fee) = utils::split_fee(ticket_count * raffle_data.ticket_price, raffle_data.fee_basis_points).unwrap();
+        expected_pool += utils::calculate_fee(fee, raffle_data.consolation_bps).unwrap();
+        buyers.push((wallet, record));
+    }
+    (buyers, expected_pool)
+}
+
+#[tokio::test]
+async fn completion_pays_the_consolation_pool_to_another_buyer() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { consolation_bps: 5_000, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let (buyers, expected_pool) = buy_from(&mut env, &raffle.raffle, &[3, 2, 4]).await;
+    let records: Vec<Pubkey> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+
+    let consolation_pool = get_raffle(&mut env.context, &raffle.raffle).await.consolation_pool;
+    assert!(consolation_pool > 0);
+    assert_eq!(consolation_pool, expected_pool);
+    let vault_before = lamports(&mut env.context, &raffle.vault).await;
+    let mut wallets_before = Vec::new();
+    for (wallet, _) in &buyers {
+        wallets_before.push(lamports(&mut env.context, &wallet.pubkey()).await);
+    }
+
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_ne!(raffle_data.consolation_winner, Pubkey::default());
+    assert_ne!(raffle_data.consolation_winner, winner);
+    assert_eq!(raffle_data.consolation_pool, 0);
+    assert_eq!(raffle_data.prize_amount, vault_before - consolation_pool);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+    for (position, (wallet, record)) in buyers.iter().enumerate() {
+        let received = lamports(&mut env.context, &wallet.pubkey()).await - wallets_before[position];
+        if record.pubkey() == winner {
+            assert_eq!(received, raffle_data.prize_amount);
+        } else if record.pubkey() == raffle_data.consolation_winner {
+            assert_eq!(received, consolation_pool);
+        } else {
+            assert_eq!(received, 0);
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_consolation_pool_below_the_rent_minimum_does_not_strand_the_vault() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { consolation_bps: 1, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let (buyers, expected_pool) = buy_from(&mut env, &raffle.raffle, &[3, 2]).await;
+    let records: Vec<Pubkey> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    assert!(expected_pool > 0 && expected_pool < rent.minimum_balance(0));
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.consolation_pool, expected_pool);
+
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let runner_up_record = records.iter().find(|record| **record != winner).unwrap();
+    assert_eq!(raffle_data.consolation_winner, *runner_up_record);
+    assert_eq!(raffle_data.consolation_pool, 0);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+}
+
+#[tokio::test]
+async fn a_sole_buyer_consolation_pool_goes_to_the_treasury() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { consolation_bps: 5_000, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let (buyers, expected_pool) = buy_from(&mut env, &raffle.raffle, &[4]).await;
+    let records = [buyers[0].1.pubkey()];
+
+    let treasury_before = lamports(&mut env.context, &env.treasury).await;
+    let (vrf, _) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.consolation_winner, env.treasury);
+    assert_eq!(lamports(&mut env.context, &env.treasury).await, treasury_before + expected_pool);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+
+    // Completion already paid the pool, so there is nothing left for ConsolationDraw
+    let initiator = new_wallet(&mut env.context);
+    let instruction = raffle_instruction::consolation_draw(
+        &env.program_id,
+        &initiator.pubkey(),
+        &raffle.raffle,
+        &vrf,
+        &env.switchboard,
+        &env.treasury,
+        &records,
+    )
+    .unwrap();
+    let result = process(&mut env.context, &[instruction], &[&initiator]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+}
@@ -0,0 +1,91 @@
+//! Another program creates a raffle through CPI, signing as one of its own PDAs
+
+mod common;
+
+use common::*;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_program_test::processor;
+use solana_sdk::signature::Signer;
+use solcino::{
+    raffle_state::{AuthorityState, Raffle, RaffleIndexPointer, RaffleStatus},
+    utils,
+};
+
+/// Seed of the mock caller's PDA that creates raffles
+const CREATOR_SEED: &[u8] = b"creator";
+
+/// A caller program that forwards its instruction data and accounts, after the raffle program at
+/// account 0, to the raffle program, signing for its creator PDA
+fn mock_caller(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (raffle_program, raffle_accounts) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (creator, bump) = Pubkey::find_program_address(&[CREATOR_SEED], program_id);
+    let instruction = Instruction {
+        program_id: *raffle_program.key,
+        accounts: raffle_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer || *account.key == creator,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    invoke_signed(&instruction, accounts, &[&[CREATOR_SEED, &[bump]]])
+}
+
+#[tokio::test]
+async fn a_caller_pda_creates_a_raffle_with_a_separate_payer() {
+    let caller_id = Pubkey::new_unique();
+    let mut env = start_with(|program_test| {
+        program_test.add_program("mock_caller", caller_id, processor!(mock_caller));
+    })
+    .await;
+    let (creator, _) = Pubkey::find_program_address(&[CREATOR_SEED], &caller_id);
+    let raffle_index = get_config(&mut env.context, &env.program_id).await.next_raffle_index;
+    let params = RaffleParams { fixed_prize_lamports: 2_000_000_000, ..raffle_params() };
+
+    // The creator PDA can't sign a transaction, so only the caller program can create under it
+    let direct = create_raffle_instruction(&env, &creator, raffle_index, params);
+    let mut unsigned = direct.clone();
+    unsigned.accounts[0].is_signer = false;
+    let result = process(&mut env.context, &[unsigned], &[]).await;
+    assert_eq!(program_error(result), ProgramError::MissingRequiredSignature);
+
+    // Through the caller, with a funded wallet as the optional payer at account 8
+    let payer = new_wallet(&mut env.context);
+    let mut accounts = vec![AccountMeta::new_readonly(env.program_id, false)];
+    accounts.extend(direct.accounts.iter().cloned().map(|meta| AccountMeta { is_signer: false, ..meta }));
+    accounts.push(AccountMeta::new(payer.pubkey(), true));
+    assert_eq!(accounts.len(), 1 + 9);
+    let via_caller = Instruction { program_id: caller_id, accounts, data: direct.data };
+    process(&mut env.context, &[via_caller], &[&payer]).await.unwrap();
+
+    let (raffle, _) = utils::find_raffle_pda(&env.program_id, &creator, params.nonce);
+    let raffle_data = get_raffle(&mut env.context, &raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::Active);
+    assert_eq!(raffle_data.authority, creator);
+    assert_eq!(raffle_data.raffle_index, raffle_index);
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.next_raffle_index, raffle_index + 1);
+
+    // The payer funded the accounts and the fixed prize; the PDA never held any lamports
+    let (vault, _) = utils::find_vault_address(&env.program_id, &raffle);
+    assert_eq!(lamports(&mut env.context, &vault).await, params.fixed_prize_lamports);
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let account_rent = rent.minimum_balance(Raffle::LEN)
+        + rent.minimum_balance(RaffleIndexPointer::LEN)
+        + rent.minimum_balance(AuthorityState::LEN);
+    assert_eq!(
+        WALLET_LAMPORTS - lamports(&mut env.context, &payer.pubkey()).await,
+        params.fixed_prize_lamports + account_rent
+    );
+    assert_eq!(lamports(&mut env.context, &creator).await, 0);
+}
@@ -0,0 +1,92 @@
+//! InitializeRaffle advances the config's raffle index, so the config must be writable, only
+//! initializes a raffle account of the current size, and never creates a raffle that has already ended
+
+mod common;
+
+use common::*;
+use solana_program::{clock::Clock, program_error::ProgramError};
+use solana_sdk::{account::AccountSharedData, signature::Signer};
+use solcino::{raffle_state::Raffle, utils};
+
+#[tokio::test]
+async fn a_read_only_config_is_rejected() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let next_index = get_config(&mut env.context, &env.program_id).await.next_raffle_index;
+
+    let mut instruction = create_raffle_instruction(&env, &authority.pubkey(), next_index, raffle_params());
+    let config_meta = instruction.accounts.iter_mut().find(|meta| meta.pubkey == env.config).unwrap();
+    assert!(config_meta.is_writable, "the constructor marks the config writable");
+    config_meta.is_writable = false;
+    let raffle = instruction.accounts[1].pubkey;
+
+    let result = process(&mut env.context, &[instruction], &[&authority]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    assert!(get_account(&mut env.context, &raffle).await.is_none());
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.next_raffle_index, next_index);
+}
+
+#[tokio::test]
+async fn each_raffle_takes_the_next_index() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let first_index = get_config(&mut env.context, &env.program_id).await.next_raffle_index;
+
+    for nonce in 0..3 {
+        let raffle = create_raffle(&mut env, &authority, RaffleParams { nonce, ..raffle_params() }).await.unwrap();
+        assert_eq!(raffle.raffle_index, first_index + nonce);
+        assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.raffle_index, first_index + nonce);
+        assert_eq!(get_config(&mut env.context, &env.program_id).await.next_raffle_index, first_index + nonce + 1);
+    }
+}
+
+#[tokio::test]
+async fn an_undersized_raffle_account_is_rejected_cleanly() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let next_index = get_config(&mut env.context, &env.program_id).await.next_raffle_index;
+
+    // Someone already allocated the raffle PDA, blank but too small for the current layout
+    let (raffle, _) = utils::find_raffle_pda(&env.program_id, &authority.pubkey(), 0);
+    let undersized = Raffle::LEN - 8;
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    env.context.set_account(
+        &raffle,
+        &AccountSharedData::new(rent.minimum_balance(undersized), undersized, &env.program_id),
+    );
+
+    let instruction = create_raffle_instruction(&env, &authority.pubkey(), next_index, raffle_params());
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&authority]).await;
+    assert_eq!(program_error(result), ProgramError::AccountDataTooSmall);
+    let expected = format!("Raffle account {} size {}, expected {}", raffle, undersized, Raffle::LEN);
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+
+    assert_eq!(get_account(&mut env.context, &raffle).await.unwrap().data, vec![0; undersized]);
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.next_raffle_index, next_index);
+}
+
+#[tokio::test]
+async fn a_new_raffle_always_ends_after_it_was_created() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+
+    for (nonce, duration) in [(0, 1), (1, 3_600), (2, 30 * 86_400)] {
+        let raffle = create_raffle(&mut env, &authority, RaffleParams { nonce, duration, ..raffle_params() })
+            .await
+            .unwrap();
+        let end_time = get_raffle(&mut env.context, &raffle.raffle).await.end_time;
+        assert!(end_time > clock.unix_timestamp, "duration {}", duration);
+        assert_eq!(end_time, clock.unix_timestamp + duration as i64);
+    }
+
+    // No duration, or one too large to add to the clock, would leave end_time at or before now
+    let next_index = get_config(&mut env.context, &env.program_id).await.next_raffle_index;
+    for duration in [0, i64::MAX as u64, u64::MAX] {
+        let params = RaffleParams { nonce: 3, duration, ..raffle_params() };
+        let instruction = create_raffle_instruction(&env, &authority.pubkey(), next_index, params);
+        let result = process(&mut env.context, &[instruction], &[&authority]).await;
+        assert_eq!(program_error(result), ProgramError::InvalidArgument, "duration {}", duration);
+    }
+    assert_eq!(get_config(&mut env.context, &env.program_id).await.next_raffle_index, next_index);
+}
@@ -0,0 +1,88 @@
+//! The creator bond goes back to the creator when their raffle completes, and into the prize
+//! when they abandon it
+
+mod common;
+
+use common::*;
+use solana_program::{clock::Clock, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{
+    raffle_instruction,
+    raffle_state::{RaffleStatus, CREATOR_BOND_GRACE_SECONDS},
+};
+
+const BOND: u64 = 1_000_000_000;
+
+/// A bonded raffle with two buyers, returning the raffle and the buyers' records
+async fn bonded_raffle(env: &mut TestEnv, authority: &Keypair) -> (TestRaffle, [Pubkey; 2]) {
+    let params = RaffleParams { creator_bond: BOND, ..raffle_params() };
+    let raffle = create_raffle(env, authority, params).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    (raffle, [buyers[0].1.pubkey(), buyers[1].1.pubkey()])
+}
+
+async fn slash(env: &mut TestEnv, raffle: &Pubkey, initiator: &Keypair) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::slash_creator_bond(&env.program_id, &initiator.pubkey(), raffle).unwrap();
+    process(&mut env.context, &[instruction], &[initiator]).await
+}
+
+#[tokio::test]
+async fn the_bond_is_refunded_when_the_raffle_completes() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let (raffle, records) = bonded_raffle(&mut env, &authority).await;
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, raffle_data.pool_principal + BOND);
+    let authority_before = lamports(&mut env.context, &authority.pubkey()).await;
+
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    // The creator gets the bond back and the winner gets the ticket pool alone
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert!(!completed.creator_bond_slashed);
+    assert_eq!(completed.prize_amount, raffle_data.pool_principal);
+    assert_eq!(completed.winner, winner);
+    assert_eq!(lamports(&mut env.context, &authority.pubkey()).await, authority_before + BOND);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+}
+
+#[tokio::test]
+async fn an_abandoned_raffle_forfeits_the_bond_into_the_prize() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let (raffle, records) = bonded_raffle(&mut env, &authority).await;
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let authority_before = lamports(&mut env.context, &authority.pubkey()).await;
+
+    // Sales close and the creator never prepares the raffle
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    advance_clock(&mut env.context, raffle_data.sales_close_time() - clock.unix_timestamp + 1).await;
+    let keeper = new_wallet(&mut env.context);
+    assert_eq!(program_error(slash(&mut env, &raffle.raffle, &keeper).await), ProgramError::InvalidArgument);
+
+    advance_clock(&mut env.context, CREATOR_BOND_GRACE_SECONDS).await;
+    assert_eq!(program_error(slash(&mut env, &raffle.raffle, &authority).await), ProgramError::InvalidArgument);
+    slash(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let slashed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert!(slashed.creator_bond_slashed);
+    assert_eq!(slashed.status, RaffleStatus::ReadyForRandomness);
+
+    let vrf = Pubkey::new_unique();
+    request_randomness(&mut env, &raffle.raffle, &vrf, &keeper).await.unwrap();
+    advance_clock(&mut env.context, 1).await;
+    complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await.unwrap();
+
+    // The bond was paid out with the prize instead of going back to the creator
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert_eq!(completed.prize_amount, raffle_data.pool_principal + BOND);
+    assert_eq!(lamports(&mut env.context, &authority.pubkey()).await, authority_before);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+}
@@ -0,0 +1,184 @@
+//! Property tests for the hand-written instruction decoder
+
+use proptest::prelude::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solcino::raffle_instruction::{RaffleInstruction, RaffleParams};
+
+/// One encoding of every instruction, used as the seed corpus for mutations
+fn seed_corpus() -> Vec<RaffleInstruction> {
+    vec![
+        RaffleInstruction::InitializeConfig { ticket_price: 25_000_000, fee_basis_points: 1000 },
+        RaffleInstruction::InitializeRaffle {
+            params: RaffleParams {
+                title: *b"Weekly raffle\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                duration: 86_400,
+                nonce: 7,
+                max_tickets: 1_000,
+                fee_override_bps: 250,
+                max_participants: 100,
+                fixed_prize_lamports: 5_000_000,
+                draw_on_sellout: true,
+                soft_cap_tickets: 500,
+                min_unique_participants: 3,
+                claim_window: 3_600,
+                purchase_grace_seconds: 60,
+                consolation_bps: 1_000,
+                creator_bond: 1_000_000,
+                payout_installments: 4,
+                payout_interval: 604_800,
+                charity: Pubkey::new_unique(),
+                charity_bps: 500,
+                min_pool_lamports: 10_000_000,
+                max_prize_lamports: 1_000_000_000,
+                max_total_fee_lamports: 50_000_000,
+                min_buyer_balance: 2_000_000,
+            },
+        },
+        RaffleInstruction::PurchaseTickets {
+            ticket_count: 3,
+            expected_ticket_price: 25_000_000,
+            referrer: Pubkey::new_unique(),
+        },
+        RaffleInstruction::CompleteRaffle {},
+        RaffleInstruction::UpdateAdmin {},
+        RaffleInstruction::UpdateFeeAddress {},
+        RaffleInstruction::UpdateTicketPrice { new_ticket_price: 30_000_000 },
+        RaffleInstruction::UpdateFeePercentage { new_fee_basis_points: 750 },
+        RaffleInstruction::RequestRandomness {},
+        RaffleInstruction::CompleteRaffleWithVrf {},
+        RaffleInstruction::PrepareRaffle {},
+        RaffleInstruction::GetWinnerInfo {},
+        RaffleInstruction::IncreaseMaxTickets { new_max: 2_000 },
+        RaffleInstruction::CancelRaffle {},
+        RaffleInstruction::RefundTickets {},
+        RaffleInstruction::GetVersion {},
+        RaffleInstruction::MigrateToVault {},
+        RaffleInstruction::CloseEmptyRaffle {},
+        RaffleInstruction::SetBlacklist { add: true, pubkey: Pubkey::new_unique() },
+        RaffleInstruction::FreezeRaffle { frozen: true },
+        RaffleInstruction::ReconcileRaffle {},
+        RaffleInstruction::ClaimPrize {},
+        RaffleInstruction::ForfeitToRunnerUp {},
+        RaffleInstruction::GetPdaInfo {},
+        RaffleInstruction::UpdateOracleQueue {},
+        RaffleInstruction::SimulatePurchase { ticket_count: 10 },
+        RaffleInstruction::ConsolationDraw {},
+        RaffleInstruction::SetPermissionlessCompletion { enabled: false },
+        RaffleInstruction::SetAllowedDurations { durations: [3_600, 86_400, 0, 604_800] },
+        RaffleInstruction::SlashCreatorBond {},
+        RaffleInstruction::SetBulkFee { bulk_threshold: 10, bulk_fee_bps: 500 },
+        RaffleInstruction::DonateToPool { amount: 1_000_000 },
+        RaffleInstruction::SetDrawDelay { draw_delay: 30 },
+        RaffleInstruction::GetBuyerSpend {},
+        RaffleInstruction::SetKeeperReward { keeper_reward_bps: 100 },
+        RaffleInstruction::SetReferralReward { referral_bps: 2_000 },
+        RaffleInstruction::RetargetVrf {},
+        RaffleInstruction::SetMaxActiveRaffles { max_active_raffles: 5 },
+        RaffleInstruction::AuditRaffle {},
+        RaffleInstruction::SetRequestReward { request_reward_bps: 50 },
+        RaffleInstruction::UpdateRaffleTitle { title: *b"Renamed raffle\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0" },
+        RaffleInstruction::MigrateConfig {},
+    ]
+}
+
+fn assert_decodes_or_rejects(data: &[u8]) {
+    match RaffleInstruction::unpack(data) {
+        Ok(instruction) => {
+            // Anything accepted must survive a round trip through its canonical encoding
+            assert_eq!(RaffleInstruction::unpack(&instruction.pack()), Ok(instruction));
+        }
+        Err(error) => assert_eq!(error, ProgramError::InvalidInstructionData, "data={:?}", data),
+    }
+}
+
+#[test]
+fn seed_corpus_covers_every_tag_and_round_trips() {
+    let corpus = seed_corpus();
+    let mut tags: Vec<u8> = corpus.iter().map(|instruction| instruction.pack()[0]).collect();
+    tags.sort_unstable();
+    tags.dedup();
+    assert_eq!(tags.len(), corpus.len(), "duplicate tag in seed corpus");
+
+    // Every tag the decoder knows about is in the corpus, and nothing past it decodes
+    for tag in 0..=u8::MAX {
+        let known = RaffleInstruction::expected_len(tag).is_some() || tag == 8;
+        assert_eq!(tags.contains(&tag), known, "tag {}", tag);
+    }
+
+    for instruction in corpus {
+        let data = instruction.pack();
+        if let Some(expected_len) = RaffleInstruction::expected_len(data[0]) {
+            assert_eq!(data.len(), expected_len, "{:?}", instruction);
+        }
+        assert_eq!(RaffleInstruction::unpack(&data), Ok(instruction));
+    }
+}
+
+#[test]
+fn a_trailing_byte_is_rejected_for_every_tag_but_request_randomness() {
+    for instruction in seed_corpus() {
+        let mut data = instruction.pack();
+        data.push(0);
+        if data[0] == 8 {
+            // RequestRandomness carries no fields and keeps accepting whatever follows its tag
+            assert_eq!(RaffleInstruction::unpack(&data), Ok(instruction));
+        } else {
+            assert_eq!(RaffleInstruction::unpack(&data), Err(ProgramError::InvalidInstructionData), "{:?}", instruction);
+        }
+    }
+}
+
+#[test]
+fn every_fixed_size_tag_rejects_one_byte_short_or_long() {
+    for instruction in seed_corpus() {
+        let data = instruction.pack();
+        let expected_len = match RaffleInstruction::expected_len(data[0]) {
+            Some(expected_len) => expected_len,
+            None => continue,
+        };
+        // The exact length decodes, so the rejections below come from the length alone
+        assert_eq!(RaffleInstruction::unpack(&data), Ok(instruction.clone()));
+
+        let short = &data[..expected_len - 1];
+        let mut long = data.clone();
+        long.push(0);
+        assert_eq!(RaffleInstruction::unpack(short), Err(ProgramError::InvalidInstructionData), "{:?} short", instruction);
+        assert_eq!(RaffleInstruction::unpack(&long), Err(ProgramError::InvalidInstructionData), "{:?} long", instruction);
+    }
+}
+
+proptest! {
+    #[test]
+    fn unpack_never_panics_on_arbitrary_bytes(data in proptest::collection::vec(any::<u8>(), 0..512)) {
+        assert_decodes_or_rejects(&data);
+    }
+
+    #[test]
+    fn unpack_never_panics_on_known_tags(tag in 0u8..=42, body in proptest::collection::vec(any::<u8>(), 0..400)) {
+        let mut data = vec![tag];
+        data.extend_from_slice(&body);
+        assert_decodes_or_rejects(&data);
+    }
+
+    #[test]
+    fn unpack_never_panics_on_mutated_seeds(
+        seed in 0..seed_corpus().len(),
+        truncate_to in any::<prop::sample::Index>(),
+        flip_at in any::<prop::sample::Index>(),
+        flip_mask in any::<u8>(),
+        extra in proptest::collection::vec(any::<u8>(), 0..4),
+    ) {
+        let original = seed_corpus()[seed].pack();
+
+        let mut flipped = original.clone();
+        let position = flip_at.index(flipped.len());
+        flipped[position] ^= flip_mask;
+        assert_decodes_or_rejects(&flipped);
+
+        assert_decodes_or_rejects(&original[..truncate_to.index(original.len() + 1)]);
+
+        let mut extended = original;
+        extended.extend_from_slice(&extra);
+        assert_decodes_or_rejects(&extended);
+    }
+}
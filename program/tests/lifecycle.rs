@@ -0,0 +1,129 @@
+//! End-to-end raffle lifecycle tests built on the shared harness
+
+mod common;
+
+use common::*;
+use solana_program::{clock::Clock, program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_instruction, raffle_state::RaffleStatus};
+
+#[tokio::test]
+async fn harness_runs_a_raffle_from_creation_to_payout() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+
+    let (alice, bob) = (new_wallet(&mut env.context), new_wallet(&mut env.context));
+    let (alice_record, bob_record) = (Keypair::new(), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &alice, &alice_record, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &bob, &bob_record, 2).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.tickets_sold, 5);
+    assert_eq!(raffle_data.participant_count, 2);
+    assert_eq!(get_record(&mut env.context, &alice_record.pubkey()).await.ticket_count, 3);
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    assert_eq!(pool, raffle_data.pool_principal);
+
+    let records = [alice_record.pubkey(), bob_record.pubkey()];
+    let wallets = [alice.pubkey(), bob.pubkey()];
+    let wallets_before = [
+        lamports(&mut env.context, &wallets[0]).await,
+        lamports(&mut env.context, &wallets[1]).await,
+    ];
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::Complete);
+    assert_eq!(raffle_data.winner, winner);
+    assert_eq!(raffle_data.prize_amount, pool);
+    assert_ne!(raffle_data.runner_up, Pubkey::default());
+    assert_ne!(raffle_data.runner_up, winner);
+    let position = records.iter().position(|record| *record == winner).unwrap();
+    assert_eq!(lamports(&mut env.context, &wallets[position]).await, wallets_before[position] + pool);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+}
+
+#[tokio::test]
+async fn a_raffle_moves_through_prepare_request_and_complete_in_order() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    // Sales are over, but the raffle stays Active until someone prepares it
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    advance_clock(&mut env.context, raffle_data.sales_close_time() - clock.unix_timestamp + 1).await;
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.status, RaffleStatus::Active);
+
+    // Skipping PrepareRaffle: neither a request nor a completion is accepted
+    let keeper = new_wallet(&mut env.context);
+    let vrf = Pubkey::new_unique();
+    let result = request_randomness(&mut env, &raffle.raffle, &vrf, &keeper).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    let result = complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await;
+    assert_eq!(program_error(result.map(|_| ())), ProgramError::InvalidArgument);
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::Active);
+    assert!(!raffle_data.vrf_request_in_progress);
+
+    let prepare = raffle_instruction::prepare_raffle(&env.program_id, &keeper.pubkey(), &raffle.raffle).unwrap();
+    process(&mut env.context, &[prepare], &[&keeper]).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::ReadyForRandomness);
+    assert!(!raffle_data.vrf_request_in_progress);
+
+    request_randomness(&mut env, &raffle.raffle, &vrf, &keeper).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::ReadyForRandomness);
+    assert!(raffle_data.vrf_request_in_progress);
+    assert_eq!(raffle_data.vrf_account, vrf);
+
+    advance_clock(&mut env.context, 1).await;
+    let winner = complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.status, RaffleStatus::Complete);
+    assert_eq!(raffle_data.winner, winner);
+    assert!(!raffle_data.vrf_request_in_progress);
+}
+
+#[tokio::test]
+async fn prepare_freezes_the_ticket_total_sold_by_then() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { purchase_grace_seconds: 600, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let buyers: Vec<(Keypair, Keypair)> = (0..3).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+
+    // Purchases in the grace window after end_time still count towards the draw
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    advance_clock(&mut env.context, raffle_data.end_time - clock.unix_timestamp + 1).await;
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 1).await.unwrap();
+
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let prepared = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(prepared.status, RaffleStatus::ReadyForRandomness);
+    assert_eq!(prepared.tickets_sold, 6);
+    assert_eq!(prepared.closing_tickets_sold, prepared.tickets_sold);
+    assert_eq!(prepared.closing_participant_count, prepared.participant_count);
+    assert_eq!(prepared.draw_tickets_sold(), 6);
+
+    // Nothing sold after preparing can change the total the draw is made over
+    let result = buy_tickets(&mut env, &raffle.raffle, &buyers[2].0, &buyers[2].1, 4).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.tickets_sold, 6);
+    assert_eq!(raffle_data.closing_tickets_sold, 6);
+    assert_eq!(raffle_data.closing_participant_count, 2);
+}
@@ -0,0 +1,243 @@
+//! MigrateToVault on raffles created before the vault model, MigrateConfig on configs
+//! created before the admin settings, and the guidance logged for accounts of the wrong size
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+};
+use solcino::{
+    raffle_instruction,
+    raffle_state::{Config, Raffle, RaffleStatus, LEGACY_CONFIG_LEN, LEGACY_RAFFLE_LEN, MAX_ALLOWED_DURATIONS},
+};
+
+/// Rewrite a raffle with tickets sold into the legacy shape: a `LEGACY_RAFFLE_LEN` account
+/// holding its own pool above rent, with no vault. Returns the pool.
+async fn make_legacy(env: &mut TestEnv, raffle: &TestRaffle) -> u64 {
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    env.context.set_account(&raffle.vault, &AccountSharedData::default());
+
+    let account = get_account(&mut env.context, &raffle.raffle).await.unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let mut legacy = AccountSharedData::new(
+        rent.minimum_balance(LEGACY_RAFFLE_LEN) + pool,
+        LEGACY_RAFFLE_LEN,
+        &env.program_id,
+    );
+    legacy.set_data(account.data[..LEGACY_RAFFLE_LEN].to_vec());
+    env.context.set_account(&raffle.raffle, &legacy);
+    pool
+}
+
+async fn migrate(env: &mut TestEnv, raffle: &Pubkey, initiator: &Keypair) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::migrate_to_vault(&env.program_id, &initiator.pubkey(), raffle).unwrap();
+    process(&mut env.context, &[instruction], &[initiator]).await
+}
+
+#[tokio::test]
+async fn a_migrated_legacy_raffle_completes_from_its_vault() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let before = get_raffle(&mut env.context, &raffle.raffle).await;
+    let pool = make_legacy(&mut env, &raffle).await;
+
+    let initiator = new_wallet(&mut env.context);
+    migrate(&mut env, &raffle.raffle, &initiator).await.unwrap();
+
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let account = get_account(&mut env.context, &raffle.raffle).await.unwrap();
+    assert_eq!(account.data.len(), Raffle::LEN);
+    assert_eq!(account.lamports, rent.minimum_balance(Raffle::LEN));
+    assert_eq!(
+        lamports(&mut env.context, &initiator.pubkey()).await,
+        WALLET_LAMPORTS - (rent.minimum_balance(Raffle::LEN) - rent.minimum_balance(LEGACY_RAFFLE_LEN))
+    );
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, pool);
+
+    let migrated = Raffle::unpack(&account.data).unwrap();
+    assert!(migrated.uses_vault);
+    assert_eq!(migrated.pool_principal, pool);
+    assert_eq!(migrated.status, RaffleStatus::Active);
+    assert_eq!(migrated.authority, before.authority);
+    assert_eq!(migrated.title, before.title);
+    assert_eq!(migrated.tickets_sold, 5);
+    assert_eq!(migrated.raffle_index, before.raffle_index);
+
+    // Migration runs once
+    let again = new_wallet(&mut env.context);
+    assert_eq!(program_error(migrate(&mut env, &raffle.raffle, &again).await), ProgramError::InvalidAccountData);
+
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+    let mut wallets_before = Vec::new();
+    for (wallet, _) in &buyers {
+        wallets_before.push(lamports(&mut env.context, &wallet.pubkey()).await);
+    }
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert_eq!(completed.prize_amount, pool);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+    let position = records.iter().position(|record| *record == winner).unwrap();
+    assert_eq!(lamports(&mut env.context, &buyers[position].0.pubkey()).await, wallets_before[position] + pool);
+}
+
+#[tokio::test]
+async fn a_legacy_raffle_must_migrate_before_it_is_drawn() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+    make_legacy(&mut env, &raffle).await;
+
+    // Every other instruction expects the current layout
+    let initiator = new_wallet(&mut env.context);
+    let prepare = raffle_instruction::prepare_raffle(&env.program_id, &initiator.pubkey(), &raffle.raffle).unwrap();
+    let result = process(&mut env.context, &[prepare], &[&initiator]).await;
+    assert_eq!(program_error(result), ProgramError::AccountDataTooSmall);
+
+    migrate(&mut env, &raffle.raffle, &initiator).await.unwrap();
+    draw_raffle(&mut env, &raffle.raffle, &[record.pubkey()]).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.status, RaffleStatus::Complete);
+}
+
+/// Resize a program-owned account to `len` bytes, keeping its leading data
+async fn resize(env: &mut TestEnv, key: &Pubkey, len: usize) {
+    let account = get_account(&mut env.context, key).await.unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let mut data = account.data;
+    data.resize(len, 0);
+    let mut resized = AccountSharedData::new(rent.minimum_balance(len), len, &env.program_id);
+    resized.set_data(data);
+    env.context.set_account(key, &resized);
+}
+
+#[tokio::test]
+async fn an_old_sized_account_logs_which_migration_to_run() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    resize(&mut env, &raffle.raffle, LEGACY_RAFFLE_LEN).await;
+
+    let initiator = new_wallet(&mut env.context);
+    let prepare = raffle_instruction::prepare_raffle(&env.program_id, &initiator.pubkey(), &raffle.raffle).unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[prepare], &[&initiator]).await;
+    assert_eq!(program_error(result), ProgramError::AccountDataTooSmall);
+    let expected = format!(
+        "Raffle account {} size {}, expected {} - migration required, run MigrateToVault",
+        raffle.raffle,
+        LEGACY_RAFFLE_LEN,
+        Raffle::LEN
+    );
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+
+    let config = env.config;
+    resize(&mut env, &config, LEGACY_CONFIG_LEN).await;
+    let update = raffle_instruction::update_ticket_price(&env.program_id, &env.admin.pubkey(), &env.config, 30_000_000).unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[update], &[&env.admin]).await;
+    assert_eq!(program_error(result), ProgramError::AccountDataTooSmall);
+    let expected = format!(
+        "Config account {} size {}, expected {} - migration required, run MigrateConfig",
+        env.config,
+        LEGACY_CONFIG_LEN,
+        Config::LEN
+    );
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+}
+
+#[tokio::test]
+async fn an_unrecognised_size_is_not_told_to_migrate() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    resize(&mut env, &raffle.raffle, Raffle::LEN + 8).await;
+
+    let initiator = new_wallet(&mut env.context);
+    let prepare = raffle_instruction::prepare_raffle(&env.program_id, &initiator.pubkey(), &raffle.raffle).unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[prepare], &[&initiator]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    let expected = format!(
+        "Raffle account {} size {}, expected {} - unrecognised layout, no migration available",
+        raffle.raffle,
+        Raffle::LEN + 8,
+        Raffle::LEN
+    );
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+    assert!(!logged(&logs, "migration required"), "{:#?}", logs);
+}
+
+async fn migrate_config(env: &mut TestEnv, admin: &Keypair) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::migrate_config(&env.program_id, &admin.pubkey(), &env.config).unwrap();
+    process(&mut env.context, &[instruction], &[admin]).await
+}
+
+#[tokio::test]
+async fn a_legacy_config_is_migrated_by_the_admin() {
+    let mut env = start().await;
+    let before = get_config(&mut env.context, &env.program_id).await;
+
+    // Cut the config back to the layout the program was first deployed with
+    let account = get_account(&mut env.context, &env.config).await.unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let mut legacy = AccountSharedData::new(rent.minimum_balance(LEGACY_CONFIG_LEN), LEGACY_CONFIG_LEN, &env.program_id);
+    legacy.set_data(account.data[..LEGACY_CONFIG_LEN].to_vec());
+    env.context.set_account(&env.config, &legacy);
+
+    let authority = new_wallet(&mut env.context);
+    let instruction =
+        create_raffle_instruction(&env, &authority.pubkey(), before.next_raffle_index, raffle_params());
+    let result = process(&mut env.context, &[instruction], &[&authority]).await;
+    assert_eq!(program_error(result), ProgramError::AccountDataTooSmall);
+
+    let stranger = new_wallet(&mut env.context);
+    assert_eq!(program_error(migrate_config(&mut env, &stranger).await), ProgramError::InvalidAccountData);
+
+    let admin = Keypair::from_bytes(&env.admin.to_bytes()).unwrap();
+    migrate_config(&mut env, &admin).await.unwrap();
+
+    let account = get_account(&mut env.context, &env.config).await.unwrap();
+    assert_eq!(account.data.len(), Config::LEN);
+    assert_eq!(account.lamports, rent.minimum_balance(Config::LEN));
+    assert_eq!(
+        lamports(&mut env.context, &admin.pubkey()).await,
+        WALLET_LAMPORTS - (rent.minimum_balance(Config::LEN) - rent.minimum_balance(LEGACY_CONFIG_LEN))
+    );
+
+    // The legacy fields carry over; the rest start from zero apart from the price band and
+    // permissionless completion
+    let migrated = get_config(&mut env.context, &env.program_id).await;
+    let defaults = Config::default();
+    assert_eq!(migrated.admin, before.admin);
+    assert_eq!(migrated.treasury, before.treasury);
+    assert_eq!(migrated.ticket_price, before.ticket_price);
+    assert_eq!(migrated.fee_basis_points, before.fee_basis_points);
+    assert_eq!(migrated.next_raffle_index, before.next_raffle_index);
+    assert_eq!(migrated.oracle_queue, Pubkey::default());
+    assert_eq!(migrated.allowed_durations, [0; MAX_ALLOWED_DURATIONS]);
+    assert_eq!((migrated.keeper_reward_bps, migrated.request_reward_bps, migrated.referral_bps), (0, 0, 0));
+    assert_eq!(migrated.min_ticket_price, defaults.min_ticket_price);
+    assert_eq!(migrated.max_ticket_price, defaults.max_ticket_price);
+    assert!(migrated.permissionless_completion);
+
+    // Raffles can be created again, and the admin's other instructions work on the new layout
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    assert_eq!(raffle.raffle_index, before.next_raffle_index);
+    let new_admin = new_wallet(&mut env.context);
+    let update = raffle_instruction::update_admin(&env.program_id, &admin.pubkey(), &new_admin.pubkey(), &env.config).unwrap();
+    process(&mut env.context, &[update], &[&admin]).await.unwrap();
+
+    // Migration runs once
+    assert_eq!(program_error(migrate_config(&mut env, &new_admin).await), ProgramError::InvalidAccountData);
+}
@@ -0,0 +1,153 @@
+//! Prizes are paid to the purchaser wallet behind a ticket record, never to the record itself
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+};
+use solcino::raffle_instruction;
+
+/// Two buyers with distinct records, returned as (wallet, record) pairs
+async fn two_buyers(env: &mut TestEnv, raffle: &Pubkey) -> [(Keypair, Keypair); 2] {
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(env, raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(env, raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    buyers
+}
+
+#[tokio::test]
+async fn completion_pays_the_winning_purchaser_not_the_record() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = two_buyers(&mut env, &raffle.raffle).await;
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let mut before = Vec::new();
+    for (wallet, record) in &buyers {
+        let wallet_lamports = lamports(&mut env.context, &wallet.pubkey()).await;
+        before.push((wallet_lamports, lamports(&mut env.context, &record.pubkey()).await));
+    }
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    for (position, (wallet, record)) in buyers.iter().enumerate() {
+        let prize = if record.pubkey() == winner { pool } else { 0 };
+        assert_eq!(lamports(&mut env.context, &wallet.pubkey()).await, before[position].0 + prize);
+        assert_eq!(lamports(&mut env.context, &record.pubkey()).await, before[position].1);
+    }
+}
+
+#[tokio::test]
+async fn completion_creates_a_winning_wallet_that_no_longer_exists() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyer = new_wallet(&mut env.context);
+    let record = Keypair::new();
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+
+    // The buyer closed their wallet after buying
+    env.context.set_account(&buyer.pubkey(), &AccountSharedData::default());
+    assert!(get_account(&mut env.context, &buyer.pubkey()).await.is_none());
+
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &[record.pubkey()]).await.unwrap();
+
+    assert_eq!(winner, record.pubkey());
+    assert_eq!(lamports(&mut env.context, &buyer.pubkey()).await, pool);
+}
+
+#[tokio::test]
+async fn completion_rejects_a_wallet_that_did_not_buy_the_winning_record() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = two_buyers(&mut env, &raffle.raffle).await;
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let keeper = new_wallet(&mut env.context);
+    let vrf = ready_to_complete(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let sorted = sorted_records(&mut env.context, &records).await;
+    let (winner, _) = sorted[drawn_position(&vrf, &sorted, &[], 0)];
+    let thief = Pubkey::new_unique();
+    let treasury = env.treasury;
+    let instruction =
+        complete_instruction(&mut env, &raffle.raffle, &vrf, &keeper, &winner, &thief, &treasury, &records).await;
+
+    let result = process(&mut env.context, &[instruction], &[&keeper]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+    assert_eq!(lamports(&mut env.context, &thief).await, 0);
+}
+
+#[tokio::test]
+async fn held_prizes_are_claimed_into_the_purchaser_wallet() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { claim_window: 3_600, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let buyers = two_buyers(&mut env, &raffle.raffle).await;
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    let (_, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+    let (wallet, record) = buyers.iter().find(|(_, record)| record.pubkey() == winner).unwrap();
+    let wallet_before = lamports(&mut env.context, &wallet.pubkey()).await;
+    let record_before = lamports(&mut env.context, &record.pubkey()).await;
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, pool);
+
+    let instruction = raffle_instruction::claim_prize(&env.program_id, &wallet.pubkey(), &raffle.raffle, &winner).unwrap();
+    process(&mut env.context, &[instruction], &[wallet]).await.unwrap();
+
+    assert_eq!(lamports(&mut env.context, &wallet.pubkey()).await, wallet_before + pool);
+    assert_eq!(lamports(&mut env.context, &record.pubkey()).await, record_before);
+    assert!(get_raffle(&mut env.context, &raffle.raffle).await.prize_claimed);
+}
+
+#[tokio::test]
+async fn forfeited_prizes_go_to_the_runner_up_purchaser_wallet() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { claim_window: 3_600, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let buyers = two_buyers(&mut env, &raffle.raffle).await;
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let pool = lamports(&mut env.context, &raffle.vault).await;
+    draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+    let runner_up = get_raffle(&mut env.context, &raffle.raffle).await.runner_up;
+    let (wallet, _) = buyers.iter().find(|(_, record)| record.pubkey() == runner_up).unwrap();
+    let wallet_before = lamports(&mut env.context, &wallet.pubkey()).await;
+    advance_clock(&mut env.context, 3_601).await;
+
+    let initiator = new_wallet(&mut env.context);
+    let instruction = raffle_instruction::forfeit_to_runner_up(
+        &env.program_id,
+        &initiator.pubkey(),
+        &raffle.raffle,
+        &runner_up,
+        &Pubkey::new_unique(),
+    )
+    .unwrap();
+    let result = process(&mut env.context, &[instruction], &[&initiator]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+
+    let instruction = raffle_instruction::forfeit_to_runner_up(
+        &env.program_id,
+        &initiator.pubkey(),
+        &raffle.raffle,
+        &runner_up,
+        &wallet.pubkey(),
+    )
+    .unwrap();
+    process(&mut env.context, &[instruction], &[&initiator]).await.unwrap();
+
+    assert_eq!(lamports(&mut env.context, &wallet.pubkey()).await, wallet_before + pool);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.winner, runner_up);
+}
@@ -0,0 +1,276 @@
+//! Ticket purchase accounting
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+};
+use solcino::{raffle_error::RaffleError, raffle_instruction, raffle_state::TicketPurchase, utils};
+
+#[tokio::test]
+async fn tickets_sold_adds_up_across_new_and_repeat_buyers() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers: Vec<(Keypair, Keypair)> = (0..3).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+
+    // The first buyer creates a record and then tops it up; the others create one each
+    let purchases = [(0, 2), (1, 1), (0, 3), (2, 4)];
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let mut expected_principal = 0;
+    for (buyer, ticket_count) in purchases {
+        let (wallet, record) = &buyers[buyer];
+        buy_tickets(&mut env, &raffle.raffle, wallet, record, ticket_count).await.unwrap();
+        let (principal, _) =
+            utils::split_fee(ticket_count * raffle_data.ticket_price, raffle_data.fee_basis_points).unwrap();
+        expected_principal += principal;
+    }
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.tickets_sold, 10);
+    assert_eq!(raffle_data.participant_count, 3);
+    assert_eq!(raffle_data.pool_principal, expected_principal);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, expected_principal);
+    for ((wallet, record), expected_count) in buyers.iter().zip([5, 1, 4]) {
+        let record_data = get_record(&mut env.context, &record.pubkey()).await;
+        assert!(record_data.is_initialized);
+        assert_eq!(record_data.raffle, raffle.raffle);
+        assert_eq!(record_data.purchaser, wallet.pubkey());
+        assert_eq!(record_data.ticket_count, expected_count);
+    }
+}
+
+#[tokio::test]
+async fn config_changes_do_not_reprice_a_live_raffle() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let snapshot = get_raffle(&mut env.context, &raffle.raffle).await;
+
+    let instructions = [
+        raffle_instruction::update_ticket_price(&env.program_id, &env.admin.pubkey(), &env.config, snapshot.ticket_price * 2)
+            .unwrap(),
+        raffle_instruction::update_fee_percentage(&env.program_id, &env.admin.pubkey(), &env.config, 2_500).unwrap(),
+    ];
+    process(&mut env.context, &instructions, &[&env.admin]).await.unwrap();
+    let config_data = get_config(&mut env.context, &env.program_id).await;
+    assert_eq!(config_data.ticket_price, snapshot.ticket_price * 2);
+    assert_eq!(config_data.fee_basis_points, 2_500);
+
+    let (wallet, record) = (new_wallet(&mut env.context), Keypair::new());
+    let treasury_before = lamports(&mut env.context, &snapshot.treasury).await;
+    buy_tickets(&mut env, &raffle.raffle, &wallet, &record, 4).await.unwrap();
+
+    // The purchase is charged at the price and fee captured when the raffle was created
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(raffle_data.ticket_price, snapshot.ticket_price);
+    assert_eq!(raffle_data.fee_basis_points, snapshot.fee_basis_points);
+    let (principal, fee) = utils::split_fee(4 * snapshot.ticket_price, snapshot.fee_basis_points).unwrap();
+    let record_rent = env.context.banks_client.get_rent().await.unwrap().minimum_balance(TicketPurchase::LEN);
+    assert_eq!(WALLET_LAMPORTS - lamports(&mut env.context, &wallet.pubkey()).await, 4 * snapshot.ticket_price + record_rent);
+    assert_eq!(lamports(&mut env.context, &snapshot.treasury).await - treasury_before, fee);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, principal);
+    assert_eq!(raffle_data.pool_principal, principal);
+}
+
+#[tokio::test]
+async fn a_buyer_left_without_a_rent_reserve_is_turned_away() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let total_price = 3 * raffle_data.ticket_price;
+
+    // Open the record up front so the buyer's balance only has to cover the tickets
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let record = Keypair::new();
+    let record_account =
+        AccountSharedData::new(rent.minimum_balance(TicketPurchase::LEN), TicketPurchase::LEN, &env.program_id);
+    env.context.set_account(&record.pubkey(), &record_account);
+
+    // Exactly the ticket price would leave the wallet below rent exemption
+    let buyer = Keypair::new();
+    fund(&mut env.context, &buyer.pubkey(), total_price);
+    let result = buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 3).await;
+    assert_eq!(program_error(result), ProgramError::InsufficientFunds);
+    assert_eq!(lamports(&mut env.context, &buyer.pubkey()).await, total_price);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.tickets_sold, 0);
+
+    // With the reserve on top the same purchase goes through, in a later block so it isn't
+    // taken for a replay of the rejected one
+    let reserve = rent.minimum_balance(0);
+    advance_clock(&mut env.context, 1).await;
+    fund(&mut env.context, &buyer.pubkey(), total_price + reserve);
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 3).await.unwrap();
+    assert_eq!(lamports(&mut env.context, &buyer.pubkey()).await, reserve);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.tickets_sold, 3);
+}
+
+/// PurchaseTickets into an existing `record` without the record keypair's signature
+async fn buy_unsigned(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    buyer: &Keypair,
+    record: &Pubkey,
+    ticket_count: u64,
+) -> Result<(), BanksClientError> {
+    let raffle_data = get_raffle(&mut env.context, raffle).await;
+    let mut instruction = raffle_instruction::purchase_tickets(
+        &env.program_id,
+        &buyer.pubkey(),
+        raffle,
+        record,
+        &raffle_data.treasury,
+        ticket_count,
+        raffle_data.ticket_price,
+        None,
+    )
+    .unwrap();
+    instruction.accounts[2].is_signer = false;
+    process(&mut env.context, &[instruction], &[buyer]).await
+}
+
+#[tokio::test]
+async fn a_record_is_only_opened_with_its_own_signature() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+
+    // An empty program-owned account someone else picked can't be turned into the buyer's record
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let record = Keypair::new();
+    let record_account =
+        AccountSharedData::new(rent.minimum_balance(TicketPurchase::LEN), TicketPurchase::LEN, &env.program_id);
+    env.context.set_account(&record.pubkey(), &record_account);
+    let buyer = new_wallet(&mut env.context);
+    let result = buy_unsigned(&mut env, &raffle.raffle, &buyer, &record.pubkey(), 2).await;
+    assert_eq!(program_error(result), ProgramError::MissingRequiredSignature);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.tickets_sold, 0);
+    let record_data = get_account(&mut env.context, &record.pubkey()).await.unwrap().data;
+    assert!(!TicketPurchase::unpack_unchecked(&record_data).unwrap().is_initialized);
+
+    // Once the record is open, topping it up doesn't need the record keypair again
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 2).await.unwrap();
+    buy_unsigned(&mut env, &raffle.raffle, &buyer, &record.pubkey(), 1).await.unwrap();
+    assert_eq!(get_record(&mut env.context, &record.pubkey()).await.ticket_count, 3);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.tickets_sold, 3);
+}
+
+#[tokio::test]
+async fn a_bogus_system_program_is_named_in_the_rejection() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 1).await.unwrap();
+
+    let bogus = Pubkey::new_unique();
+    let mut instruction = raffle_instruction::purchase_tickets(
+        &env.program_id,
+        &buyer.pubkey(),
+        &raffle.raffle,
+        &record.pubkey(),
+        &raffle_data.treasury,
+        2,
+        raffle_data.ticket_price,
+        None,
+    )
+    .unwrap();
+    instruction.accounts[4].pubkey = bogus;
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[&buyer, &record]).await;
+    assert_eq!(program_error(result), ProgramError::IncorrectProgramId);
+    let expected = format!("Expected the system program at account index 4, got {}", bogus);
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.tickets_sold, 1);
+}
+
+async fn buyer_spend(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    record: &Pubkey,
+    buyer: &Pubkey,
+) -> (Result<(), BanksClientError>, Vec<String>) {
+    let instruction = raffle_instruction::get_buyer_spend(&env.program_id, raffle, record, buyer).unwrap();
+    process_with_logs(&mut env.context, &[instruction], &[]).await
+}
+
+#[tokio::test]
+async fn buyer_spend_reports_tickets_spend_and_fees_after_buying() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 2).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+
+    let (wallet, record) = (buyers[0].0.pubkey(), buyers[0].1.pubkey());
+    let (result, logs) = buyer_spend(&mut env, &raffle.raffle, &record, &wallet).await;
+    result.unwrap();
+    let total_spent = 5 * raffle_data.ticket_price;
+    let fees_paid = utils::calculate_fee(total_spent, raffle_data.fee_basis_points).unwrap();
+    let expected = format!(
+        "BuyerSpend: raffle_index={}, buyer={}, ticket_count=5, total_spent={}, fees_paid={}",
+        raffle.raffle_index, wallet, total_spent, fees_paid
+    );
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+
+    // A buyer who never bought has no record yet and has spent nothing
+    let (wallet, record) = (buyers[1].0.pubkey(), buyers[1].1.pubkey());
+    let (result, logs) = buyer_spend(&mut env, &raffle.raffle, &record, &wallet).await;
+    result.unwrap();
+    let expected = format!(
+        "BuyerSpend: raffle_index={}, buyer={}, ticket_count=0, total_spent=0, fees_paid=0",
+        raffle.raffle_index, wallet
+    );
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+
+    // Someone else's record can't be reported as this buyer's spend
+    let (result, _) = buyer_spend(&mut env, &raffle.raffle, &buyers[0].1.pubkey(), &wallet).await;
+    assert_eq!(program_error(result), ProgramError::from(RaffleError::TicketPurchaseMismatch));
+}
+
+#[tokio::test]
+async fn simulate_purchase_previews_a_purchase_without_making_it() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let (buyer, record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &buyer, &record, 3).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let raffle_before = get_account(&mut env.context, &raffle.raffle).await.unwrap();
+    let vault_before = lamports(&mut env.context, &raffle.vault).await;
+    let treasury_before = lamports(&mut env.context, &raffle_data.treasury).await;
+
+    let instruction = raffle_instruction::simulate_purchase(&env.program_id, &raffle.raffle, 2).unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[]).await;
+    result.unwrap();
+    let total_price = 2 * raffle_data.ticket_price;
+    let (pool_amount, fee_amount) = utils::split_fee(total_price, raffle_data.fee_basis_points).unwrap();
+    let expected = format!(
+        "PurchasePreview: raffle_index={}, ticket_count=2, ticket_price={}, total_price={}, fee_amount={}, \
+         pool_amount={}, odds_bps=4000",
+        raffle.raffle_index, raffle_data.ticket_price, total_price, fee_amount, pool_amount
+    );
+    assert!(logged(&logs, &expected), "{:#?}", logs);
+
+    // Nothing moved and the raffle is byte for byte the same
+    assert_eq!(get_account(&mut env.context, &raffle.raffle).await.unwrap(), raffle_before);
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, vault_before);
+    assert_eq!(lamports(&mut env.context, &raffle_data.treasury).await, treasury_before);
+
+    // The real purchase splits exactly as previewed
+    let (second_buyer, second_record) = (new_wallet(&mut env.context), Keypair::new());
+    buy_tickets(&mut env, &raffle.raffle, &second_buyer, &second_record, 2).await.unwrap();
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await - vault_before, pool_amount);
+    assert_eq!(lamports(&mut env.context, &raffle_data.treasury).await - treasury_before, fee_amount);
+}
@@ -0,0 +1,70 @@
+//! ReconcileRaffle moves lamports sent straight to a raffle or its vault out of the prize pool
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_instruction};
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{
+    raffle_instruction,
+    raffle_state::{Raffle, RaffleStatus},
+};
+
+async fn reconcile(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    recipient: &Pubkey,
+    signer: &Keypair,
+) -> Result<(), BanksClientError> {
+    let instruction =
+        raffle_instruction::reconcile_raffle(&env.program_id, &signer.pubkey(), &env.config, raffle, recipient).unwrap();
+    process(&mut env.context, &[instruction], &[signer]).await
+}
+
+#[tokio::test]
+async fn an_over_funded_raffle_is_restored_to_its_pool() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let pool = get_raffle(&mut env.context, &raffle.raffle).await.pool_principal;
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, pool);
+
+    // Someone sends SOL straight to the vault and to the raffle account
+    let (to_vault, to_raffle) = (700_000_000, 300_000_000);
+    let sender = new_wallet(&mut env.context);
+    let transfers = [
+        system_instruction::transfer(&sender.pubkey(), &raffle.vault, to_vault),
+        system_instruction::transfer(&sender.pubkey(), &raffle.raffle, to_raffle),
+    ];
+    process(&mut env.context, &transfers, &[&sender]).await.unwrap();
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, pool + to_vault);
+
+    let recipient = Pubkey::new_unique();
+    let stranger = new_wallet(&mut env.context);
+    let result = reconcile(&mut env, &raffle.raffle, &recipient, &stranger).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+
+    let admin = Keypair::from_bytes(&env.admin.to_bytes()).unwrap();
+    reconcile(&mut env, &raffle.raffle, &recipient, &admin).await.unwrap();
+
+    // The pool is back to the buyers' principal and the raffle to its rent reserve
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, pool);
+    assert_eq!(lamports(&mut env.context, &raffle.raffle).await, rent.minimum_balance(Raffle::LEN));
+    assert_eq!(lamports(&mut env.context, &recipient).await, to_vault + to_raffle);
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.pool_principal, pool);
+
+    // The winner only gets what was bought
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+    draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert_eq!(completed.prize_amount, pool);
+}
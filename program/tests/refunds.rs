@@ -0,0 +1,69 @@
+//! RefundTickets pays a cancelled raffle's buyers back from the vault and closes their records
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_instruction, raffle_state::RaffleStatus, utils};
+
+async fn refund(env: &mut TestEnv, raffle: &Pubkey, buyer: &Pubkey, record: &Pubkey) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::refund_tickets(&env.program_id, buyer, raffle, record).unwrap();
+    process(&mut env.context, &[instruction], &[]).await
+}
+
+#[tokio::test]
+async fn refunds_return_principal_and_close_the_program_owned_record() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+
+    let raffle_data = get_raffle(&mut env.context, &raffle.raffle).await;
+    let cancel = raffle_instruction::cancel_raffle(
+        &env.program_id,
+        &authority.pubkey(),
+        &authority.pubkey(),
+        &raffle.raffle,
+        &raffle_data.treasury,
+    )
+    .unwrap();
+    process(&mut env.context, &[cancel], &[&authority]).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.status, RaffleStatus::Cancelled);
+
+    // The record is debited directly, which the program may do because it owns the record
+    let (wallet, record) = (buyers[0].0.pubkey(), buyers[0].1.pubkey());
+    let record_account = get_account(&mut env.context, &record).await.unwrap();
+    assert_eq!(record_account.owner, env.program_id);
+    let wallet_before = lamports(&mut env.context, &wallet).await;
+    refund(&mut env, &raffle.raffle, &wallet, &record).await.unwrap();
+
+    let (principal, _) = utils::split_fee(3 * raffle_data.ticket_price, raffle_data.fee_basis_points).unwrap();
+    assert_eq!(lamports(&mut env.context, &wallet).await, wallet_before + record_account.lamports + principal);
+    assert!(get_account(&mut env.context, &record).await.is_none());
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, raffle_data.pool_principal - principal);
+
+    // A closed record can't be refunded again
+    advance_clock(&mut env.context, 1).await;
+    let result = refund(&mut env, &raffle.raffle, &wallet, &record).await;
+    assert_eq!(program_error(result), ProgramError::IncorrectProgramId);
+
+    // The last refund empties the vault
+    let (wallet, record) = (buyers[1].0.pubkey(), buyers[1].1.pubkey());
+    let wallet_before = lamports(&mut env.context, &wallet).await;
+    let record_rent = lamports(&mut env.context, &record).await;
+    refund(&mut env, &raffle.raffle, &wallet, &record).await.unwrap();
+    assert_eq!(
+        lamports(&mut env.context, &wallet).await,
+        wallet_before + record_rent + raffle_data.pool_principal - principal
+    );
+    assert_eq!(lamports(&mut env.context, &raffle.vault).await, 0);
+    let refunded = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!((refunded.tickets_sold, refunded.pool_principal), (0, 0));
+}
@@ -0,0 +1,94 @@
+//! RetargetVrf moves a raffle whose randomness request stalled to a new oracle queue
+
+mod common;
+
+use common::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{
+    raffle_error::RaffleError,
+    raffle_instruction,
+    raffle_state::{RaffleStatus, VRF_REQUEST_TIMEOUT_SECONDS},
+};
+
+async fn retarget(
+    env: &mut TestEnv,
+    raffle: &Pubkey,
+    oracle_queue: &Pubkey,
+    signer: &Keypair,
+) -> Result<(), BanksClientError> {
+    let instruction =
+        raffle_instruction::retarget_vrf(&env.program_id, &signer.pubkey(), raffle, &env.config, oracle_queue).unwrap();
+    process(&mut env.context, &[instruction], &[signer]).await
+}
+
+#[tokio::test]
+async fn a_stalled_request_is_retargeted_to_a_new_queue_and_completes() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let buyers = [
+        (new_wallet(&mut env.context), Keypair::new()),
+        (new_wallet(&mut env.context), Keypair::new()),
+    ];
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let records = [buyers[0].1.pubkey(), buyers[1].1.pubkey()];
+
+    let keeper = new_wallet(&mut env.context);
+    end_sales(&mut env, &raffle.raffle, &keeper).await.unwrap();
+    let stale_vrf = Pubkey::new_unique();
+    request_randomness(&mut env, &raffle.raffle, &stale_vrf, &keeper).await.unwrap();
+    let stale_queue = env.oracle_queue;
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.oracle_queue, stale_queue);
+
+    // The old queue stops answering, so the admin allowlists a new one
+    let new_queue = Pubkey::new_unique();
+    let update =
+        raffle_instruction::update_oracle_queue(&env.program_id, &env.admin.pubkey(), &new_queue, &env.config).unwrap();
+    process(&mut env.context, &[update], &[&env.admin]).await.unwrap();
+    env.oracle_queue = new_queue;
+
+    // The request gets its full window first
+    let result = retarget(&mut env, &raffle.raffle, &new_queue, &authority).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    advance_clock(&mut env.context, VRF_REQUEST_TIMEOUT_SECONDS).await;
+
+    // A plain replacement request can't switch queues on its own
+    let requester = new_wallet(&mut env.context);
+    let result = request_randomness(&mut env, &raffle.raffle, &Pubkey::new_unique(), &requester).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+
+    let stranger = new_wallet(&mut env.context);
+    let result = retarget(&mut env, &raffle.raffle, &new_queue, &stranger).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+    let result = retarget(&mut env, &raffle.raffle, &Pubkey::new_unique(), &authority).await;
+    assert_eq!(program_error(result), ProgramError::InvalidArgument);
+
+    retarget(&mut env, &raffle.raffle, &new_queue, &authority).await.unwrap();
+    let retargeted = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(retargeted.status, RaffleStatus::ReadyForRandomness);
+    assert_eq!(retargeted.oracle_queue, new_queue);
+    assert_eq!(retargeted.vrf_account, Pubkey::default());
+    assert!(!retargeted.vrf_request_in_progress);
+
+    // With no request pending there is nothing left to retarget
+    let admin = Keypair::from_bytes(&env.admin.to_bytes()).unwrap();
+    let result = retarget(&mut env, &raffle.raffle, &new_queue, &admin).await;
+    assert_eq!(program_error(result), ProgramError::from(RaffleError::VrfNotRequested));
+
+    // The stalled request's VRF account can no longer complete the raffle
+    let result = complete_raffle(&mut env, &raffle.raffle, &stale_vrf, &keeper, &records).await;
+    assert_eq!(program_error(result.map(|_| ())), ProgramError::from(RaffleError::VrfNotRequested));
+
+    let vrf = Pubkey::new_unique();
+    request_randomness(&mut env, &raffle.raffle, &vrf, &requester).await.unwrap();
+    advance_clock(&mut env.context, 1).await;
+    complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await.unwrap();
+
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert_eq!(completed.vrf_account, vrf);
+    assert_eq!(completed.oracle_queue, new_queue);
+}
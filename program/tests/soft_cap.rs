@@ -0,0 +1,58 @@
+//! A soft cap lets the raffle authority close sales and draw before end_time
+
+mod common;
+
+use common::*;
+use solana_program::{clock::Clock, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_instruction, raffle_state::RaffleStatus};
+
+async fn prepare(env: &mut TestEnv, raffle: &TestRaffle, initiator: &Keypair) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::prepare_raffle(&env.program_id, &initiator.pubkey(), &raffle.raffle).unwrap();
+    process(&mut env.context, &[instruction], &[initiator]).await
+}
+
+#[tokio::test]
+async fn the_authority_prepares_early_once_the_soft_cap_is_reached() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { soft_cap_tickets: 5, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let buyers: Vec<(Keypair, Keypair)> = (0..3).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+
+    buy_tickets(&mut env, &raffle.raffle, &buyers[0].0, &buyers[0].1, 3).await.unwrap();
+    let early = new_wallet(&mut env.context);
+    assert_eq!(program_error(prepare(&mut env, &raffle, &early).await), ProgramError::InvalidArgument);
+
+    // Reaching the soft cap makes the raffle drawable, but sales stay open for everyone else
+    buy_tickets(&mut env, &raffle.raffle, &buyers[1].0, &buyers[1].1, 2).await.unwrap();
+    let stranger = new_wallet(&mut env.context);
+    assert_eq!(program_error(prepare(&mut env, &raffle, &stranger).await), ProgramError::InvalidAccountData);
+    buy_tickets(&mut env, &raffle.raffle, &buyers[2].0, &buyers[2].1, 1).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.status, RaffleStatus::Active);
+
+    prepare(&mut env, &raffle, &authority).await.unwrap();
+    let prepared = get_raffle(&mut env.context, &raffle.raffle).await;
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    assert!(clock.unix_timestamp < prepared.end_time);
+    assert_eq!(prepared.status, RaffleStatus::ReadyForRandomness);
+    assert_eq!(prepared.closing_tickets_sold, 6);
+
+    // Preparing closed sales
+    let (late_buyer, late_record) = (new_wallet(&mut env.context), Keypair::new());
+    let result = buy_tickets(&mut env, &raffle.raffle, &late_buyer, &late_record, 1).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+
+    // The draw completes before end_time
+    let keeper = new_wallet(&mut env.context);
+    let vrf = Pubkey::new_unique();
+    request_randomness(&mut env, &raffle.raffle, &vrf, &keeper).await.unwrap();
+    advance_clock(&mut env.context, 1).await;
+    let records: Vec<_> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+    complete_raffle(&mut env, &raffle.raffle, &vrf, &keeper, &records).await.unwrap();
+
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert!(completed.completed_at < completed.end_time);
+}
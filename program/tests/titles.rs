@@ -0,0 +1,73 @@
+//! Title rules are the same at creation and in UpdateRaffleTitle
+
+mod common;
+
+use common::*;
+use solana_program::program_error::ProgramError;
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_error::RaffleError, raffle_instruction, raffle_state::MAX_TITLE_LEN};
+
+fn title_of_len(len: usize) -> [u8; 32] {
+    let mut title = [0u8; 32];
+    title[..len].fill(b'a');
+    title
+}
+
+async fn update_title(
+    env: &mut TestEnv,
+    raffle: &TestRaffle,
+    authority: &Keypair,
+    title: [u8; 32],
+) -> Result<(), BanksClientError> {
+    let instruction = raffle_instruction::update_raffle_title(&env.program_id, &authority.pubkey(), &raffle.raffle, title).unwrap();
+    process(&mut env.context, &[instruction], &[authority]).await
+}
+
+#[tokio::test]
+async fn creation_accepts_a_title_at_the_limit_and_rejects_one_over_it() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+
+    let over = RaffleParams { title: title_of_len(MAX_TITLE_LEN + 1), ..raffle_params() };
+    let result = create_raffle(&mut env, &authority, over).await.map(|_| ());
+    assert_eq!(program_error(result), ProgramError::from(RaffleError::InvalidTitle));
+
+    let at_limit = RaffleParams { title: title_of_len(MAX_TITLE_LEN), ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, at_limit).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.title, title_of_len(MAX_TITLE_LEN));
+}
+
+#[tokio::test]
+async fn update_accepts_a_title_at_the_limit_and_rejects_one_over_it() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+    let original = get_raffle(&mut env.context, &raffle.raffle).await.title;
+
+    let result = update_title(&mut env, &raffle, &authority, title_of_len(MAX_TITLE_LEN + 1)).await;
+    assert_eq!(program_error(result), ProgramError::from(RaffleError::InvalidTitle));
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.title, original);
+
+    // Bytes after the first null are dropped, as at creation
+    let mut padded = title_of_len(MAX_TITLE_LEN);
+    padded[MAX_TITLE_LEN + 2] = b'x';
+    update_title(&mut env, &raffle, &authority, padded).await.unwrap();
+    assert_eq!(get_raffle(&mut env.context, &raffle.raffle).await.title, title_of_len(MAX_TITLE_LEN));
+}
+
+#[tokio::test]
+async fn update_rejects_invalid_utf8_and_other_signers() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let raffle = create_raffle(&mut env, &authority, raffle_params()).await.unwrap();
+
+    let mut invalid = title_of_len(4);
+    invalid[1] = 0xff;
+    let result = update_title(&mut env, &raffle, &authority, invalid).await;
+    assert_eq!(program_error(result), ProgramError::from(RaffleError::InvalidTitle));
+
+    let stranger = new_wallet(&mut env.context);
+    let result = update_title(&mut env, &raffle, &stranger, title_of_len(8)).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+}
@@ -0,0 +1,58 @@
+//! GetWinnerInfo logs every prize tier of a completed raffle
+
+mod common;
+
+use common::*;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solcino::{raffle_instruction, raffle_state::RaffleStatus};
+
+async fn winner_info_logs(env: &mut TestEnv, raffle: &Pubkey) -> Vec<String> {
+    let instruction = raffle_instruction::get_winner_info(&env.program_id, raffle).unwrap();
+    let (result, logs) = process_with_logs(&mut env.context, &[instruction], &[]).await;
+    result.unwrap();
+    logs
+}
+
+#[tokio::test]
+async fn a_completed_raffle_reports_its_winner_runner_up_and_consolation() {
+    let mut env = start().await;
+    let authority = new_wallet(&mut env.context);
+    let params = RaffleParams { consolation_bps: 5_000, ..raffle_params() };
+    let raffle = create_raffle(&mut env, &authority, params).await.unwrap();
+    let buyers: Vec<(Keypair, Keypair)> = (0..3).map(|_| (new_wallet(&mut env.context), Keypair::new())).collect();
+    for ((wallet, record), ticket_count) in buyers.iter().zip([3, 2, 4]) {
+        buy_tickets(&mut env, &raffle.raffle, wallet, record, ticket_count).await.unwrap();
+    }
+    let records: Vec<Pubkey> = buyers.iter().map(|(_, record)| record.pubkey()).collect();
+
+    // Before completion only the status is reported
+    let logs = winner_info_logs(&mut env, &raffle.raffle).await;
+    let expected = format!("WinnerInfo: raffle={}, index={}, status=Active", raffle.raffle, raffle.raffle_index);
+    assert!(logs.iter().any(|line| line.ends_with(&expected)), "{:#?}", logs);
+
+    let consolation_pool = get_raffle(&mut env.context, &raffle.raffle).await.consolation_pool;
+    let (vrf, winner) = draw_raffle(&mut env, &raffle.raffle, &records).await.unwrap();
+
+    let completed = get_raffle(&mut env.context, &raffle.raffle).await;
+    assert_eq!(completed.status, RaffleStatus::Complete);
+    assert_ne!(completed.runner_up, Pubkey::default());
+    assert_ne!(completed.consolation_winner, Pubkey::default());
+    assert_eq!(completed.consolation_paid, consolation_pool);
+
+    let logs = winner_info_logs(&mut env, &raffle.raffle).await;
+    let expected = format!(
+        "WinnerInfo: raffle={}, index={}, status=Complete, winner={}, prize_amount={}, completed_at={}, vrf_account={}, \
+         runner_up={}, consolation_winner={}, consolation_paid={}, consolation_pool=0",
+        raffle.raffle,
+        raffle.raffle_index,
+        winner,
+        completed.prize_amount,
+        completed.completed_at,
+        vrf,
+        completed.runner_up,
+        completed.consolation_winner,
+        consolation_pool
+    );
+    assert!(logs.iter().any(|line| line.ends_with(&expected)), "{:#?}", logs);
+}